@@ -0,0 +1,424 @@
+use std::collections::HashSet;
+
+use amber_ast::{
+    EnumDef, Expression, ExpressionKind, Function, ImplBlock, Literal, Param, Prefix, Span,
+    StructDef, Type, UnaryOp, UnionDef, VariantPayload, source_span,
+};
+
+use crate::checker::{Checker, FunctionContext, payload_fields};
+use crate::error::AnalysisError;
+use crate::scope::VarInfo;
+use crate::typed::{
+    TypedBlock, TypedElseBranch, TypedEnum, TypedExprKind, TypedFunction, TypedIfElse, TypedImpl,
+    TypedStatement, TypedUnion, TypedUnionVariant,
+};
+
+impl Checker {
+    pub(crate) fn check_struct(&mut self, def: &StructDef) -> Result<StructDef, AnalysisError> {
+        let mut seen = HashSet::new();
+        for field in &def.fields {
+            if !seen.insert(field.name.as_str()) {
+                return Err(AnalysisError::DuplicateField {
+                    struct_name: def.name.clone(),
+                    field: field.name.clone(),
+                    span: source_span(field.span),
+                });
+            }
+            self.resolve_type(&field.ty, field.span)?;
+        }
+        for field in &def.fields {
+            if self.holds_by_value(&field.ty, &def.name, &mut HashSet::new()) {
+                return Err(AnalysisError::RecursiveStruct {
+                    name: def.name.clone(),
+                    span: source_span(field.span),
+                    field: field.name.clone(),
+                });
+            }
+        }
+        Ok(def.clone())
+    }
+
+    /// Whether a value of type `ty` has a `name` inside it, directly or
+    /// through array elements, struct fields and union payloads but not
+    /// through pointers
+    fn holds_by_value(&self, ty: &Type, name: &str, visited: &mut HashSet<String>) -> bool {
+        match ty {
+            Type::Array { inner, .. } => self.holds_by_value(inner, name, visited),
+            Type::Named(named) if named == name => true,
+            Type::Named(named) if visited.insert(named.clone()) => {
+                if let Some(def) = self.structs.get(named) {
+                    def.fields
+                        .iter()
+                        .any(|field| self.holds_by_value(&field.ty, name, visited))
+                } else if let Some(def) = self.unions.get(named) {
+                    def.variants.iter().any(|variant| {
+                        payload_fields(variant)
+                            .iter()
+                            .any(|(_, ty)| self.holds_by_value(ty, name, visited))
+                    })
+                } else {
+                    false
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Resolve every discriminant of an enum. Variants without an explicit
+    /// value continue from the previous one, starting at 0 like C.
+    pub(crate) fn check_enum(&mut self, def: &EnumDef) -> Result<TypedEnum, AnalysisError> {
+        // Without an underlying type the enum is a C `enum`, whose
+        // constants are `int`
+        let repr = def.repr.clone().unwrap_or(Type::I32);
+        let (min, max) = repr_range(&def.name, &repr, def.span)?;
+
+        let mut variants: Vec<(String, i64)> = Vec::new();
+        let mut next = 0;
+        for variant in &def.variants {
+            let qualified = format!("{}::{}", def.name, variant.name);
+            if variants.iter().any(|(name, _)| *name == variant.name) {
+                return Err(AnalysisError::DuplicateVariant {
+                    enum_name: def.name.clone(),
+                    variant: variant.name.clone(),
+                    span: source_span(variant.span),
+                });
+            }
+            let value = match &variant.value {
+                Some(expr) => {
+                    discriminant(expr).ok_or_else(|| AnalysisError::UnresolvedDiscriminant {
+                        variant: qualified.clone(),
+                        span: source_span(expr.span),
+                    })?
+                }
+                None => next,
+            };
+            if value < min || value > max {
+                return Err(AnalysisError::DiscriminantOutOfRange {
+                    variant: qualified,
+                    value,
+                    repr,
+                    span: source_span(variant.span),
+                });
+            }
+            let value = value as i64;
+            if let Some((other, _)) = variants.iter().find(|(_, other)| *other == value) {
+                return Err(AnalysisError::DuplicateDiscriminant {
+                    variant: qualified,
+                    other: format!("{}::{}", def.name, other),
+                    value,
+                    span: source_span(variant.span),
+                });
+            }
+            variants.push((variant.name.clone(), value));
+            next = i128::from(value) + 1;
+        }
+
+        Ok(TypedEnum {
+            name: def.name.clone(),
+            repr: def.repr.clone(),
+            variants,
+        })
+    }
+
+    /// Check a tagged union. Tags count up from 0 in declaration order and,
+    /// like enum discriminants, default to C's `int`.
+    pub(crate) fn check_union(&mut self, def: &UnionDef) -> Result<TypedUnion, AnalysisError> {
+        let repr = def.repr.clone().unwrap_or(Type::I32);
+        let (_, max) = repr_range(&def.name, &repr, def.span)?;
+
+        let mut variants: Vec<TypedUnionVariant> = Vec::new();
+        for (tag, variant) in def.variants.iter().enumerate() {
+            let qualified = format!("{}::{}", def.name, variant.name);
+            if variants.iter().any(|other| other.name == variant.name) {
+                return Err(AnalysisError::DuplicateVariant {
+                    enum_name: def.name.clone(),
+                    variant: variant.name.clone(),
+                    span: source_span(variant.span),
+                });
+            }
+            if tag as i128 > max {
+                return Err(AnalysisError::DiscriminantOutOfRange {
+                    variant: qualified,
+                    value: tag as i128,
+                    repr,
+                    span: source_span(variant.span),
+                });
+            }
+            match &variant.payload {
+                VariantPayload::Unit => {}
+                VariantPayload::Tuple(types) => {
+                    for ty in types {
+                        self.resolve_type(ty, variant.span)?;
+                    }
+                }
+                VariantPayload::Struct(fields) => {
+                    let mut seen = HashSet::new();
+                    for field in fields {
+                        if !seen.insert(field.name.as_str()) {
+                            return Err(AnalysisError::DuplicateField {
+                                struct_name: qualified,
+                                field: field.name.clone(),
+                                span: source_span(field.span),
+                            });
+                        }
+                        self.resolve_type(&field.ty, field.span)?;
+                    }
+                }
+            }
+            variants.push(TypedUnionVariant {
+                name: variant.name.clone(),
+                tag: tag as i64,
+                fields: payload_fields(variant),
+            });
+        }
+
+        Ok(TypedUnion {
+            name: def.name.clone(),
+            repr: def.repr.clone(),
+            variants,
+        })
+    }
+
+    pub(crate) fn check_impl(&mut self, block: &ImplBlock) -> Result<TypedImpl, AnalysisError> {
+        if !self.structs.contains_key(&block.target) {
+            return Err(AnalysisError::UnknownType {
+                name: block.target.clone(),
+                span: source_span(block.span),
+            });
+        }
+
+        let mut names = HashSet::new();
+        let mut methods = Vec::new();
+        for method in &block.methods {
+            if method.is_extern {
+                return Err(AnalysisError::ExternImplMethod {
+                    target: block.target.clone(),
+                    name: method.name.clone(),
+                    span: source_span(method.span),
+                });
+            }
+            if !names.insert(method.name.as_str()) {
+                return Err(AnalysisError::DuplicateDefinition {
+                    name: format!("{}::{}", block.target, method.name),
+                    span: source_span(method.span),
+                });
+            }
+            methods.push(self.check_function(method, Some(&block.target))?);
+        }
+
+        Ok(TypedImpl {
+            target: block.target.clone(),
+            methods,
+        })
+    }
+
+    /// Check a free function (`impl_target` is `None`) or an impl method
+    pub(crate) fn check_function(
+        &mut self,
+        func: &Function,
+        impl_target: Option<&str>,
+    ) -> Result<TypedFunction, AnalysisError> {
+        let span = source_span(func.span);
+        match (&func.body, func.is_extern) {
+            (Some(_), true) => {
+                return Err(AnalysisError::ExternFunctionWithBody {
+                    name: func.name.clone(),
+                    span,
+                });
+            }
+            (None, false) => {
+                return Err(AnalysisError::MissingFunctionBody {
+                    name: func.name.clone(),
+                    span,
+                });
+            }
+            _ => {}
+        }
+
+        let mut params = Vec::new();
+        let mut has_self = false;
+        for param in &func.params {
+            match param {
+                Param::SelfParam { is_mut } => {
+                    let Some(target) = impl_target else {
+                        return Err(AnalysisError::SelfParamOutsideImpl {
+                            name: func.name.clone(),
+                            span,
+                        });
+                    };
+                    if has_self {
+                        return Err(AnalysisError::MultipleSelfParams {
+                            name: func.name.clone(),
+                            span,
+                        });
+                    }
+                    has_self = true;
+                    params.push(("self".to_string(), self_type(target, *is_mut)));
+                }
+                Param::Typed { name, ty } => {
+                    self.resolve_type(ty, func.span)?;
+                    reject_array(ty, func)?;
+                    params.push((name.clone(), ty.clone()));
+                }
+            }
+        }
+        if let Some(ty) = &func.return_type {
+            self.resolve_type(ty, func.span)?;
+            reject_array(ty, func)?;
+        }
+
+        let errors = self.errors.len();
+        let body = func.body.as_ref().map(|body| {
+            self.scope.push();
+            for (name, ty) in params {
+                self.scope.define(
+                    &name,
+                    VarInfo {
+                        ty,
+                        is_mutable: false,
+                        inferred: None,
+                    },
+                );
+            }
+            self.function = Some(FunctionContext {
+                name: func.name.clone(),
+                return_type: func.return_type.clone().unwrap_or(Type::Void),
+            });
+            let body = self.check_block(body);
+            self.function = None;
+            self.scope.pop();
+            body
+        });
+        // Statements that failed to check are missing from the typed body,
+        // so only a body without errors can be trusted to fall through
+        if let (Some(return_type), Some(body)) = (&func.return_type, &body)
+            && *return_type != Type::Void
+            && self.errors.len() == errors
+            && !always_returns(body)
+        {
+            return Err(AnalysisError::MissingReturn {
+                name: func.name.clone(),
+                span,
+                return_type: return_type.clone(),
+            });
+        }
+
+        Ok(TypedFunction {
+            name: func.name.clone(),
+            params: func.params.clone(),
+            return_type: func.return_type.clone(),
+            body,
+            is_extern: func.is_extern,
+            span: func.span,
+        })
+    }
+}
+
+/// Whether every path through `block` ends in a `return` or an endless
+/// `while true` loop
+fn always_returns(block: &TypedBlock) -> bool {
+    block.statements.iter().any(|statement| match statement {
+        TypedStatement::Return(_) => true,
+        TypedStatement::Block(block) => always_returns(block),
+        TypedStatement::IfElse(if_else) => if_else_returns(if_else),
+        TypedStatement::Match(match_stmt) => {
+            match_stmt.arms.iter().all(|arm| always_returns(&arm.body))
+        }
+        TypedStatement::WhileLoop(while_loop) => {
+            matches!(
+                while_loop.condition.kind,
+                TypedExprKind::Literal(Literal::Bool(true))
+            ) && !breaks_out(&while_loop.block, while_loop.exits.id, false)
+        }
+        _ => false,
+    })
+}
+
+fn if_else_returns(if_else: &TypedIfElse) -> bool {
+    always_returns(&if_else.then_block)
+        && match &if_else.else_branch {
+            Some(TypedElseBranch::If(next)) => if_else_returns(next),
+            Some(TypedElseBranch::Block(block)) => always_returns(block),
+            None => false,
+        }
+}
+
+/// Whether some `break` in `block` leaves loop `id`. A plain `break` inside
+/// a nested loop leaves that loop instead.
+fn breaks_out(block: &TypedBlock, id: usize, nested: bool) -> bool {
+    block.statements.iter().any(|statement| match statement {
+        TypedStatement::Break(None) => !nested,
+        TypedStatement::Break(Some(target)) => *target == id,
+        TypedStatement::Block(block) => breaks_out(block, id, nested),
+        TypedStatement::IfElse(if_else) => {
+            let mut if_else = Some(if_else);
+            let mut found = false;
+            while let Some(current) = if_else {
+                found |= breaks_out(&current.then_block, id, nested);
+                if_else = match &current.else_branch {
+                    Some(TypedElseBranch::If(next)) => Some(next),
+                    Some(TypedElseBranch::Block(block)) => {
+                        found |= breaks_out(block, id, nested);
+                        None
+                    }
+                    None => None,
+                };
+            }
+            found
+        }
+        TypedStatement::Match(match_stmt) => match_stmt
+            .arms
+            .iter()
+            .any(|arm| breaks_out(&arm.body, id, nested)),
+        TypedStatement::WhileLoop(inner) => breaks_out(&inner.block, id, true),
+        TypedStatement::ForLoop(inner) => breaks_out(&inner.block, id, true),
+        _ => false,
+    })
+}
+
+/// Range of values an enum or union tag can take with the given
+/// underlying type
+fn repr_range(name: &str, repr: &Type, span: Span) -> Result<(i128, i128), AnalysisError> {
+    let (min, max) = repr
+        .integer_range()
+        .ok_or_else(|| AnalysisError::InvalidEnumRepr {
+            name: name.to_string(),
+            found: repr.clone(),
+            span: source_span(span),
+        })?;
+    Ok((min, max.min(i64::MAX as i128)))
+}
+
+/// Value of a discriminant written as an integer literal, possibly negated.
+/// Other expressions are evaluated into literals by the comptime pass.
+fn discriminant(expr: &Expression) -> Option<i128> {
+    match &expr.kind {
+        ExpressionKind::Literal(lit) => lit.as_integer(),
+        ExpressionKind::UnaryExpr {
+            op: UnaryOp::PrefixOp(Prefix::Neg),
+            expr,
+        } => discriminant(expr).map(|value| -value),
+        _ => None,
+    }
+}
+
+/// C passes arrays as pointers and cannot return them, so functions only
+/// accept arrays behind an explicit pointer
+fn reject_array(ty: &Type, func: &Function) -> Result<(), AnalysisError> {
+    match ty {
+        Type::Array { .. } => Err(AnalysisError::ArrayByValue {
+            function: func.name.clone(),
+            span: source_span(func.span),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Type of `self` inside methods of `target`, matching the `Target* self`
+/// (or `const Target* self`) parameter emitted in C
+fn self_type(target: &str, is_mut: bool) -> Type {
+    Type::Pointer {
+        inner: Box::new(Type::Named(target.to_string())),
+        is_mut,
+    }
+}
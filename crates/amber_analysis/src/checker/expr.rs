@@ -0,0 +1,1101 @@
+use amber_ast::{
+    BinaryOp, Expression, ExpressionKind, FieldInit, Literal, NumericLiteral, Postfix, Prefix,
+    Span, Type, UnaryOp, UnionVariant, source_span,
+};
+
+use crate::checker::{Checker, Signature, expect_assignable, payload_fields, payload_shape};
+use crate::error::AnalysisError;
+use crate::typed::{Callee, TypedExpr, TypedExprKind};
+
+impl Checker {
+    /// Type-check an expression. `hint` is the type the surrounding context
+    /// expects; untyped literals adopt it, everything else ignores it.
+    pub(crate) fn check_expr(
+        &mut self,
+        expr: &Expression,
+        hint: Option<&Type>,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let span = expr.span;
+        match &expr.kind {
+            ExpressionKind::Literal(lit @ (Literal::String(value) | Literal::CString(value))) => {
+                let ty = self.check_literal(lit, hint, span).ty;
+                Ok(self.check_string(value, ty, span))
+            }
+            ExpressionKind::Literal(lit) => {
+                let literal = self.check_literal(lit, hint, span);
+                if let Some(value) = lit.as_integer() {
+                    check_int_range(value, &literal.ty, span)?;
+                }
+                Ok(literal)
+            }
+            ExpressionKind::Identifier(name) => {
+                let info = self.scope.lookup_mut(name).ok_or_else(|| {
+                    AnalysisError::UndefinedIdentifier {
+                        name: name.clone(),
+                        span: source_span(span),
+                    }
+                })?;
+                // A binding declared without a type or value gets both from
+                // its first assignment, so reading it earlier is an error.
+                // Later uses are checked against the expected type, or else
+                // the default integer type.
+                if let Some(id) = info.inferred
+                    && self.inference.unassigned.remove(&id).is_some()
+                {
+                    info.inferred = None;
+                    info.ty = hint
+                        .cloned()
+                        .unwrap_or_else(|| self.inference.default_int.clone());
+                    self.inference.types[id] = info.ty.clone();
+                    return Err(AnalysisError::UsedBeforeAssignment {
+                        name: name.clone(),
+                        span: source_span(span),
+                    });
+                }
+                // The first use of a binding whose type is still open settles
+                // it: to the expected type if its literal can take that type,
+                // otherwise to the literal's own type
+                if let Some(id) = info.inferred.take()
+                    && let Some(hint) = hint
+                    && accepts_type(&info.ty, hint)
+                {
+                    info.ty = hint.clone();
+                    self.inference.types[id] = hint.clone();
+                }
+                Ok(TypedExpr::new(
+                    TypedExprKind::Identifier(name.clone()),
+                    info.ty.clone(),
+                    span,
+                ))
+            }
+            ExpressionKind::Path { target, name } => self.check_path(target, name, span),
+            ExpressionKind::UnaryExpr { op, expr } => match op {
+                UnaryOp::PrefixOp(prefix) => self.check_prefix(prefix, expr, hint, span),
+                UnaryOp::PostfixOp(Postfix::Index { index }) => self.check_index(expr, index, span),
+            },
+            ExpressionKind::BinaryExpr { left, op, right } => {
+                self.check_binary(left, op, right, hint, span)
+            }
+            ExpressionKind::TernaryExpr {
+                condition,
+                then_expr,
+                else_expr,
+            } => {
+                let condition = self.check_condition(condition)?;
+                let (then_expr, else_expr) = self.check_operands(then_expr, else_expr, hint)?;
+                expect_assignable(&else_expr.ty, &then_expr.ty, else_expr.span)?;
+                let ty = then_expr.ty.clone();
+                Ok(TypedExpr::new(
+                    TypedExprKind::Ternary {
+                        condition: Box::new(condition),
+                        then_expr: Box::new(then_expr),
+                        else_expr: Box::new(else_expr),
+                    },
+                    ty,
+                    span,
+                ))
+            }
+            ExpressionKind::Call { callee, args } => self.check_call(callee, args, span),
+            ExpressionKind::MethodCall {
+                receiver,
+                method,
+                args,
+            } => self.check_method_call(receiver, method, args, span),
+            ExpressionKind::ArrayLiteral(elements) => {
+                self.check_array_literal(elements, hint, span)
+            }
+            ExpressionKind::FieldAccess { base, field } => self.check_field(base, field, span),
+            ExpressionKind::StructLiteral { name, fields } => {
+                self.check_struct_literal(name, fields, span)
+            }
+            ExpressionKind::VariantLiteral {
+                target,
+                variant,
+                fields,
+            } => self.check_variant_literal(target, variant, fields, span),
+        }
+    }
+
+    /// Check `Target::name` used as a value, which must be an enum variant or
+    /// a union variant without payload. Associated functions can only be
+    /// called for now.
+    pub(crate) fn check_path(
+        &self,
+        target: &str,
+        name: &str,
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        if self.unions.contains_key(target) {
+            let variant = self.union_variant(target, name, span)?;
+            expect_shape(target, &variant, "unit", span)?;
+            return Ok(union_literal(target, name, Vec::new(), span));
+        }
+        let Some(def) = self.enums.get(target) else {
+            return Err(AnalysisError::UndefinedIdentifier {
+                name: format!("{}::{}", target, name),
+                span: source_span(span),
+            });
+        };
+        if !def.variants.iter().any(|variant| variant.name == name) {
+            return Err(AnalysisError::UnknownVariant {
+                enum_name: target.to_string(),
+                variant: name.to_string(),
+                span: source_span(span),
+            });
+        }
+        Ok(TypedExpr::new(
+            TypedExprKind::EnumVariant {
+                enum_name: target.to_string(),
+                variant: name.to_string(),
+            },
+            Type::Named(target.to_string()),
+            span,
+        ))
+    }
+
+    /// Check an expression whose value is used, rejecting calls to functions
+    /// that do not return anything and arrays other than array literals,
+    /// which C cannot copy
+    pub(crate) fn check_value(
+        &mut self,
+        expr: &Expression,
+        hint: Option<&Type>,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let value = self.check_expr(expr, hint)?;
+        match &value.kind {
+            TypedExprKind::Call { callee, .. } if value.ty == Type::Void => {
+                Err(AnalysisError::VoidValue {
+                    function: callee_name(callee),
+                    span: source_span(value.span),
+                })
+            }
+            TypedExprKind::ArrayLiteral(_) => Ok(value),
+            _ if matches!(value.ty, Type::Array { .. }) => Err(AnalysisError::ArrayCopy {
+                span: source_span(value.span),
+            }),
+            _ => Ok(value),
+        }
+    }
+
+    /// Check an expression used as an `if`/`while`/ternary condition
+    pub(crate) fn check_condition(
+        &mut self,
+        expr: &Expression,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let condition = self.check_expr(expr, Some(&Type::Bool))?;
+        expect_assignable(&condition.ty, &Type::Bool, condition.span)?;
+        Ok(condition)
+    }
+
+    /// Ensure an expression denotes a writable location
+    pub(crate) fn check_place(&self, expr: &TypedExpr) -> Result<(), AnalysisError> {
+        match &expr.kind {
+            TypedExprKind::Identifier(name) => match self.scope.lookup(name) {
+                Some(info) if info.is_mutable => Ok(()),
+                _ => Err(AnalysisError::AssignToImmutable {
+                    name: name.clone(),
+                    span: source_span(expr.span),
+                }),
+            },
+            TypedExprKind::Prefix {
+                op: Prefix::Deref,
+                expr: pointer,
+            } => expect_mut_pointer(pointer),
+            TypedExprKind::Index { base, .. } => match base.ty {
+                Type::Array { .. } => self.check_place(base),
+                _ => expect_mut_pointer(base),
+            },
+            TypedExprKind::Field {
+                base,
+                through_pointer,
+                ..
+            } => {
+                if *through_pointer {
+                    expect_mut_pointer(base)
+                } else {
+                    self.check_place(base)
+                }
+            }
+            _ => Err(AnalysisError::InvalidAssignmentTarget {
+                span: source_span(expr.span),
+            }),
+        }
+    }
+
+    fn check_prefix(
+        &mut self,
+        op: &Prefix,
+        operand: &Expression,
+        hint: Option<&Type>,
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        // `-128` fits in i8 although `128` does not, so the range of a
+        // negated literal is checked as a whole
+        if *op == Prefix::Neg
+            && let ExpressionKind::Literal(lit) = &operand.kind
+            && let Some(value) = lit.as_integer()
+        {
+            let literal = self.check_literal(lit, hint, operand.span);
+            if literal.ty.is_numeric() {
+                check_int_range(-value, &literal.ty, span)?;
+                let ty = literal.ty.clone();
+                return Ok(TypedExpr::new(
+                    TypedExprKind::Prefix {
+                        op: op.clone(),
+                        expr: Box::new(literal),
+                    },
+                    ty,
+                    span,
+                ));
+            }
+        }
+
+        let operand = match op {
+            Prefix::AddrOf | Prefix::AddrOfMut => {
+                let operand = self.check_expr(operand, None)?;
+                return self.take_address(operand, *op == Prefix::AddrOfMut);
+            }
+            Prefix::Not => self.check_expr(operand, Some(&Type::Bool))?,
+            Prefix::Deref | Prefix::PreInc | Prefix::PreDec => self.check_expr(operand, None)?,
+            Prefix::Neg | Prefix::Pos | Prefix::BitNot => self.check_expr(operand, hint)?,
+        };
+
+        let ty = match op {
+            Prefix::Neg | Prefix::Pos if operand.ty.is_numeric() => operand.ty.clone(),
+            Prefix::Not if operand.ty == Type::Bool => Type::Bool,
+            Prefix::BitNot if operand.ty.is_integer() => operand.ty.clone(),
+            Prefix::PreInc | Prefix::PreDec
+                if operand.ty.is_integer() || operand.ty.is_pointer() =>
+            {
+                self.check_place(&operand)?;
+                operand.ty.clone()
+            }
+            Prefix::Deref => match &operand.ty {
+                Type::Pointer { inner, .. } => (**inner).clone(),
+                _ => return Err(invalid_unary(op, &operand.ty, span)),
+            },
+            Prefix::AddrOf | Prefix::AddrOfMut => unreachable!("handled above"),
+            _ => return Err(invalid_unary(op, &operand.ty, span)),
+        };
+
+        Ok(TypedExpr::new(
+            TypedExprKind::Prefix {
+                op: op.clone(),
+                expr: Box::new(operand),
+            },
+            ty,
+            span,
+        ))
+    }
+
+    /// Take the address of a place. A mutable address requires the place to
+    /// be writable.
+    fn take_address(&self, operand: TypedExpr, is_mut: bool) -> Result<TypedExpr, AnalysisError> {
+        if !is_addressable(&operand) {
+            return Err(AnalysisError::AddressOfTemporary {
+                span: source_span(operand.span),
+            });
+        }
+        if is_mut {
+            self.check_place(&operand).map_err(|err| match err {
+                AnalysisError::AssignToImmutable { name, span } => {
+                    AnalysisError::MutableAddressOfImmutable { name, span }
+                }
+                other => other,
+            })?;
+        }
+        let ty = Type::Pointer {
+            inner: Box::new(operand.ty.clone()),
+            is_mut,
+        };
+        let span = operand.span;
+        Ok(TypedExpr::new(
+            TypedExprKind::AddressOf {
+                expr: Box::new(operand),
+                is_mut,
+            },
+            ty,
+            span,
+        ))
+    }
+
+    fn check_index(
+        &mut self,
+        base: &Expression,
+        index: &Expression,
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let base = self.check_expr(base, None)?;
+        let index = self.check_expr(index, None)?;
+        if !index.ty.is_integer() {
+            return Err(AnalysisError::NonIntegerIndex {
+                found: index.ty,
+                span: source_span(index.span),
+            });
+        }
+        if let (Type::Array { len, .. }, Some(value)) = (&base.ty, constant_index(&index))
+            && usize::try_from(value).map_or(true, |value| value >= *len)
+        {
+            return Err(AnalysisError::IndexOutOfBounds {
+                index: value,
+                len: *len,
+                span: source_span(index.span),
+            });
+        }
+        let ty = match &base.ty {
+            Type::Array { inner, .. } | Type::Pointer { inner, .. } => (**inner).clone(),
+            other => {
+                return Err(AnalysisError::NotIndexable {
+                    found: other.clone(),
+                    span: source_span(base.span),
+                });
+            }
+        };
+        Ok(TypedExpr::new(
+            TypedExprKind::Index {
+                base: Box::new(base),
+                index: Box::new(index),
+            },
+            ty,
+            span,
+        ))
+    }
+
+    /// Check `[a, b, ...]`. Elements take the element type of an array hint;
+    /// without one, the first element decides it.
+    fn check_array_literal(
+        &mut self,
+        elements: &[Expression],
+        hint: Option<&Type>,
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let (first, rest) =
+            elements
+                .split_first()
+                .ok_or_else(|| AnalysisError::EmptyArrayLiteral {
+                    span: source_span(span),
+                })?;
+        let hint = match hint {
+            Some(Type::Array { inner, len }) => {
+                if elements.len() != *len {
+                    return Err(AnalysisError::ArrayLengthMismatch {
+                        expected: *len,
+                        found: elements.len(),
+                        span: source_span(span),
+                    });
+                }
+                Some(&**inner)
+            }
+            _ => None,
+        };
+
+        let first = self.check_value(first, hint)?;
+        let inner = hint.cloned().unwrap_or_else(|| first.ty.clone());
+        expect_assignable(&first.ty, &inner, first.span)?;
+        let mut typed = vec![first];
+        for element in rest {
+            let element = self.check_value(element, Some(&inner))?;
+            expect_assignable(&element.ty, &inner, element.span)?;
+            typed.push(element);
+        }
+
+        let ty = Type::Array {
+            inner: Box::new(inner),
+            len: typed.len(),
+        };
+        Ok(TypedExpr::new(TypedExprKind::ArrayLiteral(typed), ty, span))
+    }
+
+    /// Look up a variant of a union known to exist
+    pub(crate) fn union_variant(
+        &self,
+        union_name: &str,
+        variant: &str,
+        span: Span,
+    ) -> Result<UnionVariant, AnalysisError> {
+        self.unions
+            .get(union_name)
+            .and_then(|def| def.variants.iter().find(|v| v.name == variant))
+            .cloned()
+            .ok_or_else(|| AnalysisError::UnknownVariant {
+                enum_name: union_name.to_string(),
+                variant: variant.to_string(),
+                span: source_span(span),
+            })
+    }
+
+    /// Check `Union::Variant(a, b)`, building a union with a tuple payload
+    fn check_variant_call(
+        &mut self,
+        target: &str,
+        name: &str,
+        args: &[Expression],
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let variant = self.union_variant(target, name, span)?;
+        expect_shape(target, &variant, "tuple", span)?;
+        let fields = payload_fields(&variant);
+        if fields.len() != args.len() {
+            return Err(AnalysisError::PayloadCountMismatch {
+                variant: format!("{}::{}", target, name),
+                expected: fields.len(),
+                found: args.len(),
+                span: source_span(span),
+            });
+        }
+
+        let mut typed_fields = Vec::with_capacity(args.len());
+        for ((field, ty), arg) in fields.iter().zip(args) {
+            let value = self.check_value(arg, Some(ty))?;
+            expect_assignable(&value.ty, ty, value.span)?;
+            typed_fields.push((field.clone(), value));
+        }
+        Ok(union_literal(target, name, typed_fields, span))
+    }
+
+    /// Check `Union::Variant { field: value, ... }`, building a union with a
+    /// struct payload
+    fn check_variant_literal(
+        &mut self,
+        target: &str,
+        name: &str,
+        inits: &[FieldInit],
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        if !self.unions.contains_key(target) {
+            return Err(AnalysisError::UnknownType {
+                name: target.to_string(),
+                span: source_span(span),
+            });
+        }
+        let variant = self.union_variant(target, name, span)?;
+        expect_shape(target, &variant, "struct", span)?;
+        let qualified = format!("{}::{}", target, name);
+        let fields = self.check_field_inits(&qualified, &payload_fields(&variant), inits, span)?;
+        Ok(union_literal(target, name, fields, span))
+    }
+
+    /// Check `base.field`; a pointer to a struct is dereferenced implicitly
+    fn check_field(
+        &mut self,
+        base: &Expression,
+        field: &str,
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let base = self.check_expr(base, None)?;
+        let (owner, through_pointer) = match &base.ty {
+            Type::Pointer { inner, .. } => (&**inner, true),
+            ty => (ty, false),
+        };
+        let ty = match owner {
+            Type::Named(name) if self.structs.contains_key(name) => {
+                self.field_type(name, field, span)?
+            }
+            Type::Str => str_field_type(field).ok_or_else(|| AnalysisError::UnknownField {
+                struct_name: Type::Str.to_string(),
+                field: field.to_string(),
+                span: source_span(span),
+            })?,
+            _ => return Err(no_fields(&base)),
+        };
+        Ok(TypedExpr::new(
+            TypedExprKind::Field {
+                base: Box::new(base),
+                field: field.to_string(),
+                through_pointer,
+            },
+            ty,
+            span,
+        ))
+    }
+
+    /// Check `Name { field: value, ... }`, which must initialize every field
+    /// of the struct exactly once
+    fn check_struct_literal(
+        &mut self,
+        name: &str,
+        fields: &[FieldInit],
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let def = self
+            .structs
+            .get(name)
+            .ok_or_else(|| AnalysisError::UnknownType {
+                name: name.to_string(),
+                span: source_span(span),
+            })?;
+        let declared: Vec<(String, Type)> = def
+            .fields
+            .iter()
+            .map(|field| (field.name.clone(), field.ty.clone()))
+            .collect();
+        let fields = self.check_field_inits(name, &declared, fields, span)?;
+
+        Ok(TypedExpr::new(
+            TypedExprKind::StructLiteral {
+                name: name.to_string(),
+                fields,
+            },
+            Type::Named(name.to_string()),
+            span,
+        ))
+    }
+
+    /// Check field initializers against the `declared` fields of `owner`,
+    /// each of which must be initialized exactly once
+    fn check_field_inits(
+        &mut self,
+        owner: &str,
+        declared: &[(String, Type)],
+        inits: &[FieldInit],
+        span: Span,
+    ) -> Result<Vec<(String, TypedExpr)>, AnalysisError> {
+        let mut typed_fields: Vec<(String, TypedExpr)> = Vec::new();
+        for init in inits {
+            if typed_fields.iter().any(|(field, _)| *field == init.name) {
+                return Err(AnalysisError::FieldInitializedTwice {
+                    struct_name: owner.to_string(),
+                    field: init.name.clone(),
+                    span: source_span(init.span),
+                });
+            }
+            let (_, ty) = declared
+                .iter()
+                .find(|(field, _)| *field == init.name)
+                .ok_or_else(|| AnalysisError::UnknownField {
+                    struct_name: owner.to_string(),
+                    field: init.name.clone(),
+                    span: source_span(init.span),
+                })?;
+            let value = self.check_value(&init.value, Some(ty))?;
+            expect_assignable(&value.ty, ty, value.span)?;
+            typed_fields.push((init.name.clone(), value));
+        }
+
+        let missing: Vec<String> = declared
+            .iter()
+            .filter(|(field, _)| !typed_fields.iter().any(|(name, _)| name == field))
+            .map(|(field, _)| format!("'{}'", field))
+            .collect();
+        if !missing.is_empty() {
+            return Err(AnalysisError::MissingFields {
+                struct_name: owner.to_string(),
+                fields: missing.join(", "),
+                span: source_span(span),
+            });
+        }
+        Ok(typed_fields)
+    }
+
+    fn field_type(
+        &self,
+        struct_name: &str,
+        field: &str,
+        span: Span,
+    ) -> Result<Type, AnalysisError> {
+        self.structs
+            .get(struct_name)
+            .and_then(|def| def.fields.iter().find(|f| f.name == field))
+            .map(|f| f.ty.clone())
+            .ok_or_else(|| AnalysisError::UnknownField {
+                struct_name: struct_name.to_string(),
+                field: field.to_string(),
+                span: source_span(span),
+            })
+    }
+
+    fn check_call(
+        &mut self,
+        callee: &Expression,
+        args: &[Expression],
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let (callee, signature) = match &callee.kind {
+            ExpressionKind::Identifier(name) => {
+                // Locals shadow functions, and values cannot be called
+                if let Some(info) = self.scope.lookup(name) {
+                    return Err(AnalysisError::NotCallable {
+                        name: name.clone(),
+                        found: info.ty.clone(),
+                        span: source_span(callee.span),
+                    });
+                }
+                let signature = self.functions.get(name).cloned().ok_or_else(|| {
+                    AnalysisError::UndefinedIdentifier {
+                        name: name.clone(),
+                        span: source_span(callee.span),
+                    }
+                })?;
+                (Callee::Function(name.clone()), signature)
+            }
+            ExpressionKind::Path { target, name } if self.unions.contains_key(target) => {
+                return self.check_variant_call(target, name, args, span);
+            }
+            ExpressionKind::Path { target, name } => {
+                let signature = self.lookup_method(target, name, callee.span)?;
+                if signature.receiver.is_some() {
+                    return Err(AnalysisError::MethodNeedsReceiver {
+                        target: target.clone(),
+                        method: name.clone(),
+                        span: source_span(callee.span),
+                    });
+                }
+                let callee = Callee::Method {
+                    target: target.clone(),
+                    name: name.clone(),
+                };
+                (callee, signature)
+            }
+            _ => {
+                return Err(AnalysisError::InvalidCallee {
+                    span: source_span(callee.span),
+                });
+            }
+        };
+
+        let args = self.check_args(&callee, &signature, args, span)?;
+        Ok(TypedExpr::new(
+            TypedExprKind::Call { callee, args },
+            signature.return_type,
+            span,
+        ))
+    }
+
+    /// Check `receiver.method(args)`, passing the receiver's address (or the
+    /// receiver itself when it is already a pointer) as `self`
+    fn check_method_call(
+        &mut self,
+        receiver: &Expression,
+        method: &str,
+        args: &[Expression],
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let receiver = self.check_expr(receiver, None)?;
+        let target = match &receiver.ty {
+            Type::Named(target) => Some(target),
+            Type::Pointer { inner, .. } => match &**inner {
+                Type::Named(target) => Some(target),
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(target) = target.cloned() else {
+            return Err(AnalysisError::NoMethods {
+                found: receiver.ty,
+                span: source_span(receiver.span),
+            });
+        };
+
+        let signature = self.lookup_method(&target, method, span)?;
+        let Some(mut_self) = signature.receiver else {
+            return Err(AnalysisError::AssociatedFunctionOnValue {
+                target,
+                method: method.to_string(),
+                span: source_span(span),
+            });
+        };
+        // A `mut self` method needs a writable receiver; a plain `self` one
+        // gets a `*Target` to any receiver
+        let self_arg = match receiver.ty {
+            Type::Pointer { is_mut, .. } if mut_self && !is_mut => {
+                return Err(AnalysisError::ImmutablePointee {
+                    found: receiver.ty,
+                    span: source_span(receiver.span),
+                });
+            }
+            Type::Pointer { .. } => receiver,
+            _ => {
+                if !is_addressable(&receiver) {
+                    return Err(AnalysisError::TemporaryReceiver {
+                        span: source_span(receiver.span),
+                    });
+                }
+                self.take_address(receiver, mut_self)?
+            }
+        };
+        let callee = Callee::Method {
+            target,
+            name: method.to_string(),
+        };
+        let mut all_args = vec![self_arg];
+        all_args.extend(self.check_args(&callee, &signature, args, span)?);
+        Ok(TypedExpr::new(
+            TypedExprKind::Call {
+                callee,
+                args: all_args,
+            },
+            signature.return_type,
+            span,
+        ))
+    }
+
+    fn lookup_method(
+        &self,
+        target: &str,
+        method: &str,
+        span: Span,
+    ) -> Result<Signature, AnalysisError> {
+        if !self.structs.contains_key(target) {
+            return Err(AnalysisError::UnknownType {
+                name: target.to_string(),
+                span: source_span(span),
+            });
+        }
+        self.methods
+            .get(target)
+            .and_then(|methods| methods.get(method))
+            .cloned()
+            .ok_or_else(|| AnalysisError::UnknownMethod {
+                target: target.to_string(),
+                method: method.to_string(),
+                span: source_span(span),
+            })
+    }
+
+    /// Check call arguments against the parameters after `self`
+    fn check_args(
+        &mut self,
+        callee: &Callee,
+        signature: &Signature,
+        args: &[Expression],
+        span: Span,
+    ) -> Result<Vec<TypedExpr>, AnalysisError> {
+        if signature.params.len() != args.len() {
+            return Err(AnalysisError::ArgumentCountMismatch {
+                function: callee_name(callee),
+                expected: signature.params.len(),
+                found: args.len(),
+                span: source_span(span),
+            });
+        }
+
+        let mut typed_args = Vec::with_capacity(args.len());
+        for (arg, param_ty) in args.iter().zip(&signature.params) {
+            let arg = self.check_value(arg, Some(param_ty))?;
+            expect_assignable(&arg.ty, param_ty, arg.span)?;
+            typed_args.push(arg);
+        }
+        Ok(typed_args)
+    }
+
+    fn check_binary(
+        &mut self,
+        left: &Expression,
+        op: &BinaryOp,
+        right: &Expression,
+        hint: Option<&Type>,
+        span: Span,
+    ) -> Result<TypedExpr, AnalysisError> {
+        let (left, right) = match op {
+            BinaryOp::And | BinaryOp::Or => (
+                self.check_expr(left, Some(&Type::Bool))?,
+                self.check_expr(right, Some(&Type::Bool))?,
+            ),
+            // The shift amount is independent of the shifted value's type
+            BinaryOp::Shl | BinaryOp::Shr => {
+                (self.check_expr(left, hint)?, self.check_expr(right, None)?)
+            }
+            BinaryOp::Eq
+            | BinaryOp::Ne
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge => self.check_operands(left, right, None)?,
+            _ => self.check_operands(left, right, hint)?,
+        };
+
+        let invalid = || AnalysisError::InvalidBinaryOperands {
+            op: op.symbol(),
+            left: left.ty.clone(),
+            right: right.ty.clone(),
+            span: source_span(span),
+        };
+        let same = left.ty == right.ty;
+
+        let ty = match op {
+            BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div
+                if same && left.ty.is_numeric() =>
+            {
+                left.ty.clone()
+            }
+            BinaryOp::Mod if same && left.ty.is_integer() => left.ty.clone(),
+            BinaryOp::BitAnd | BinaryOp::BitOr | BinaryOp::BitXor
+                if same && (left.ty.is_integer() || left.ty == Type::Bool) =>
+            {
+                left.ty.clone()
+            }
+            BinaryOp::Shl | BinaryOp::Shr if left.ty.is_integer() && right.ty.is_integer() => {
+                left.ty.clone()
+            }
+            BinaryOp::Eq | BinaryOp::Ne if same => Type::Bool,
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+                if same && (left.ty.is_numeric() || left.ty == Type::Char) =>
+            {
+                Type::Bool
+            }
+            BinaryOp::And | BinaryOp::Or if same && left.ty == Type::Bool => Type::Bool,
+            _ => return Err(invalid()),
+        };
+
+        Ok(TypedExpr::new(
+            TypedExprKind::Binary {
+                left: Box::new(left),
+                op: op.clone(),
+                right: Box::new(right),
+            },
+            ty,
+            span,
+        ))
+    }
+
+    /// Check two operands that must share a type. When only one side is an
+    /// untyped literal, the other side is checked first so the literal can
+    /// adopt its type (`1 < count` types `1` like `count`).
+    pub(crate) fn check_operands(
+        &mut self,
+        left: &Expression,
+        right: &Expression,
+        hint: Option<&Type>,
+    ) -> Result<(TypedExpr, TypedExpr), AnalysisError> {
+        if self.is_untyped(left) && !self.is_untyped(right) {
+            let right = self.check_expr(right, hint)?;
+            let left = self.check_expr(left, Some(&right.ty))?;
+            Ok((left, right))
+        } else {
+            let left = self.check_expr(left, hint)?;
+            let right = self.check_expr(right, Some(&left.ty))?;
+            Ok((left, right))
+        }
+    }
+
+    pub(crate) fn check_literal(
+        &self,
+        lit: &Literal,
+        hint: Option<&Type>,
+        span: Span,
+    ) -> TypedExpr {
+        let ty = match lit {
+            Literal::Numeric(NumericLiteral::Integer(_)) => match hint {
+                Some(ty) if ty.is_numeric() => ty.clone(),
+                _ => self.inference.default_int.clone(),
+            },
+            Literal::Numeric(NumericLiteral::Suffixed(_, suffix)) => suffix.ty(),
+            Literal::Numeric(NumericLiteral::Float(_)) => match hint {
+                Some(ty) if ty.is_floating() => ty.clone(),
+                _ => Type::F32,
+            },
+            Literal::Numeric(NumericLiteral::Double(_)) => match hint {
+                Some(ty) if ty.is_floating() => ty.clone(),
+                _ => Type::F64,
+            },
+            Literal::Bool(_) => Type::Bool,
+            Literal::Char(_) => Type::Char,
+            Literal::Byte(_) => Type::U8,
+            Literal::String(_) => Type::Str,
+            Literal::CString(_) => Type::Pointer {
+                inner: Box::new(Type::U8),
+                is_mut: false,
+            },
+        };
+        TypedExpr::new(TypedExprKind::Literal(lit.clone()), ty, span)
+    }
+
+    /// String literal of type `ty`, its contents stored once however often
+    /// they are used
+    fn check_string(&mut self, value: &str, ty: Type, span: Span) -> TypedExpr {
+        let index = match self.strings.iter().position(|other| other == value) {
+            Some(index) => index,
+            None => {
+                self.strings.push(value.to_string());
+                self.strings.len() - 1
+            }
+        };
+        if ty == Type::Str {
+            self.uses_str = true;
+        }
+        let kind = TypedExprKind::StringLiteral {
+            index,
+            value: value.to_string(),
+        };
+        TypedExpr::new(kind, ty, span)
+    }
+
+    /// Whether an expression's type is decided by context: it is built from
+    /// numeric literals and bindings whose type is still open
+    fn is_untyped(&self, expr: &Expression) -> bool {
+        is_untyped_with(expr, &|name| {
+            self.scope
+                .lookup(name)
+                .is_some_and(|info| info.inferred.is_some())
+        })
+    }
+}
+
+/// Whether a value of type `literal`, given to an untyped literal, may be
+/// changed to `expected`
+fn accepts_type(literal: &Type, expected: &Type) -> bool {
+    if literal.is_floating() {
+        expected.is_floating()
+    } else {
+        expected.is_numeric()
+    }
+}
+
+/// Whether an expression is built only from numeric literals, so its type is
+/// decided by context rather than by its operands
+pub(crate) fn is_untyped_literal(expr: &Expression) -> bool {
+    is_untyped_with(expr, &|_| false)
+}
+
+/// Like `is_untyped_literal`, also accepting the names for which `open` holds
+fn is_untyped_with(expr: &Expression, open: &dyn Fn(&str) -> bool) -> bool {
+    match &expr.kind {
+        ExpressionKind::Identifier(name) => open(name),
+        ExpressionKind::Literal(Literal::Numeric(NumericLiteral::Suffixed(..))) => false,
+        ExpressionKind::Literal(lit) => lit.is_numeric(),
+        ExpressionKind::UnaryExpr {
+            op: UnaryOp::PrefixOp(Prefix::Neg | Prefix::Pos | Prefix::BitNot),
+            expr,
+        } => is_untyped_with(expr, open),
+        ExpressionKind::BinaryExpr {
+            left,
+            op:
+                BinaryOp::Add
+                | BinaryOp::Sub
+                | BinaryOp::Mul
+                | BinaryOp::Div
+                | BinaryOp::Mod
+                | BinaryOp::BitAnd
+                | BinaryOp::BitOr
+                | BinaryOp::BitXor,
+            right,
+        } => is_untyped_with(left, open) && is_untyped_with(right, open),
+        _ => false,
+    }
+}
+
+/// Check that an integer literal with the value `value` fits its type `ty`
+pub(crate) fn check_int_range(value: i128, ty: &Type, span: Span) -> Result<(), AnalysisError> {
+    match ty.integer_range() {
+        Some((min, max)) if value < min || value > max => Err(AnalysisError::LiteralOutOfRange {
+            value,
+            ty: ty.clone(),
+            span: source_span(span),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Value of an index written as an integer literal, possibly negated
+fn constant_index(index: &TypedExpr) -> Option<i128> {
+    match &index.kind {
+        TypedExprKind::Literal(lit) => lit.as_integer(),
+        TypedExprKind::Prefix {
+            op: Prefix::Neg,
+            expr,
+        } => constant_index(expr).map(|value| -value),
+        _ => None,
+    }
+}
+
+/// Whether C can take the address of an expression
+fn is_addressable(expr: &TypedExpr) -> bool {
+    match &expr.kind {
+        TypedExprKind::Identifier(_) => true,
+        TypedExprKind::Prefix {
+            op: Prefix::Deref, ..
+        } => true,
+        TypedExprKind::Index { base, .. } => match base.ty {
+            Type::Array { .. } => is_addressable(base),
+            _ => true,
+        },
+        TypedExprKind::Field {
+            base,
+            through_pointer,
+            ..
+        } => *through_pointer || is_addressable(base),
+        _ => false,
+    }
+}
+
+/// Ensure data can be modified through a pointer-typed expression
+fn expect_mut_pointer(pointer: &TypedExpr) -> Result<(), AnalysisError> {
+    match &pointer.ty {
+        Type::Pointer { is_mut: false, .. } => Err(AnalysisError::ImmutablePointee {
+            found: pointer.ty.clone(),
+            span: source_span(pointer.span),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Ensure a union variant carries the kind of payload it is used with
+pub(crate) fn expect_shape(
+    union_name: &str,
+    variant: &UnionVariant,
+    shape: &'static str,
+    span: Span,
+) -> Result<(), AnalysisError> {
+    let actual = payload_shape(&variant.payload);
+    if actual == shape {
+        Ok(())
+    } else {
+        Err(AnalysisError::VariantShapeMismatch {
+            variant: format!("{}::{}", union_name, variant.name),
+            shape: actual,
+            span: source_span(span),
+        })
+    }
+}
+
+fn union_literal(
+    union_name: &str,
+    variant: &str,
+    fields: Vec<(String, TypedExpr)>,
+    span: Span,
+) -> TypedExpr {
+    TypedExpr::new(
+        TypedExprKind::UnionLiteral {
+            union_name: union_name.to_string(),
+            variant: variant.to_string(),
+            fields,
+        },
+        Type::Named(union_name.to_string()),
+        span,
+    )
+}
+
+/// Type of the field `field` of a `str`, which C lays out as a struct
+fn str_field_type(field: &str) -> Option<Type> {
+    match field {
+        "ptr" => Some(Type::Pointer {
+            inner: Box::new(Type::U8),
+            is_mut: false,
+        }),
+        "len" => Some(Type::U32),
+        _ => None,
+    }
+}
+
+fn no_fields(base: &TypedExpr) -> AnalysisError {
+    AnalysisError::NoFields {
+        found: base.ty.clone(),
+        span: source_span(base.span),
+    }
+}
+
+/// Name of a callee as written in Amber source
+fn callee_name(callee: &Callee) -> String {
+    match callee {
+        Callee::Function(name) => name.clone(),
+        Callee::Method { target, name } => format!("{}::{}", target, name),
+    }
+}
+
+fn invalid_unary(op: &Prefix, operand: &Type, span: Span) -> AnalysisError {
+    AnalysisError::InvalidUnaryOperand {
+        op: op.symbol(),
+        operand: operand.clone(),
+        span: source_span(span),
+    }
+}
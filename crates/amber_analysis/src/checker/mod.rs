@@ -0,0 +1,265 @@
+mod decl;
+mod expr;
+mod stmt;
+
+use std::collections::{HashMap, HashSet};
+
+use amber_ast::{
+    EnumDef, Function, Param, Program, Span, Statement, StructDef, Type, UnionDef, UnionVariant,
+    VariantPayload, source_span,
+};
+
+use crate::error::AnalysisError;
+use crate::scope::Scope;
+use crate::typed::{TypedLoopExits, TypedProgram};
+
+/// Return type of the function whose body is being checked
+struct FunctionContext {
+    name: String,
+    return_type: Type,
+}
+
+/// A loop whose body is being checked
+struct LoopContext {
+    label: Option<String>,
+    exits: TypedLoopExits,
+    /// Match arms entered since the loop; their C `switch` catches a plain
+    /// `break`
+    matches: usize,
+}
+
+/// What a call needs to know about a function or impl method
+#[derive(Debug, Clone)]
+struct Signature {
+    params: Vec<Type>,
+    /// `Some(is_mut)` for methods taking `self` or `mut self`
+    receiver: Option<bool>,
+    return_type: Type,
+}
+
+impl Signature {
+    fn of(func: &Function) -> Self {
+        let mut params = Vec::new();
+        let mut receiver = None;
+        for param in &func.params {
+            match param {
+                Param::SelfParam { is_mut } => receiver = Some(*is_mut),
+                Param::Typed { ty, .. } => params.push(ty.clone()),
+            }
+        }
+        Self {
+            params,
+            receiver,
+            return_type: func.return_type.clone().unwrap_or(Type::Void),
+        }
+    }
+}
+
+/// Types of unannotated bindings initialized with untyped literals, which are
+/// decided by the first use of the binding, or not initialized at all, which
+/// are decided by the first assignment
+struct Inference {
+    /// Type of an integer literal that nothing gives a type to
+    default_int: Type,
+    /// Current type of each such binding
+    types: Vec<Type>,
+    /// Declarations of the bindings that have no type at all until their
+    /// first assignment
+    unassigned: HashMap<usize, Span>,
+    /// Binding opened by the statement being checked, to be settled at the
+    /// end of its block
+    opened: Option<usize>,
+}
+
+impl Default for Inference {
+    fn default() -> Self {
+        Self {
+            default_int: Type::I64,
+            types: Vec::new(),
+            unassigned: HashMap::new(),
+            opened: None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Checker {
+    scope: Scope,
+    structs: HashMap<String, StructDef>,
+    enums: HashMap<String, EnumDef>,
+    unions: HashMap<String, UnionDef>,
+    functions: HashMap<String, Signature>,
+    methods: HashMap<String, HashMap<String, Signature>>,
+    top_level_names: HashSet<String>,
+    function: Option<FunctionContext>,
+    /// Enclosing loops, innermost last
+    loops: Vec<LoopContext>,
+    next_loop_id: usize,
+    inference: Inference,
+    /// Contents of the string literals seen so far, each stored once
+    strings: Vec<String>,
+    uses_str: bool,
+    errors: Vec<AnalysisError>,
+}
+
+impl Checker {
+    /// Checker that gives integer literals without any other type `default_int`
+    pub fn with_default_int(default_int: Type) -> Self {
+        let mut checker = Self::default();
+        checker.inference.default_int = default_int;
+        checker
+    }
+
+    pub fn check_program(mut self, program: &Program) -> Result<TypedProgram, Vec<AnalysisError>> {
+        // Types, functions and methods are visible everywhere so
+        // declarations can refer to each other regardless of order
+        for statement in &program.statements {
+            match statement {
+                Statement::Struct(def) => {
+                    self.structs.insert(def.name.clone(), def.clone());
+                }
+                Statement::Enum(def) => {
+                    self.enums
+                        .entry(def.name.clone())
+                        .or_insert_with(|| def.clone());
+                }
+                Statement::Union(def) => {
+                    self.unions
+                        .entry(def.name.clone())
+                        .or_insert_with(|| def.clone());
+                }
+                Statement::Function(func) => {
+                    self.functions
+                        .entry(func.name.clone())
+                        .or_insert_with(|| Signature::of(func));
+                }
+                Statement::Impl(block) => {
+                    let methods = self.methods.entry(block.target.clone()).or_default();
+                    for method in &block.methods {
+                        methods
+                            .entry(method.name.clone())
+                            .or_insert_with(|| Signature::of(method));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut statements = Vec::new();
+        let mut opened = Vec::new();
+        for statement in &program.statements {
+            match self.check_top_level(statement) {
+                Ok(typed) => statements.push(typed),
+                Err(err) => self.errors.push(err),
+            }
+            if let Some(id) = self.inference.opened.take() {
+                opened.push((statements.len() - 1, id, statement));
+            }
+        }
+        self.settle_inferred(&mut statements, opened);
+
+        if self.errors.is_empty() {
+            Ok(TypedProgram {
+                statements,
+                strings: self.strings,
+                uses_str: self.uses_str,
+            })
+        } else {
+            Err(self.errors)
+        }
+    }
+
+    fn declare_top_level_name(&mut self, name: &str, span: Span) -> Result<(), AnalysisError> {
+        if self.top_level_names.insert(name.to_string()) {
+            Ok(())
+        } else {
+            Err(AnalysisError::DuplicateDefinition {
+                name: name.to_string(),
+                span: source_span(span),
+            })
+        }
+    }
+
+    /// Check that every named type mentioned in `ty` has been declared.
+    /// `span` is the node the type annotation belongs to.
+    fn resolve_type(&mut self, ty: &Type, span: Span) -> Result<(), AnalysisError> {
+        match ty {
+            Type::Named(name)
+                if !self.structs.contains_key(name)
+                    && !self.enums.contains_key(name)
+                    && !self.unions.contains_key(name) =>
+            {
+                Err(AnalysisError::UnknownType {
+                    name: name.clone(),
+                    span: source_span(span),
+                })
+            }
+            Type::Pointer { inner, .. } | Type::Array { inner, .. } => {
+                self.resolve_type(inner, span)
+            }
+            Type::ComptimeArray { .. } => Err(AnalysisError::UnresolvedArrayLength {
+                span: source_span(span),
+            }),
+            Type::Str => {
+                self.uses_str = true;
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Payload fields of a union variant as they are laid out in C. Positional
+/// fields are named `_0`, `_1`, ...
+fn payload_fields(variant: &UnionVariant) -> Vec<(String, Type)> {
+    match &variant.payload {
+        VariantPayload::Unit => Vec::new(),
+        VariantPayload::Tuple(types) => types
+            .iter()
+            .enumerate()
+            .map(|(i, ty)| (format!("_{}", i), ty.clone()))
+            .collect(),
+        VariantPayload::Struct(fields) => fields
+            .iter()
+            .map(|field| (field.name.clone(), field.ty.clone()))
+            .collect(),
+    }
+}
+
+/// Kind of payload a variant carries, as named in diagnostics
+fn payload_shape(payload: &VariantPayload) -> &'static str {
+    match payload {
+        VariantPayload::Unit => "unit",
+        VariantPayload::Tuple(_) => "tuple",
+        VariantPayload::Struct(_) => "struct",
+    }
+}
+
+/// Whether a value of type `found` may be stored where `expected` is required
+fn is_assignable(found: &Type, expected: &Type) -> bool {
+    match (found, expected) {
+        (
+            Type::Pointer {
+                inner: found_inner,
+                is_mut: found_mut,
+            },
+            Type::Pointer {
+                inner: expected_inner,
+                is_mut: expected_mut,
+            },
+        ) => found_inner == expected_inner && (*found_mut || !*expected_mut),
+        _ => found == expected,
+    }
+}
+
+fn expect_assignable(found: &Type, expected: &Type, span: Span) -> Result<(), AnalysisError> {
+    if is_assignable(found, expected) {
+        Ok(())
+    } else {
+        Err(AnalysisError::TypeMismatch {
+            expected: expected.clone(),
+            found: found.clone(),
+            span: source_span(span),
+        })
+    }
+}
@@ -0,0 +1,767 @@
+use std::collections::HashSet;
+
+use amber_ast::{
+    Block, ElseBranch, Expression, ExpressionKind, ForIterable, ForLoop, IfElse, Match, Modifier,
+    PatternKind, PayloadPattern, Span, Statement, Type, VariableBinding, source_span,
+};
+
+use crate::checker::expr::{check_int_range, expect_shape, is_untyped_literal};
+use crate::checker::{Checker, LoopContext, expect_assignable, payload_fields};
+use crate::error::AnalysisError;
+use crate::scope::VarInfo;
+use crate::typed::{
+    TypedBinding, TypedBlock, TypedElseBranch, TypedForIterable, TypedForLoop, TypedIfElse,
+    TypedLoopExits, TypedMatch, TypedMatchArm, TypedPattern, TypedPayloadBinding, TypedStatement,
+    TypedWhileLoop,
+};
+
+impl Checker {
+    /// Check a statement at the top level of the program
+    pub(crate) fn check_top_level(
+        &mut self,
+        statement: &Statement,
+    ) -> Result<TypedStatement, AnalysisError> {
+        match statement {
+            Statement::Binding(binding) => {
+                self.declare_top_level_name(&binding.name, binding.span)?;
+                self.check_binding(binding).map(TypedStatement::Binding)
+            }
+            Statement::Struct(def) => {
+                self.declare_top_level_name(&def.name, def.span)?;
+                self.check_struct(def).map(TypedStatement::Struct)
+            }
+            Statement::Enum(def) => {
+                self.declare_top_level_name(&def.name, def.span)?;
+                self.check_enum(def).map(TypedStatement::Enum)
+            }
+            Statement::Union(def) => {
+                self.declare_top_level_name(&def.name, def.span)?;
+                self.check_union(def).map(TypedStatement::Union)
+            }
+            Statement::Function(func) => {
+                self.declare_top_level_name(&func.name, func.span)?;
+                self.check_function(func, None)
+                    .map(TypedStatement::Function)
+            }
+            Statement::Impl(block) => self.check_impl(block).map(TypedStatement::Impl),
+            other => Err(AnalysisError::TopLevelStatement {
+                kind: statement_kind(other),
+                span: source_span(other.span()),
+            }),
+        }
+    }
+
+    pub(crate) fn check_block(&mut self, block: &Block) -> TypedBlock {
+        self.scope.push();
+        let mut statements = Vec::new();
+        let mut opened = Vec::new();
+        for statement in &block.statements {
+            match self.check_statement(statement) {
+                Ok(typed) => statements.push(typed),
+                Err(err) => self.errors.push(err),
+            }
+            if let Some(id) = self.inference.opened.take() {
+                opened.push((statements.len() - 1, id, statement));
+            }
+        }
+        self.settle_inferred(&mut statements, opened);
+        self.scope.pop();
+        TypedBlock { statements }
+    }
+
+    /// Give the bindings whose type was left open by their initializer, or
+    /// by the lack of one, the type their uses decided. `opened` pairs the position of each binding in
+    /// `statements` with its `Inference` id and its source.
+    pub(crate) fn settle_inferred(
+        &mut self,
+        statements: &mut [TypedStatement],
+        opened: Vec<(usize, usize, &Statement)>,
+    ) {
+        for (index, id, statement) in opened {
+            let (TypedStatement::Binding(typed), Statement::Binding(binding)) =
+                (&mut statements[index], statement)
+            else {
+                continue;
+            };
+            if let Some(span) = self.inference.unassigned.remove(&id) {
+                self.errors.push(AnalysisError::CannotInferType {
+                    name: binding.name.clone(),
+                    span: source_span(span),
+                });
+                continue;
+            }
+            let ty = self.inference.types[id].clone();
+            if typed.ty == ty {
+                continue;
+            }
+            let Some(expr) = &binding.value else {
+                typed.ty = ty;
+                continue;
+            };
+            // The initializer is made of literals only, so checking it again
+            // has no other effect
+            match self.check_value(expr, Some(&ty)) {
+                Ok(value) => {
+                    typed.ty = ty;
+                    typed.value = Some(value);
+                }
+                Err(err) => self.errors.push(err),
+            }
+        }
+    }
+
+    fn check_statement(&mut self, statement: &Statement) -> Result<TypedStatement, AnalysisError> {
+        match statement {
+            Statement::Binding(binding) => self.check_binding(binding).map(TypedStatement::Binding),
+            Statement::IfElse(if_else) => self.check_if_else(if_else).map(TypedStatement::IfElse),
+            Statement::WhileLoop(while_loop) => {
+                let condition = self.check_condition(&while_loop.condition);
+                let (block, exits) = self.check_loop_body(
+                    while_loop.label.as_deref(),
+                    &while_loop.block,
+                    while_loop.span,
+                )?;
+                Ok(TypedStatement::WhileLoop(TypedWhileLoop {
+                    condition: condition?,
+                    block,
+                    exits,
+                }))
+            }
+            Statement::ForLoop(for_loop) => self.check_for_loop(for_loop),
+            Statement::Match(match_stmt) => self.check_match(match_stmt),
+            Statement::Block(block) => Ok(TypedStatement::Block(self.check_block(block))),
+            Statement::ExprStatement(expr) => self
+                .check_expr(expr, None)
+                .map(TypedStatement::ExprStatement),
+            Statement::Assignment { target, value, .. } => {
+                // A binding without a type yet takes the type of the first
+                // value assigned to it
+                let first = match &target.kind {
+                    ExpressionKind::Identifier(name) if self.is_unassigned(name) => {
+                        let value = self.check_expr(value, None)?;
+                        self.assign_first(name, &value.ty);
+                        Some(value)
+                    }
+                    _ => None,
+                };
+                let target = self.check_expr(target, first.as_ref().map(|value| &value.ty))?;
+                self.check_place(&target)?;
+                if let Type::Array { .. } = target.ty {
+                    return Err(AnalysisError::ArrayCopy {
+                        span: source_span(target.span),
+                    });
+                }
+                let value = match first {
+                    Some(value) => value,
+                    None => self.check_expr(value, Some(&target.ty))?,
+                };
+                expect_assignable(&value.ty, &target.ty, value.span)?;
+                Ok(TypedStatement::Assignment { target, value })
+            }
+            Statement::Return { value, span } => self.check_return(value.as_ref(), *span),
+            Statement::Break { label, span } => self
+                .check_jump(true, label.as_deref(), *span)
+                .map(TypedStatement::Break),
+            Statement::Continue { label, span } => self
+                .check_jump(false, label.as_deref(), *span)
+                .map(TypedStatement::Continue),
+            Statement::Struct(_)
+            | Statement::Enum(_)
+            | Statement::Union(_)
+            | Statement::Function(_)
+            | Statement::Impl(_) => Err(AnalysisError::NestedDeclaration {
+                kind: statement_kind(statement),
+                span: source_span(statement.span()),
+            }),
+        }
+    }
+
+    /// Check an `if` and the rest of its `else if` chain. Every branch is
+    /// checked even if a condition has an error.
+    fn check_if_else(&mut self, if_else: &IfElse) -> Result<TypedIfElse, AnalysisError> {
+        if if_else.modifier == Some(Modifier::Comptime) {
+            return Err(AnalysisError::ComptimeBranch {
+                span: source_span(if_else.span),
+            });
+        }
+        let condition = self.check_condition(&if_else.condition);
+        let then_block = self.check_block(&if_else.then_block);
+        let else_branch = match &if_else.else_branch {
+            Some(ElseBranch::If(next)) => Some(
+                self.check_if_else(next)
+                    .map(|next| TypedElseBranch::If(Box::new(next))),
+            ),
+            Some(ElseBranch::Block(block)) => {
+                Some(Ok(TypedElseBranch::Block(self.check_block(block))))
+            }
+            None => None,
+        };
+        Ok(TypedIfElse {
+            condition: condition?,
+            then_block,
+            else_branch: else_branch.transpose()?,
+        })
+    }
+
+    /// Check a `for` loop. Range bounds share one integer type, which the loop
+    /// variable takes; an array loop binds a copy of each element. Either way
+    /// the variable is immutable and only visible in the body.
+    fn check_for_loop(&mut self, for_loop: &ForLoop) -> Result<TypedStatement, AnalysisError> {
+        if for_loop.is_comptime {
+            return Err(AnalysisError::ComptimeLoop {
+                span: source_span(for_loop.span),
+            });
+        }
+        let (iterable, ty) = match &for_loop.iterable {
+            ForIterable::Range { start, end } => {
+                let (start, end) = self.check_operands(start, end, None)?;
+                for bound in [&start, &end] {
+                    if !bound.ty.is_integer() {
+                        return Err(AnalysisError::NonIntegerRange {
+                            found: bound.ty.clone(),
+                            span: source_span(bound.span),
+                        });
+                    }
+                }
+                expect_assignable(&end.ty, &start.ty, end.span)?;
+                let ty = start.ty.clone();
+                (TypedForIterable::Range { start, end }, ty)
+            }
+            ForIterable::Array(expr) => {
+                let array = self.check_expr(expr, None)?;
+                let Type::Array { inner, .. } = &array.ty else {
+                    return Err(AnalysisError::NotIterable {
+                        found: array.ty.clone(),
+                        span: source_span(array.span),
+                    });
+                };
+                if let Type::Array { .. } = **inner {
+                    return Err(AnalysisError::ArrayCopy {
+                        span: source_span(array.span),
+                    });
+                }
+                let ty = (**inner).clone();
+                (TypedForIterable::Array(array), ty)
+            }
+        };
+
+        self.scope.push();
+        self.scope.define(
+            &for_loop.variable,
+            VarInfo {
+                ty: ty.clone(),
+                is_mutable: false,
+                inferred: None,
+            },
+        );
+        let body = self.check_loop_body(for_loop.label.as_deref(), &for_loop.block, for_loop.span);
+        self.scope.pop();
+        let (block, exits) = body?;
+        Ok(TypedStatement::ForLoop(TypedForLoop {
+            variable: for_loop.variable.clone(),
+            ty,
+            iterable,
+            block,
+            exits,
+        }))
+    }
+
+    /// Check the body of a loop, which `break` and `continue` inside may
+    /// target by its label
+    fn check_loop_body(
+        &mut self,
+        label: Option<&str>,
+        block: &Block,
+        span: Span,
+    ) -> Result<(TypedBlock, TypedLoopExits), AnalysisError> {
+        if let Some(label) = label
+            && self
+                .loops
+                .iter()
+                .any(|other| other.label.as_deref() == Some(label))
+        {
+            return Err(AnalysisError::DuplicateLabel {
+                label: label.to_string(),
+                span: source_span(span),
+            });
+        }
+        let exits = TypedLoopExits {
+            id: self.next_loop_id,
+            ..Default::default()
+        };
+        self.next_loop_id += 1;
+        self.loops.push(LoopContext {
+            label: label.map(str::to_string),
+            exits,
+            matches: 0,
+        });
+        let block = self.check_block(block);
+        let context = self.loops.pop().expect("the loop was pushed above");
+        Ok((block, context.exits))
+    }
+
+    /// Resolve the loop a `break` or `continue` targets. Returns the loop's id
+    /// when C needs a `goto` to get there: the loop is not the innermost one,
+    /// or a `break` would only leave the `switch` of a match.
+    fn check_jump(
+        &mut self,
+        is_break: bool,
+        label: Option<&str>,
+        span: Span,
+    ) -> Result<Option<usize>, AnalysisError> {
+        let keyword = if is_break { "break" } else { "continue" };
+        let index = match label {
+            Some(label) => self
+                .loops
+                .iter()
+                .rposition(|context| context.label.as_deref() == Some(label))
+                .ok_or_else(|| AnalysisError::UndefinedLabel {
+                    label: label.to_string(),
+                    span: source_span(span),
+                })?,
+            None => self
+                .loops
+                .len()
+                .checked_sub(1)
+                .ok_or(AnalysisError::JumpOutsideLoop {
+                    keyword,
+                    span: source_span(span),
+                })?,
+        };
+        let innermost = index + 1 == self.loops.len();
+        let context = &mut self.loops[index];
+        if innermost && !(is_break && context.matches > 0) {
+            return Ok(None);
+        }
+        if is_break {
+            context.exits.break_label = true;
+        } else {
+            context.exits.continue_label = true;
+        }
+        Ok(Some(context.exits.id))
+    }
+
+    /// Check a match statement: every pattern must be a constant or variant
+    /// of the matched type, no pattern may repeat, and all values must be
+    /// covered
+    fn check_match(&mut self, match_stmt: &Match) -> Result<TypedStatement, AnalysisError> {
+        let scrutinee = self.check_expr(&match_stmt.scrutinee, None)?;
+        let ty = &scrutinee.ty;
+        let tagged = matches!(ty, Type::Named(name) if self.unions.contains_key(name));
+        let variants = match ty {
+            Type::Named(name) => {
+                let names: Option<Vec<&String>> = match self.enums.get(name) {
+                    Some(def) => Some(def.variants.iter().map(|v| &v.name).collect()),
+                    None => self
+                        .unions
+                        .get(name)
+                        .map(|def| def.variants.iter().map(|v| &v.name).collect()),
+                };
+                names.map(|names| {
+                    names
+                        .into_iter()
+                        .map(|variant| format!("{}::{}", name, variant))
+                        .collect::<Vec<_>>()
+                })
+            }
+            _ => None,
+        };
+        if variants.is_none() && !(ty.is_integer() || *ty == Type::Bool || *ty == Type::Char) {
+            return Err(AnalysisError::InvalidMatchType {
+                found: ty.clone(),
+                span: source_span(scrutinee.span),
+            });
+        }
+
+        let mut covered: Vec<String> = Vec::new();
+        let mut has_wildcard = false;
+        let mut arms = Vec::new();
+        for arm in &match_stmt.arms {
+            if has_wildcard {
+                return Err(AnalysisError::UnreachableArm {
+                    span: source_span(arm.span),
+                });
+            }
+            let mut patterns = Vec::new();
+            for pattern in &arm.patterns {
+                let (text, typed) = match &pattern.kind {
+                    PatternKind::Wildcard => {
+                        has_wildcard = true;
+                        patterns.push(TypedPattern::Wildcard);
+                        continue;
+                    }
+                    PatternKind::Literal(lit) => {
+                        let value = self.check_literal(lit, Some(ty), pattern.span);
+                        expect_assignable(&value.ty, ty, pattern.span)?;
+                        if let Some(number) = lit.as_integer() {
+                            check_int_range(number, ty, pattern.span)?;
+                        }
+                        // `b'A'` and `65` are the same pattern
+                        let key = lit
+                            .as_integer()
+                            .map_or_else(|| lit.to_string(), |n| n.to_string());
+                        (key, TypedPattern::Value(value))
+                    }
+                    // Naming a union variant without a payload pattern matches
+                    // it whatever its payload
+                    PatternKind::Variant { target, name } if self.unions.contains_key(target) => {
+                        self.union_variant(target, name, pattern.span)?;
+                        let typed = TypedPattern::Variant {
+                            union_name: target.clone(),
+                            variant: name.clone(),
+                            bindings: Vec::new(),
+                        };
+                        expect_assignable(&Type::Named(target.clone()), ty, pattern.span)?;
+                        (format!("{}::{}", target, name), typed)
+                    }
+                    PatternKind::Variant { target, name } => {
+                        let value = self.check_path(target, name, pattern.span)?;
+                        expect_assignable(&value.ty, ty, pattern.span)?;
+                        (format!("{}::{}", target, name), TypedPattern::Value(value))
+                    }
+                    PatternKind::Destructure {
+                        target,
+                        name,
+                        payload,
+                    } => {
+                        let bindings =
+                            self.check_payload_pattern(target, name, payload, pattern.span)?;
+                        if !bindings.is_empty() && arm.patterns.len() > 1 {
+                            return Err(AnalysisError::BindingInAlternative {
+                                span: source_span(pattern.span),
+                            });
+                        }
+                        expect_assignable(&Type::Named(target.clone()), ty, pattern.span)?;
+                        let typed = TypedPattern::Variant {
+                            union_name: target.clone(),
+                            variant: name.clone(),
+                            bindings,
+                        };
+                        (format!("{}::{}", target, name), typed)
+                    }
+                };
+                if covered.contains(&text) {
+                    return Err(AnalysisError::DuplicatePattern {
+                        pattern: text,
+                        span: source_span(pattern.span),
+                    });
+                }
+                covered.push(text);
+                patterns.push(typed);
+            }
+
+            // Payload bindings are visible in the arm's body only
+            self.scope.push();
+            for pattern in &patterns {
+                if let TypedPattern::Variant { bindings, .. } = pattern {
+                    for binding in bindings {
+                        self.scope.define(
+                            &binding.name,
+                            VarInfo {
+                                ty: binding.ty.clone(),
+                                is_mutable: false,
+                                inferred: None,
+                            },
+                        );
+                    }
+                }
+            }
+            if let Some(context) = self.loops.last_mut() {
+                context.matches += 1;
+            }
+            let body = self.check_block(&arm.body);
+            if let Some(context) = self.loops.last_mut() {
+                context.matches -= 1;
+            }
+            self.scope.pop();
+            arms.push(TypedMatchArm { patterns, body });
+        }
+
+        if !has_wildcard {
+            let all = match variants {
+                Some(variants) => variants,
+                None if *ty == Type::Bool => vec!["true".to_string(), "false".to_string()],
+                None => vec!["_".to_string()],
+            };
+            let missing: Vec<String> = all
+                .into_iter()
+                .filter(|value| !covered.contains(value))
+                .map(|value| format!("'{}'", value))
+                .collect();
+            if !missing.is_empty() {
+                return Err(AnalysisError::NonExhaustiveMatch {
+                    missing: missing.join(", "),
+                    span: source_span(match_stmt.span),
+                });
+            }
+        }
+
+        Ok(TypedStatement::Match(TypedMatch {
+            scrutinee,
+            tagged,
+            arms,
+        }))
+    }
+
+    /// Resolve the payload fields a union variant pattern binds
+    fn check_payload_pattern(
+        &self,
+        target: &str,
+        name: &str,
+        payload: &PayloadPattern,
+        span: Span,
+    ) -> Result<Vec<TypedPayloadBinding>, AnalysisError> {
+        let qualified = format!("{}::{}", target, name);
+        if !self.unions.contains_key(target) {
+            return Err(if self.enums.contains_key(target) {
+                AnalysisError::VariantShapeMismatch {
+                    variant: qualified,
+                    shape: "unit",
+                    span: source_span(span),
+                }
+            } else {
+                AnalysisError::UnknownType {
+                    name: target.to_string(),
+                    span: source_span(span),
+                }
+            });
+        }
+        let variant = self.union_variant(target, name, span)?;
+        let fields = payload_fields(&variant);
+
+        // (field, name, span) for every field bound to a name
+        let mut bound: Vec<(&String, &String, Span)> = Vec::new();
+        match payload {
+            PayloadPattern::Tuple(names) => {
+                expect_shape(target, &variant, "tuple", span)?;
+                if names.len() != fields.len() {
+                    return Err(AnalysisError::PayloadCountMismatch {
+                        variant: qualified,
+                        expected: fields.len(),
+                        found: names.len(),
+                        span: source_span(span),
+                    });
+                }
+                for ((field, _), name) in fields.iter().zip(names) {
+                    if let Some(name) = name {
+                        bound.push((field, name, span));
+                    }
+                }
+            }
+            PayloadPattern::Struct(field_bindings) => {
+                expect_shape(target, &variant, "struct", span)?;
+                let mut seen = HashSet::new();
+                for binding in field_bindings {
+                    if !fields.iter().any(|(field, _)| *field == binding.field) {
+                        return Err(AnalysisError::UnknownField {
+                            struct_name: qualified,
+                            field: binding.field.clone(),
+                            span: source_span(binding.span),
+                        });
+                    }
+                    if !seen.insert(&binding.field) {
+                        return Err(AnalysisError::DuplicateBinding {
+                            name: binding.field.clone(),
+                            span: source_span(binding.span),
+                        });
+                    }
+                    if let Some(name) = &binding.binding {
+                        bound.push((&binding.field, name, binding.span));
+                    }
+                }
+            }
+        }
+
+        let mut bindings: Vec<TypedPayloadBinding> = Vec::new();
+        for (field, name, span) in bound {
+            if bindings.iter().any(|other| other.name == *name) {
+                return Err(AnalysisError::DuplicateBinding {
+                    name: name.clone(),
+                    span: source_span(span),
+                });
+            }
+            let (_, ty) = fields
+                .iter()
+                .find(|(other, _)| other == field)
+                .expect("bound fields exist in the variant");
+            // The binding is a copy, which C cannot make of an array
+            if let Type::Array { .. } = ty {
+                return Err(AnalysisError::ArrayCopy {
+                    span: source_span(span),
+                });
+            }
+            bindings.push(TypedPayloadBinding {
+                field: field.clone(),
+                name: name.clone(),
+                ty: ty.clone(),
+            });
+        }
+        Ok(bindings)
+    }
+
+    fn check_binding(&mut self, binding: &VariableBinding) -> Result<TypedBinding, AnalysisError> {
+        if let Some(ty) = &binding.ty {
+            self.resolve_type(ty, binding.span)?;
+        }
+
+        // A comptime initializer is replaced by the value it computes, so it
+        // may read an array that C could not copy
+        let checked = match &binding.value {
+            Some(expr) if binding.modifier == Some(Modifier::Comptime) => {
+                Some(self.check_expr(expr, binding.ty.as_ref()))
+            }
+            Some(expr) => Some(self.check_value(expr, binding.ty.as_ref())),
+            None => None,
+        };
+        let value = match checked {
+            Some(Ok(value)) => Some(value),
+            Some(Err(err)) => {
+                // Keep an annotated name defined so later uses do not
+                // produce follow-up errors
+                if let Some(ty) = &binding.ty {
+                    self.define_binding(binding, ty.clone(), None);
+                }
+                return Err(err);
+            }
+            None => None,
+        };
+
+        let ty = match (&binding.ty, &value) {
+            (Some(ty), _) => ty.clone(),
+            (None, Some(value)) => value.ty.clone(),
+            // A `var` can wait for its first assignment to decide its type
+            (None, None) if binding.is_mutable => {
+                let id = self.open_inference(Type::Void);
+                self.inference.unassigned.insert(id, binding.span);
+                self.define_binding(binding, Type::Void, Some(id));
+                return Ok(TypedBinding {
+                    modifier: binding.modifier.clone(),
+                    is_mutable: true,
+                    name: binding.name.clone(),
+                    ty: Type::Void,
+                    value: None,
+                    span: binding.span,
+                });
+            }
+            (None, None) => {
+                return Err(AnalysisError::CannotInferType {
+                    name: binding.name.clone(),
+                    span: source_span(binding.span),
+                });
+            }
+        };
+        // An untyped literal only suggests a type; the first use of the
+        // binding may still choose another one
+        let inferred = (binding.ty.is_none()
+            && binding.value.as_ref().is_some_and(is_untyped_literal))
+        .then(|| self.open_inference(ty.clone()));
+        self.define_binding(binding, ty.clone(), inferred);
+        if let Some(value) = &value {
+            expect_assignable(&value.ty, &ty, value.span)?;
+        }
+
+        Ok(TypedBinding {
+            modifier: binding.modifier.clone(),
+            is_mutable: binding.is_mutable,
+            name: binding.name.clone(),
+            ty,
+            value,
+            span: binding.span,
+        })
+    }
+
+    /// Start inferring the type of the binding being declared, from `ty`
+    fn open_inference(&mut self, ty: Type) -> usize {
+        let id = self.inference.types.len();
+        self.inference.types.push(ty);
+        self.inference.opened = Some(id);
+        id
+    }
+
+    /// Whether `name` is a binding that has no type until its first assignment
+    fn is_unassigned(&self, name: &str) -> bool {
+        self.scope
+            .lookup(name)
+            .and_then(|info| info.inferred)
+            .is_some_and(|id| self.inference.unassigned.contains_key(&id))
+    }
+
+    /// Give a binding that has no type yet the type of its first assigned value
+    fn assign_first(&mut self, name: &str, ty: &Type) {
+        if let Some(info) = self.scope.lookup_mut(name)
+            && let Some(id) = info.inferred.take()
+        {
+            self.inference.unassigned.remove(&id);
+            info.ty = ty.clone();
+            self.inference.types[id] = ty.clone();
+        }
+    }
+
+    fn define_binding(&mut self, binding: &VariableBinding, ty: Type, inferred: Option<usize>) {
+        self.scope.define(
+            &binding.name,
+            VarInfo {
+                ty,
+                is_mutable: binding.is_mutable,
+                inferred,
+            },
+        );
+    }
+
+    fn check_return(
+        &mut self,
+        value: Option<&Expression>,
+        span: Span,
+    ) -> Result<TypedStatement, AnalysisError> {
+        let (function, return_type) = match &self.function {
+            Some(ctx) => (ctx.name.clone(), ctx.return_type.clone()),
+            None => {
+                return Err(AnalysisError::TopLevelStatement {
+                    kind: "return",
+                    span: source_span(span),
+                });
+            }
+        };
+
+        match value {
+            Some(expr) => {
+                if return_type == Type::Void {
+                    return Err(AnalysisError::UnexpectedReturnValue {
+                        function,
+                        span: source_span(expr.span),
+                    });
+                }
+                let value = self.check_expr(expr, Some(&return_type))?;
+                expect_assignable(&value.ty, &return_type, value.span)?;
+                Ok(TypedStatement::Return(Some(value)))
+            }
+            None if return_type != Type::Void => Err(AnalysisError::MissingReturnValue {
+                function,
+                expected: return_type,
+                span: source_span(span),
+            }),
+            None => Ok(TypedStatement::Return(None)),
+        }
+    }
+}
+
+fn statement_kind(statement: &Statement) -> &'static str {
+    match statement {
+        Statement::Binding(_) => "binding",
+        Statement::IfElse(_) => "if statement",
+        Statement::WhileLoop(_) => "while loop",
+        Statement::ForLoop(_) => "for loop",
+        Statement::Match(_) => "match statement",
+        Statement::Block(_) => "block",
+        Statement::ExprStatement(_) => "expression statement",
+        Statement::Struct(_) => "struct definition",
+        Statement::Enum(_) => "enum definition",
+        Statement::Union(_) => "union definition",
+        Statement::Function(_) => "function definition",
+        Statement::Impl(_) => "impl block",
+        Statement::Assignment { .. } => "assignment",
+        Statement::Return { .. } => "return",
+        Statement::Break { .. } => "break",
+        Statement::Continue { .. } => "continue",
+    }
+}
@@ -0,0 +1,645 @@
+use amber_ast::Type;
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+#[derive(Error, Diagnostic, Debug, Clone, PartialEq)]
+pub enum AnalysisError {
+    #[error("cannot find '{name}' in this scope")]
+    #[diagnostic(code(amber_analysis::undefined_identifier))]
+    UndefinedIdentifier {
+        name: String,
+        #[label("not found in this scope")]
+        span: SourceSpan,
+    },
+
+    #[error("unknown type '{name}'")]
+    #[diagnostic(code(amber_analysis::unknown_type))]
+    UnknownType {
+        name: String,
+        #[label("type used here")]
+        span: SourceSpan,
+    },
+
+    #[error("mismatched types: expected {expected}, found {found}")]
+    #[diagnostic(code(amber_analysis::type_mismatch))]
+    TypeMismatch {
+        expected: Type,
+        found: Type,
+        #[label("expected {expected}")]
+        span: SourceSpan,
+    },
+
+    #[error("operator '{op}' cannot be applied to {left} and {right}")]
+    #[diagnostic(code(amber_analysis::invalid_operands))]
+    InvalidBinaryOperands {
+        op: &'static str,
+        left: Type,
+        right: Type,
+        #[label("invalid operands")]
+        span: SourceSpan,
+    },
+
+    #[error("operator '{op}' cannot be applied to {operand}")]
+    #[diagnostic(code(amber_analysis::invalid_operand))]
+    InvalidUnaryOperand {
+        op: &'static str,
+        operand: Type,
+        #[label("invalid operand")]
+        span: SourceSpan,
+    },
+
+    #[error("type {found} cannot be indexed")]
+    #[diagnostic(code(amber_analysis::not_indexable))]
+    NotIndexable {
+        found: Type,
+        #[label("indexed here")]
+        span: SourceSpan,
+    },
+
+    #[error("index must be an integer, found {found}")]
+    #[diagnostic(code(amber_analysis::non_integer_index))]
+    NonIntegerIndex {
+        found: Type,
+        #[label("not an integer")]
+        span: SourceSpan,
+    },
+
+    #[error("'{name}' is not a function")]
+    #[diagnostic(code(amber_analysis::not_callable))]
+    NotCallable {
+        name: String,
+        found: Type,
+        #[label("has type {found}")]
+        span: SourceSpan,
+    },
+
+    #[error("only named functions can be called")]
+    #[diagnostic(code(amber_analysis::invalid_callee))]
+    InvalidCallee {
+        #[label("not a function name")]
+        span: SourceSpan,
+    },
+
+    #[error("type {found} has no fields")]
+    #[diagnostic(code(amber_analysis::no_fields))]
+    NoFields {
+        found: Type,
+        #[label("field accessed here")]
+        span: SourceSpan,
+    },
+
+    #[error("struct '{struct_name}' has no field named '{field}'")]
+    #[diagnostic(code(amber_analysis::unknown_field))]
+    UnknownField {
+        struct_name: String,
+        field: String,
+        #[label("unknown field")]
+        span: SourceSpan,
+    },
+
+    #[error("field '{field}' of struct '{struct_name}' is initialized twice")]
+    #[diagnostic(code(amber_analysis::field_initialized_twice))]
+    FieldInitializedTwice {
+        struct_name: String,
+        field: String,
+        #[label("initialized again here")]
+        span: SourceSpan,
+    },
+
+    #[error("missing fields {fields} in initializer of struct '{struct_name}'")]
+    #[diagnostic(code(amber_analysis::missing_fields))]
+    MissingFields {
+        struct_name: String,
+        fields: String,
+        #[label("missing fields")]
+        span: SourceSpan,
+    },
+
+    #[error("no method named '{method}' found for type '{target}'")]
+    #[diagnostic(code(amber_analysis::unknown_method))]
+    UnknownMethod {
+        target: String,
+        method: String,
+        #[label("method not found")]
+        span: SourceSpan,
+    },
+
+    #[error("type {found} has no methods")]
+    #[diagnostic(code(amber_analysis::no_methods))]
+    NoMethods {
+        found: Type,
+        #[label("method receiver")]
+        span: SourceSpan,
+    },
+
+    #[error("'{target}::{method}' takes `self` and must be called on a value")]
+    #[diagnostic(
+        code(amber_analysis::method_needs_receiver),
+        help("call it as `value.{method}(...)`")
+    )]
+    MethodNeedsReceiver {
+        target: String,
+        method: String,
+        #[label("called without a receiver")]
+        span: SourceSpan,
+    },
+
+    #[error("'{target}::{method}' is an associated function, not a method")]
+    #[diagnostic(
+        code(amber_analysis::associated_function_on_value),
+        help("call it as `{target}::{method}(...)`")
+    )]
+    AssociatedFunctionOnValue {
+        target: String,
+        method: String,
+        #[label("called on a value")]
+        span: SourceSpan,
+    },
+
+    #[error("cannot call a method on a temporary value")]
+    #[diagnostic(
+        code(amber_analysis::temporary_receiver),
+        help("bind the value to a name first")
+    )]
+    TemporaryReceiver {
+        #[label("this value has no address")]
+        span: SourceSpan,
+    },
+
+    #[error("function '{function}' takes {expected} argument(s) but {found} were supplied")]
+    #[diagnostic(code(amber_analysis::argument_count))]
+    ArgumentCountMismatch {
+        function: String,
+        expected: usize,
+        found: usize,
+        #[label("called here")]
+        span: SourceSpan,
+    },
+
+    #[error("function '{function}' does not return a value")]
+    #[diagnostic(code(amber_analysis::void_value))]
+    VoidValue {
+        function: String,
+        #[label("used as a value here")]
+        span: SourceSpan,
+    },
+
+    #[error("cannot assign twice to immutable binding '{name}'")]
+    #[diagnostic(
+        code(amber_analysis::assign_to_immutable),
+        help("declare the binding with `var` to make it mutable")
+    )]
+    AssignToImmutable {
+        name: String,
+        #[label("cannot assign")]
+        span: SourceSpan,
+    },
+
+    #[error("cannot take a mutable address of immutable binding '{name}'")]
+    #[diagnostic(
+        code(amber_analysis::mutable_address_of_immutable),
+        help("declare the binding with `var` to make it mutable")
+    )]
+    MutableAddressOfImmutable {
+        name: String,
+        #[label("immutable binding")]
+        span: SourceSpan,
+    },
+
+    #[error("cannot modify data behind {found}")]
+    #[diagnostic(
+        code(amber_analysis::immutable_pointee),
+        help("use a `*mut` pointer to modify the data it points to")
+    )]
+    ImmutablePointee {
+        found: Type,
+        #[label("pointer does not allow modification")]
+        span: SourceSpan,
+    },
+
+    #[error("cannot take the address of a temporary value")]
+    #[diagnostic(
+        code(amber_analysis::address_of_temporary),
+        help("bind the value to a name first")
+    )]
+    AddressOfTemporary {
+        #[label("this value has no address")]
+        span: SourceSpan,
+    },
+
+    #[error("invalid left-hand side of assignment")]
+    #[diagnostic(code(amber_analysis::invalid_assignment_target))]
+    InvalidAssignmentTarget {
+        #[label("cannot assign to this expression")]
+        span: SourceSpan,
+    },
+
+    #[error("cannot infer a type for '{name}'")]
+    #[diagnostic(
+        code(amber_analysis::cannot_infer_type),
+        help("add a type annotation or an initializer")
+    )]
+    CannotInferType {
+        name: String,
+        #[label("declared here")]
+        span: SourceSpan,
+    },
+
+    #[error("'{name}' is used before a value is assigned to it")]
+    #[diagnostic(
+        code(amber_analysis::used_before_assignment),
+        help("assign a value first, or give the binding an initializer")
+    )]
+    UsedBeforeAssignment {
+        name: String,
+        #[label("read here")]
+        span: SourceSpan,
+    },
+
+    #[error("function '{function}' must return a value of type {expected}")]
+    #[diagnostic(code(amber_analysis::missing_return_value))]
+    MissingReturnValue {
+        function: String,
+        expected: Type,
+        #[label("missing value")]
+        span: SourceSpan,
+    },
+
+    #[error("function '{function}' does not return a value")]
+    #[diagnostic(code(amber_analysis::unexpected_return_value))]
+    UnexpectedReturnValue {
+        function: String,
+        #[label("unexpected value")]
+        span: SourceSpan,
+    },
+
+    #[error("{kind} is not allowed at the top level")]
+    #[diagnostic(code(amber_analysis::top_level_statement))]
+    TopLevelStatement {
+        kind: &'static str,
+        #[label("not allowed here")]
+        span: SourceSpan,
+    },
+
+    #[error("{kind} is only allowed at the top level")]
+    #[diagnostic(code(amber_analysis::nested_declaration))]
+    NestedDeclaration {
+        kind: &'static str,
+        #[label("not allowed here")]
+        span: SourceSpan,
+    },
+
+    #[error("'{name}' is defined multiple times")]
+    #[diagnostic(code(amber_analysis::duplicate_definition))]
+    DuplicateDefinition {
+        name: String,
+        #[label("redefined here")]
+        span: SourceSpan,
+    },
+
+    #[error("field '{field}' is declared multiple times in struct '{struct_name}'")]
+    #[diagnostic(code(amber_analysis::duplicate_field))]
+    DuplicateField {
+        struct_name: String,
+        field: String,
+        #[label("redeclared here")]
+        span: SourceSpan,
+    },
+
+    #[error("struct '{name}' contains itself by value")]
+    #[diagnostic(
+        code(amber_analysis::recursive_struct),
+        help("store a pointer such as `*{name}` instead")
+    )]
+    RecursiveStruct {
+        name: String,
+        #[label("field '{field}' would make the struct infinitely large")]
+        span: SourceSpan,
+        field: String,
+    },
+
+    #[error("`self` parameter is only allowed inside impl blocks (function '{name}')")]
+    #[diagnostic(code(amber_analysis::self_outside_impl))]
+    SelfParamOutsideImpl {
+        name: String,
+        #[label("in this function")]
+        span: SourceSpan,
+    },
+
+    #[error("function '{name}' contains multiple `self` parameters")]
+    #[diagnostic(code(amber_analysis::multiple_self_params))]
+    MultipleSelfParams {
+        name: String,
+        #[label("in this function")]
+        span: SourceSpan,
+    },
+
+    #[error("impl method '{target}::{name}' cannot be declared extern")]
+    #[diagnostic(code(amber_analysis::extern_impl_method))]
+    ExternImplMethod {
+        target: String,
+        name: String,
+        #[label("declared extern here")]
+        span: SourceSpan,
+    },
+
+    #[error("extern function '{name}' cannot have a body")]
+    #[diagnostic(code(amber_analysis::extern_with_body))]
+    ExternFunctionWithBody {
+        name: String,
+        #[label("declared here")]
+        span: SourceSpan,
+    },
+
+    #[error("function '{name}' is missing a body")]
+    #[diagnostic(code(amber_analysis::missing_function_body))]
+    MissingFunctionBody {
+        name: String,
+        #[label("declared here")]
+        span: SourceSpan,
+    },
+
+    #[error("function '{name}' can reach its end without returning a value")]
+    #[diagnostic(
+        code(amber_analysis::missing_return),
+        help("end every path through the body with `return`")
+    )]
+    MissingReturn {
+        name: String,
+        #[label("declared to return {return_type}")]
+        span: SourceSpan,
+        return_type: Type,
+    },
+
+    #[error("array length has not been evaluated at compile time")]
+    #[diagnostic(
+        code(amber_analysis::unresolved_array_length),
+        help("run the comptime pass before analysis")
+    )]
+    UnresolvedArrayLength {
+        #[label("array type used here")]
+        span: SourceSpan,
+    },
+
+    #[error("expected an array of {expected} elements, found {found}")]
+    #[diagnostic(code(amber_analysis::array_length_mismatch))]
+    ArrayLengthMismatch {
+        expected: usize,
+        found: usize,
+        #[label("array literal has {found} elements")]
+        span: SourceSpan,
+    },
+
+    #[error("array literals need at least one element")]
+    #[diagnostic(code(amber_analysis::empty_array_literal))]
+    EmptyArrayLiteral {
+        #[label("empty array literal")]
+        span: SourceSpan,
+    },
+
+    #[error("literal {value} does not fit in {ty}")]
+    #[diagnostic(code(amber_analysis::literal_out_of_range))]
+    LiteralOutOfRange {
+        value: i128,
+        ty: Type,
+        #[label("out of range for {ty}")]
+        span: SourceSpan,
+    },
+
+    #[error("index {index} is out of bounds for an array of length {len}")]
+    #[diagnostic(code(amber_analysis::index_out_of_bounds))]
+    IndexOutOfBounds {
+        index: i128,
+        len: usize,
+        #[label("out of bounds")]
+        span: SourceSpan,
+    },
+
+    #[error("arrays cannot be copied as a whole")]
+    #[diagnostic(
+        code(amber_analysis::array_copy),
+        help("use an array literal or copy individual elements")
+    )]
+    ArrayCopy {
+        #[label("array value used here")]
+        span: SourceSpan,
+    },
+
+    #[error("'{name}' must have an integer underlying type, found {found}")]
+    #[diagnostic(code(amber_analysis::invalid_enum_repr))]
+    InvalidEnumRepr {
+        name: String,
+        found: Type,
+        #[label("in this declaration")]
+        span: SourceSpan,
+    },
+
+    #[error("variant '{variant}' is declared multiple times in '{enum_name}'")]
+    #[diagnostic(code(amber_analysis::duplicate_variant))]
+    DuplicateVariant {
+        enum_name: String,
+        variant: String,
+        #[label("redeclared here")]
+        span: SourceSpan,
+    },
+
+    #[error("discriminant of '{variant}' has not been evaluated at compile time")]
+    #[diagnostic(
+        code(amber_analysis::unresolved_discriminant),
+        help("run the comptime pass before analysis")
+    )]
+    UnresolvedDiscriminant {
+        variant: String,
+        #[label("discriminant given here")]
+        span: SourceSpan,
+    },
+
+    #[error("discriminant {value} of '{variant}' does not fit in {repr}")]
+    #[diagnostic(code(amber_analysis::discriminant_out_of_range))]
+    DiscriminantOutOfRange {
+        variant: String,
+        value: i128,
+        repr: Type,
+        #[label("out of range")]
+        span: SourceSpan,
+    },
+
+    #[error("'{variant}' has the same discriminant {value} as '{other}'")]
+    #[diagnostic(code(amber_analysis::duplicate_discriminant))]
+    DuplicateDiscriminant {
+        variant: String,
+        other: String,
+        value: i64,
+        #[label("duplicate discriminant")]
+        span: SourceSpan,
+    },
+
+    #[error("'{enum_name}' has no variant '{variant}'")]
+    #[diagnostic(code(amber_analysis::unknown_variant))]
+    UnknownVariant {
+        enum_name: String,
+        variant: String,
+        #[label("unknown variant")]
+        span: SourceSpan,
+    },
+
+    #[error("cannot match on values of type {found}")]
+    #[diagnostic(
+        code(amber_analysis::invalid_match_type),
+        help("match works on integers, chars, bools, enums and tagged unions")
+    )]
+    InvalidMatchType {
+        found: Type,
+        #[label("matched value")]
+        span: SourceSpan,
+    },
+
+    #[error("pattern '{pattern}' is already covered by an earlier arm")]
+    #[diagnostic(code(amber_analysis::duplicate_pattern))]
+    DuplicatePattern {
+        pattern: String,
+        #[label("duplicate pattern")]
+        span: SourceSpan,
+    },
+
+    #[error("unreachable match arm")]
+    #[diagnostic(
+        code(amber_analysis::unreachable_arm),
+        help("a `_` arm before it already matches every value")
+    )]
+    UnreachableArm {
+        #[label("never reached")]
+        span: SourceSpan,
+    },
+
+    #[error("match is not exhaustive: {missing} not covered")]
+    #[diagnostic(
+        code(amber_analysis::non_exhaustive_match),
+        help("add arms for the missing values or a `_` arm")
+    )]
+    NonExhaustiveMatch {
+        missing: String,
+        #[label("in this match")]
+        span: SourceSpan,
+    },
+
+    #[error("'{variant}' is a {shape} variant")]
+    #[diagnostic(
+        code(amber_analysis::variant_shape_mismatch),
+        help(
+            "unit variants are written `Name::Variant`, tuple variants `Name::Variant(..)` \
+             and struct variants `Name::Variant {{ .. }}`"
+        )
+    )]
+    VariantShapeMismatch {
+        variant: String,
+        shape: &'static str,
+        #[label("used as a different kind of variant")]
+        span: SourceSpan,
+    },
+
+    #[error("'{variant}' has {expected} payload fields but {found} were given")]
+    #[diagnostic(code(amber_analysis::payload_count_mismatch))]
+    PayloadCountMismatch {
+        variant: String,
+        expected: usize,
+        found: usize,
+        #[label("wrong number of fields")]
+        span: SourceSpan,
+    },
+
+    #[error("'{name}' is bound more than once in the same pattern")]
+    #[diagnostic(code(amber_analysis::duplicate_binding))]
+    DuplicateBinding {
+        name: String,
+        #[label("bound again here")]
+        span: SourceSpan,
+    },
+
+    #[error("payload bindings are not allowed in an arm with several patterns")]
+    #[diagnostic(
+        code(amber_analysis::binding_in_alternative),
+        help("give each variant its own arm")
+    )]
+    BindingInAlternative {
+        #[label("binds payload fields")]
+        span: SourceSpan,
+    },
+
+    #[error("range bounds must be integers, found {found}")]
+    #[diagnostic(code(amber_analysis::non_integer_range))]
+    NonIntegerRange {
+        found: Type,
+        #[label("not an integer")]
+        span: SourceSpan,
+    },
+
+    #[error("type {found} cannot be iterated")]
+    #[diagnostic(
+        code(amber_analysis::not_iterable),
+        help("loop over a range `start..end` or an array")
+    )]
+    NotIterable {
+        found: Type,
+        #[label("iterated here")]
+        span: SourceSpan,
+    },
+
+    #[error("comptime loop has not been unrolled")]
+    #[diagnostic(
+        code(amber_analysis::comptime_loop),
+        help("run the comptime pass before analysis")
+    )]
+    ComptimeLoop {
+        #[label("still a loop")]
+        span: SourceSpan,
+    },
+
+    #[error("comptime if has not been resolved")]
+    #[diagnostic(
+        code(amber_analysis::comptime_branch),
+        help("run the comptime pass before analysis")
+    )]
+    ComptimeBranch {
+        #[label("branch not selected yet")]
+        span: SourceSpan,
+    },
+
+    #[error("'{keyword}' outside of a loop")]
+    #[diagnostic(code(amber_analysis::jump_outside_loop))]
+    JumpOutsideLoop {
+        keyword: &'static str,
+        #[label("not inside a loop")]
+        span: SourceSpan,
+    },
+
+    #[error("use of undeclared label '{label}'")]
+    #[diagnostic(code(amber_analysis::undefined_label))]
+    UndefinedLabel {
+        label: String,
+        #[label("no enclosing loop has this label")]
+        span: SourceSpan,
+    },
+
+    #[error("label '{label}' is already used by an enclosing loop")]
+    #[diagnostic(
+        code(amber_analysis::duplicate_label),
+        help("give the inner loop a different label")
+    )]
+    DuplicateLabel {
+        label: String,
+        #[label("label reused here")]
+        span: SourceSpan,
+    },
+
+    #[error("function '{function}' cannot take or return an array by value")]
+    #[diagnostic(
+        code(amber_analysis::array_by_value),
+        help("pass a pointer to the array instead")
+    )]
+    ArrayByValue {
+        function: String,
+        #[label("in this function")]
+        span: SourceSpan,
+    },
+}
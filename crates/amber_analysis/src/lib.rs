@@ -1,14 +1,1394 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
+mod checker;
+mod error;
+mod scope;
+mod typed;
+
+pub use error::AnalysisError;
+pub use typed::{
+    Callee, TypedBinding, TypedBlock, TypedElseBranch, TypedEnum, TypedExpr, TypedExprKind, TypedForIterable,
+    TypedForLoop, TypedFunction, TypedIfElse, TypedImpl, TypedLoopExits, TypedMatch,
+    TypedMatchArm, TypedPattern, TypedPayloadBinding, TypedProgram, TypedStatement, TypedUnion,
+    TypedUnionVariant, TypedWhileLoop,
+};
+
+use amber_ast::{Program, Type};
+use checker::Checker;
+
+/// Resolve names and check types across a whole program, producing the typed
+/// program consumed by code generation. All detected errors are returned.
+/// Integer literals that nothing gives a type to are `i64`.
+pub fn analyze(program: &Program) -> Result<TypedProgram, Vec<AnalysisError>> {
+    analyze_with(program, Type::I64)
+}
+
+/// Analyze `program` like `analyze`, giving integer literals that nothing
+/// gives a type to the integer type `default_int`
+pub fn analyze_with(
+    program: &Program,
+    default_int: Type,
+) -> Result<TypedProgram, Vec<AnalysisError>> {
+    Checker::with_default_int(default_int).check_program(program)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use amber_ast::Type;
+    use amber_parser::build_ast;
+
+    fn check(code: &str) -> Result<TypedProgram, Vec<AnalysisError>> {
+        analyze(&build_ast(code).unwrap())
+    }
+
+    fn messages(errors: &[AnalysisError]) -> Vec<String> {
+        errors.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_well_typed_program() {
+        let code = r#"
+            struct Point {
+                x: i32,
+                y: i32,
+            }
+
+            impl Point {
+                fn get_x(self, value: i32) -> i32 {
+                    return value;
+                }
+            }
+
+            const LIMIT: i32 = 5;
+
+            fn main() {
+                var counter: i32 = 0;
+                while counter < LIMIT {
+                    counter = counter + 1;
+                }
+                if 10 > counter && true {
+                    const doubled: i32 = counter * 2;
+                }
+            }
+        "#;
+        assert!(check(code).is_ok());
+    }
+
+    #[test]
+    fn test_literals_adopt_context_type() {
+        let program = check("const mask: u8 = 1 << 3;").unwrap();
+        let TypedStatement::Binding(binding) = &program.statements[0] else {
+            panic!("Expected binding");
+        };
+        assert_eq!(binding.value.as_ref().unwrap().ty, Type::U8);
+    }
+
+    #[test]
+    fn test_binding_type_inference() {
+        let code = r#"
+            const LIMIT = 10;
+            const SCALE = 1.5;
+            const UNUSED = 7;
+
+            fn main(count: u16) {
+                const done = LIMIT > count;
+                const ratio: f64 = SCALE;
+            }
+        "#;
+        let program = analyze(&build_ast(code).unwrap()).unwrap();
+        let bindings: Vec<&TypedBinding> = program
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                TypedStatement::Binding(binding) => Some(binding),
+                _ => None,
+            })
+            .collect();
+        let types: Vec<&Type> = bindings.iter().map(|binding| &binding.ty).collect();
+        assert_eq!(types, [&Type::U16, &Type::F64, &Type::I64]);
+        // The initializer is typed again with the type its use decided
+        assert_eq!(bindings[0].value.as_ref().unwrap().ty, Type::U16);
+
+        let program = analyze_with(&build_ast(code).unwrap(), Type::I32).unwrap();
+        let TypedStatement::Binding(unused) = &program.statements[2] else {
+            panic!("Expected binding");
+        };
+        assert_eq!(unused.ty, Type::I32);
+
+        // A float literal cannot become an integer
+        let code = r#"
+            fn main() {
+                const half = 0.5;
+                const n: u8 = half;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec!["mismatched types: expected u8, found f64"]
+        );
+    }
+
+    #[test]
+    fn test_uninitialized_binding_inference() {
+        // A `var` with neither a type nor a value takes one from its first
+        // assignment
+        let code = r#"
+            fn take(value: u8) {}
+            fn main() {
+                var assigned;
+                assigned = 3u16;
+                var passed;
+                passed = 4u8;
+                take(passed);
+            }
+        "#;
+        let program = check(code).unwrap();
+        let TypedStatement::Function(main) = &program.statements[1] else {
+            panic!("Expected function");
+        };
+        let types: Vec<&Type> = main
+            .body
+            .as_ref()
+            .unwrap()
+            .statements
+            .iter()
+            .filter_map(|statement| match statement {
+                TypedStatement::Binding(binding) => Some(&binding.ty),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(types, [&Type::U16, &Type::U8]);
+
+        // Reading the binding before that is reported once, and a binding
+        // never assigned has no type at all. Only the order of the statements
+        // is checked: an assignment in one branch of an `if` still counts for
+        // the reads after the `if`.
+        let code = r#"
+            fn take(value: u8) {}
+            fn main() {
+                var y;
+                const same = y == y;
+                var read;
+                take(read);
+                read = 1;
+                var never;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "'y' is used before a value is assigned to it",
+                "'read' is used before a value is assigned to it",
+                "cannot infer a type for 'never'"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_mismatch() {
+        let code = r#"
+            fn main() {
+                const a: u8 = 1;
+                const b: u32 = a;
+                const c: bool = a + 1;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "mismatched types: expected u32, found u8",
+                "mismatched types: expected bool, found u8",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undefined_identifier_and_immutable_assignment() {
+        let code = r#"
+            fn main() {
+                const a: i32 = missing;
+                const b: i32 = 1;
+                b = 2;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "cannot find 'missing' in this scope",
+                "cannot assign twice to immutable binding 'b'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_return_types() {
+        let code = r#"
+            fn value() -> i32 {
+                return true;
+            }
+
+            fn nothing() {
+                return 1;
+            }
+
+            fn missing() -> u8 {
+                return;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "mismatched types: expected i32, found bool",
+                "function 'nothing' does not return a value",
+                "function 'missing' must return a value of type u8",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_missing_return() {
+        let code = r#"
+            enum Mode {
+                Idle,
+                Busy,
+            }
+
+            fn pick(flag: bool) -> u8 {
+                if flag {
+                    return 1;
+                } else if !flag {
+                    return 2;
+                } else {
+                    return 3;
+                }
+            }
+
+            fn rank(mode: Mode) -> u8 {
+                match mode {
+                    Mode::Idle => {
+                        return 0;
+                    }
+                    Mode::Busy => {
+                        return 1;
+                    }
+                }
+            }
+
+            fn spin() -> u8 {
+                while true {
+                    while true {
+                        break;
+                    }
+                }
+            }
+        "#;
+        assert!(check(code).is_ok());
+
+        let code = r#"
+            fn empty() -> u8 {}
+
+            fn one_branch(flag: bool) -> u8 {
+                if flag {
+                    return 1;
+                }
+            }
+
+            fn escapes() -> u8 {
+                while true {
+                    break;
+                }
+            }
+
+            fn looped(n: u8) -> u8 {
+                while n > 0 {
+                    return n;
+                }
+            }
+
+            fn nothing() {}
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "function 'empty' can reach its end without returning a value",
+                "function 'one_branch' can reach its end without returning a value",
+                "function 'escapes' can reach its end without returning a value",
+                "function 'looped' can reach its end without returning a value",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_operand_and_type_errors() {
+        let code = r#"
+            struct Point {
+                x: Missing,
+            }
+
+            fn main(flag: bool, value: i32) {
+                const a: i32 = -flag;
+                const b: bool = flag < true;
+                const c: i32 = *value;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(errors.len(), 4);
+        assert_eq!(errors[0].to_string(), "unknown type 'Missing'");
+        assert!(matches!(
+            errors[1],
+            AnalysisError::InvalidUnaryOperand { op: "-", .. }
+        ));
+        assert!(matches!(
+            errors[2],
+            AnalysisError::InvalidBinaryOperands { op: "<", .. }
+        ));
+        assert!(matches!(
+            errors[3],
+            AnalysisError::InvalidUnaryOperand { op: "*", .. }
+        ));
+    }
+
+    #[test]
+    fn test_top_level_rules() {
+        let code = r#"
+            const a: i32 = 1;
+            const a: i32 = 2;
+            fn main() {}
+            fn main() {}
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "'a' is defined multiple times",
+                "'main' is defined multiple times",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_errors_point_at_source() {
+        let code = "fn main() {\n    const flag: bool = 1 + missing;\n}";
+        let errors = check(code).unwrap_err();
+        let AnalysisError::UndefinedIdentifier { span, .. } = &errors[0] else {
+            panic!("Expected undefined identifier, got {:?}", errors[0]);
+        };
+        assert_eq!(&code[span.offset()..span.offset() + span.len()], "missing");
+    }
+
+    #[test]
+    fn test_function_calls() {
+        let code = r#"
+            extern fn HAL_Delay(ms: u32);
+
+            fn main() {
+                HAL_Delay(100);
+                const total: i32 = add(1, 2) * 3;
+            }
+
+            fn add(a: i32, b: i32) -> i32 {
+                return a + b;
+            }
+        "#;
+        let program = check(code).unwrap();
+        let TypedStatement::Function(main) = &program.statements[1] else {
+            panic!("Expected function");
+        };
+        let body = main.body.as_ref().unwrap();
+        let TypedStatement::ExprStatement(call) = &body.statements[0] else {
+            panic!("Expected call statement");
+        };
+        assert_eq!(call.ty, Type::Void);
+        let TypedExprKind::Call { callee, args } = &call.kind else {
+            panic!("Expected call, got {:?}", call.kind);
+        };
+        assert_eq!(*callee, Callee::Function("HAL_Delay".to_string()));
+        // The literal argument adopts the parameter type
+        assert_eq!(args[0].ty, Type::U32);
+    }
+
+    #[test]
+    fn test_call_errors() {
+        let code = r#"
+            fn add(a: i32, b: i32) -> i32 {
+                return a + b;
+            }
+
+            fn tick() {}
+
+            fn main() {
+                const x: i32 = 1;
+                add(1);
+                add(1, true);
+                x(2);
+                missing();
+                const nothing = tick();
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "function 'add' takes 2 argument(s) but 1 were supplied",
+                "mismatched types: expected i32, found bool",
+                "'x' is not a function",
+                "cannot find 'missing' in this scope",
+                "function 'tick' does not return a value",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_method_calls() {
+        let code = r#"
+            struct Point {
+                x: i32,
+                y: i32,
+            }
+
+            impl Point {
+                fn origin() -> i32 {
+                    return 0;
+                }
+
+                fn sum(self, a: i32, b: i32) -> i32 {
+                    return a + b;
+                }
+            }
+
+            fn main() {
+                var p: Point;
+                const a: i32 = p.sum(1, 2);
+                const b: i32 = Point::origin();
+            }
+        "#;
+        let program = check(code).unwrap();
+        let TypedStatement::Function(main) = &program.statements[2] else {
+            panic!("Expected function");
+        };
+        let body = main.body.as_ref().unwrap();
+        let TypedStatement::Binding(binding) = &body.statements[1] else {
+            panic!("Expected binding");
+        };
+        let TypedExprKind::Call { callee, args } = &binding.value.as_ref().unwrap().kind else {
+            panic!("Expected call");
+        };
+        assert_eq!(
+            *callee,
+            Callee::Method {
+                target: "Point".to_string(),
+                name: "sum".to_string(),
+            }
+        );
+        // The receiver's address is passed as `self`
+        assert_eq!(args.len(), 3);
+        assert!(matches!(
+            args[0].kind,
+            TypedExprKind::AddressOf { is_mut: false, .. }
+        ));
+
+        let TypedStatement::Binding(binding) = &body.statements[2] else {
+            panic!("Expected binding");
+        };
+        let TypedExprKind::Call { callee, args } = &binding.value.as_ref().unwrap().kind else {
+            panic!("Expected call");
+        };
+        assert_eq!(
+            *callee,
+            Callee::Method {
+                target: "Point".to_string(),
+                name: "origin".to_string(),
+            }
+        );
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn test_method_receiver_mutability() {
+        let code = r#"
+            struct Point {
+                x: i32,
+            }
+
+            impl Point {
+                fn sum(self, a: i32, b: i32) -> i32 {
+                    return self.x + a + b;
+                }
+
+                fn shift(mut self, dx: i32) {
+                    self.x = self.x + dx;
+                }
+            }
+
+            fn main() {
+                const p: Point = Point { x: 1 };
+                var q: Point = Point { x: 2 };
+                const a: i32 = p.sum(1, 2);
+                q.shift(a);
+                const r: *Point = &q;
+                const b: i32 = r.sum(3, 4);
+            }
+        "#;
+        let program = check(code).unwrap();
+        let TypedStatement::Function(main) = &program.statements[2] else {
+            panic!("Expected function");
+        };
+        let body = main.body.as_ref().unwrap();
+        let TypedStatement::Binding(binding) = &body.statements[2] else {
+            panic!("Expected binding");
+        };
+        let TypedExprKind::Call { args, .. } = &binding.value.as_ref().unwrap().kind else {
+            panic!("Expected call");
+        };
+        assert!(matches!(
+            args[0].kind,
+            TypedExprKind::AddressOf { is_mut: false, .. }
+        ));
+        let TypedStatement::ExprStatement(call) = &body.statements[3] else {
+            panic!("Expected expression statement");
+        };
+        let TypedExprKind::Call { args, .. } = &call.kind else {
+            panic!("Expected call");
+        };
+        assert!(matches!(
+            args[0].kind,
+            TypedExprKind::AddressOf { is_mut: true, .. }
+        ));
+
+        let code = r#"
+            struct Point {
+                x: i32,
+            }
+
+            impl Point {
+                fn shift(mut self, dx: i32) {
+                    self.x = self.x + dx;
+                }
+
+                fn reset(self) {
+                    self.x = 0;
+                }
+            }
+
+            fn main() {
+                const p: Point = Point { x: 1 };
+                var q: Point = Point { x: 2 };
+                const r: *Point = &q;
+                p.shift(1);
+                r.shift(1);
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "cannot modify data behind *Point",
+                "cannot take a mutable address of immutable binding 'p'",
+                "cannot modify data behind *Point",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_method_call_errors() {
+        let code = r#"
+            struct Point {
+                x: i32,
+            }
+
+            impl Point {
+                fn origin() -> i32 {
+                    return 0;
+                }
+
+                fn get(self) -> i32 {
+                    return 0;
+                }
+            }
+
+            fn main() {
+                var p: Point;
+                const n: i32 = 1;
+                p.missing();
+                p.origin();
+                Point::get();
+                n.get();
+                Point::get;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "no method named 'missing' found for type 'Point'",
+                "'Point::origin' is an associated function, not a method",
+                "'Point::get' takes `self` and must be called on a value",
+                "type i32 has no methods",
+                "cannot find 'Point::get' in this scope",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_struct_fields() {
+        let code = r#"
+            struct Point {
+                x: i32,
+                y: i32,
+            }
+
+            fn shift(p: *mut Point) {
+                p.x = p.x + 1;
+            }
+
+            fn main() {
+                var p = Point { y: 2, x: 1 };
+                p.y = 3;
+            }
+        "#;
+        let program = check(code).unwrap();
+        let TypedStatement::Function(shift) = &program.statements[1] else {
+            panic!("Expected function");
+        };
+        let TypedStatement::Assignment { target, .. } = &shift.body.as_ref().unwrap().statements[0]
+        else {
+            panic!("Expected assignment");
+        };
+        assert_eq!(target.ty, Type::I32);
+        assert!(matches!(
+            target.kind,
+            TypedExprKind::Field {
+                through_pointer: true,
+                ..
+            }
+        ));
+
+        let TypedStatement::Function(main) = &program.statements[2] else {
+            panic!("Expected function");
+        };
+        let TypedStatement::Binding(binding) = &main.body.as_ref().unwrap().statements[0] else {
+            panic!("Expected binding");
+        };
+        let value = binding.value.as_ref().unwrap();
+        assert_eq!(value.ty, Type::Named("Point".to_string()));
+        let TypedExprKind::StructLiteral { fields, .. } = &value.kind else {
+            panic!("Expected struct literal");
+        };
+        // Literals adopt the field types
+        assert!(fields.iter().all(|(_, value)| value.ty == Type::I32));
+    }
+
+    #[test]
+    fn test_struct_field_errors() {
+        let code = r#"
+            struct Point {
+                x: i32,
+                y: i32,
+            }
+
+            fn main() {
+                const p = Point { x: 1, y: 2 };
+                const n: i32 = 1;
+                const a: i32 = p.z;
+                const b: i32 = n.x;
+                p.x = 3;
+                const c = Point { x: 1 };
+                const d = Point { x: 1, x: 2, y: 3 };
+                const e = Point { x: 1, y: true };
+                const f = Size { w: 1 };
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "struct 'Point' has no field named 'z'",
+                "type i32 has no fields",
+                "cannot assign twice to immutable binding 'p'",
+                "missing fields 'y' in initializer of struct 'Point'",
+                "field 'x' of struct 'Point' is initialized twice",
+                "mismatched types: expected i32, found bool",
+                "unknown type 'Size'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recursive_structs() {
+        let code = r#"
+            struct Node {
+                value: i32,
+                next: *Node,
+            }
+
+            struct List {
+                head: Node,
+                nodes: [4]*List,
+            }
+        "#;
+        assert!(check(code).is_ok());
+
+        let code = r#"
+            struct S {
+                s: S,
+            }
+
+            struct Row {
+                cells: [2]Cell,
+            }
+
+            struct Cell {
+                row: Row,
+            }
+
+            union Shape {
+                Group(Group),
+                Empty,
+            }
+
+            struct Group {
+                first: Shape,
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "struct 'S' contains itself by value",
+                "struct 'Row' contains itself by value",
+                "struct 'Cell' contains itself by value",
+                "struct 'Group' contains itself by value",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_address_of() {
+        let program = check("fn main() {\n    var x: i32 = 1;\n    const p = &mut x;\n}").unwrap();
+        let TypedStatement::Function(main) = &program.statements[0] else {
+            panic!("Expected function");
+        };
+        let TypedStatement::Binding(binding) = &main.body.as_ref().unwrap().statements[1] else {
+            panic!("Expected binding");
+        };
+        assert_eq!(
+            binding.value.as_ref().unwrap().ty,
+            Type::Pointer {
+                inner: Box::new(Type::I32),
+                is_mut: true,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pointer_mutability_errors() {
+        let code = r#"
+            struct Point {
+                x: i32,
+            }
+
+            impl Point {
+                fn reset(mut self) {}
+            }
+
+            fn main() {
+                const x: i32 = 1;
+                var y: i32 = 2;
+                const p = Point { x: 1 };
+                const ro: *i32 = &y;
+                const rp: *Point = &p;
+                const a = &mut x;
+                const b = &(1 + 2);
+                *ro = 3;
+                rp.x = 4;
+                p.reset();
+                rp.reset();
+                const c: *mut i32 = &y;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "cannot take a mutable address of immutable binding 'x'",
+                "cannot take the address of a temporary value",
+                "cannot modify data behind *i32",
+                "cannot modify data behind *Point",
+                "cannot take a mutable address of immutable binding 'p'",
+                "cannot modify data behind *Point",
+                "mismatched types: expected *mut i32, found *i32",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_arrays() {
+        let code = r#"
+            struct Buffer {
+                data: [4]u8,
+            }
+
+            fn fill(dst: *mut [4]u8) {
+                (*dst)[0] = 1;
+            }
+
+            fn main() {
+                var buf: [4]u8 = [1, 2, 3, 4];
+                const grid = [[1, 2], [3, 4]];
+                const ptrs: [2]*u8 = [&buf[0], &buf[1]];
+                const b = Buffer { data: [0, 0, 0, 0] };
+                buf[3] = b.data[0] + buf[grid[1][0]];
+                fill(&mut buf);
+            }
+        "#;
+        let program = check(code).unwrap();
+        let TypedStatement::Function(main) = &program.statements[2] else {
+            panic!("Expected function");
+        };
+        let TypedStatement::Binding(grid) = &main.body.as_ref().unwrap().statements[1] else {
+            panic!("Expected binding");
+        };
+        assert_eq!(grid.value.as_ref().unwrap().ty.to_string(), "[2][2]i64");
+    }
+
+    #[test]
+    fn test_array_errors() {
+        let code = r#"
+            fn first(values: [4]u8) -> u8 {
+                return values[0];
+            }
+
+            fn main() {
+                var buf: [4]u8 = [1, 2, 3];
+                var other: [4]u8 = [1, 2, 3, 4];
+                const copy: [4]u8 = other;
+                const empty = [];
+                const x = other[4];
+                const y = other[-1];
+                other = [5, 6, 7, 8];
+                const mixed = [1, true];
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "function 'first' cannot take or return an array by value",
+                "expected an array of 4 elements, found 3",
+                "arrays cannot be copied as a whole",
+                "array literals need at least one element",
+                "index 4 is out of bounds for an array of length 4",
+                "index -1 is out of bounds for an array of length 4",
+                "arrays cannot be copied as a whole",
+                "mismatched types: expected i64, found bool",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_enums_and_match() {
+        let code = r#"
+            enum State: u8 {
+                Idle,
+                Running = 4,
+                Done,
+            }
+
+            enum Level {
+                Low = -1,
+                High,
+            }
+
+            fn next(state: State) -> State {
+                var result: State = State::Idle;
+                match state {
+                    State::Idle => {
+                        result = State::Running;
+                    }
+                    State::Running | State::Done => {
+                        result = State::Done;
+                    }
+                }
+                return result;
+            }
+
+            fn describe(code: i32, flag: bool) {
+                match code {
+                    -1 | 0 => {}
+                    _ => {}
+                }
+                match flag {
+                    true => {}
+                    false => {}
+                }
+                const same: bool = next(State::Idle) == State::Running;
+            }
+        "#;
+        let program = check(code).unwrap();
+        let TypedStatement::Enum(state) = &program.statements[0] else {
+            panic!("Expected enum");
+        };
+        assert_eq!(state.repr, Some(Type::U8));
+        assert_eq!(
+            state.variants,
+            [
+                ("Idle".to_string(), 0),
+                ("Running".to_string(), 4),
+                ("Done".to_string(), 5)
+            ]
+        );
+        let TypedStatement::Enum(level) = &program.statements[1] else {
+            panic!("Expected enum");
+        };
+        assert_eq!(level.variants[1], ("High".to_string(), 0));
+    }
+
+    #[test]
+    fn test_enum_errors() {
+        let code = r#"
+            enum Bad: f32 { A }
+            enum Small: u8 { A = 255, B }
+            enum Twice { A = 1, B = 1 }
+            enum Repeat { A, A }
+            enum State { Idle, Done }
+
+            fn main() {
+                const s: State = State::Missing;
+                const t: State = Other::Idle;
+                const u = State::Idle.value;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "'Bad' must have an integer underlying type, found f32",
+                "discriminant 256 of 'Small::B' does not fit in u8",
+                "'Twice::B' has the same discriminant 1 as 'Twice::A'",
+                "variant 'A' is declared multiple times in 'Repeat'",
+                "'State' has no variant 'Missing'",
+                "cannot find 'Other::Idle' in this scope",
+                "type State has no fields",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_match_errors() {
+        let code = r#"
+            enum State { Idle, Running, Done }
+
+            fn main(state: State, code: u8, ratio: f32) {
+                match state {
+                    State::Idle => {}
+                }
+                match state {
+                    State::Idle => {}
+                    State::Idle => {}
+                    _ => {}
+                }
+                match code {
+                    1 => {}
+                }
+                match code {
+                    _ => {}
+                    1 => {}
+                }
+                match code {
+                    true => {}
+                    _ => {}
+                }
+                match ratio {
+                    _ => {}
+                }
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "match is not exhaustive: 'State::Running', 'State::Done' not covered",
+                "pattern 'State::Idle' is already covered by an earlier arm",
+                "match is not exhaustive: '_' not covered",
+                "unreachable match arm",
+                "mismatched types: expected u8, found bool",
+                "cannot match on values of type f32",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unions_and_destructuring() {
+        let code = r#"
+            union Packet: u8 {
+                Ping,
+                Data(u8, *u8),
+                Move { x: i32, y: i32 },
+            }
+
+            fn make(len: u8, bytes: *u8) -> Packet {
+                const ping: Packet = Packet::Ping;
+                const data = Packet::Data(len, bytes);
+                return Packet::Move { y: 2, x: 1 };
+            }
+
+            fn handle(packet: Packet) -> i32 {
+                match packet {
+                    Packet::Data(len, _) => {
+                        const copy: u8 = len;
+                    }
+                    Packet::Move { x, y: dy } => {
+                        return x + dy;
+                    }
+                    Packet::Ping => {}
+                }
+                return 0;
+            }
+        "#;
+        let program = check(code).unwrap();
+        let TypedStatement::Union(packet) = &program.statements[0] else {
+            panic!("Expected union");
+        };
+        assert_eq!(packet.repr, Some(Type::U8));
+        let tags: Vec<_> = packet
+            .variants
+            .iter()
+            .map(|v| (v.name.as_str(), v.tag))
+            .collect();
+        assert_eq!(tags, [("Ping", 0), ("Data", 1), ("Move", 2)]);
+        let fields: Vec<_> = packet.variants[1]
+            .fields
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(fields, ["_0", "_1"]);
+
+        let TypedStatement::Function(handle) = &program.statements[2] else {
+            panic!("Expected function");
+        };
+        let TypedStatement::Match(match_stmt) = &handle.body.as_ref().unwrap().statements[0]
+        else {
+            panic!("Expected match");
+        };
+        assert!(match_stmt.tagged);
+        let TypedPattern::Variant { bindings, .. } = &match_stmt.arms[1].patterns[0] else {
+            panic!("Expected variant pattern");
+        };
+        assert_eq!(
+            bindings[1],
+            TypedPayloadBinding {
+                field: "y".to_string(),
+                name: "dy".to_string(),
+                ty: Type::I32,
+            }
+        );
+    }
+
+    #[test]
+    fn test_union_errors() {
+        let code = r#"
+            union Tiny: u8 { A, B(u8), A }
+            union Packet { Ping, Data(u8, u8), Move { x: i32 }, Raw([4]u8) }
+            enum State { Idle }
+
+            fn main(packet: Packet) {
+                const a = Packet::Data;
+                const b = Packet::Data(1);
+                const c = Packet::Move { x: 1, z: 2 };
+                const d = Packet::Ping(1);
+                const e = packet.tag;
+                match packet {
+                    Packet::Ping => {}
+                }
+                match packet {
+                    Packet::Data(x, x) => {}
+                    _ => {}
+                }
+                match packet {
+                    Packet::Data(len, _) | Packet::Ping => {}
+                    _ => {}
+                }
+                match packet {
+                    Packet::Move(x) => {}
+                    _ => {}
+                }
+                match packet {
+                    Packet::Raw(bytes) => {}
+                    _ => {}
+                }
+                match packet {
+                    State::Idle(x) => {}
+                    _ => {}
+                }
+                const inner: i32 = x;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "variant 'A' is declared multiple times in 'Tiny'",
+                "'Packet::Data' is a tuple variant",
+                "'Packet::Data' has 2 payload fields but 1 were given",
+                "struct 'Packet::Move' has no field named 'z'",
+                "'Packet::Ping' is a unit variant",
+                "type Packet has no fields",
+                "match is not exhaustive: 'Packet::Data', 'Packet::Move', 'Packet::Raw' not covered",
+                "'x' is bound more than once in the same pattern",
+                "payload bindings are not allowed in an arm with several patterns",
+                "'Packet::Move' is a struct variant",
+                "arrays cannot be copied as a whole",
+                "'State::Idle' is a unit variant",
+                "cannot find 'x' in this scope",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_for_loops() {
+        let code = r#"
+            fn sum(count: u16) -> u32 {
+                var total: u32 = 0;
+                for i in 0..count {
+                    total = total + 1;
+                }
+                const table: [3]u32 = [1, 2, 3];
+                for x in table {
+                    total = total + x;
+                }
+                for i in 0..4 {}
+                return total;
+            }
+        "#;
+        let program = check(code).unwrap();
+        let TypedStatement::Function(sum) = &program.statements[0] else {
+            panic!("Expected function");
+        };
+        let body = &sum.body.as_ref().unwrap().statements;
+        let TypedStatement::ForLoop(range) = &body[1] else {
+            panic!("Expected for loop");
+        };
+        // The literal bound adopts the other bound's type
+        assert_eq!(range.ty, Type::U16);
+        let TypedForIterable::Range { start, .. } = &range.iterable else {
+            panic!("Expected range");
+        };
+        assert_eq!(start.ty, Type::U16);
+        let TypedStatement::ForLoop(array) = &body[3] else {
+            panic!("Expected for loop");
+        };
+        assert_eq!(array.ty, Type::U32);
+        assert!(matches!(array.iterable, TypedForIterable::Array(_)));
+    }
+
+    #[test]
+    fn test_for_loop_errors() {
+        let code = r#"
+            fn main(flag: bool, small: u8, big: u32) {
+                const grid = [[1, 2], [3, 4]];
+                for i in 0..flag {}
+                for i in small..big {}
+                for x in big {}
+                for row in grid {}
+                for i in 0..4 {
+                    i = 2;
+                }
+                comptime for i in 0..4 {}
+                const after = i;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "range bounds must be integers, found bool",
+                "mismatched types: expected u8, found u32",
+                "type u32 cannot be iterated",
+                "arrays cannot be copied as a whole",
+                "cannot assign twice to immutable binding 'i'",
+                "comptime loop has not been unrolled",
+                "cannot find 'i' in this scope",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_break_and_continue() {
+        let code = r#"
+            enum Step { Skip, Stop }
+
+            fn run(step: Step, flag: bool) {
+                outer: while flag {
+                    for i in 0..4 {
+                        if flag {
+                            continue;
+                        }
+                        continue outer;
+                    }
+                    match step {
+                        Step::Skip => {
+                            continue;
+                        }
+                        Step::Stop => {
+                            break;
+                        }
+                    }
+                }
+            }
+        "#;
+        let program = check(code).unwrap();
+        let TypedStatement::Function(run) = &program.statements[1] else {
+            panic!("Expected function");
+        };
+        let TypedStatement::WhileLoop(outer) = &run.body.as_ref().unwrap().statements[0] else {
+            panic!("Expected while loop");
+        };
+        // A labeled jump past the inner loop and a `break` out of a match
+        // need the outer loop's labels
+        assert!(outer.exits.break_label && outer.exits.continue_label);
+        let TypedStatement::ForLoop(inner) = &outer.block.statements[0] else {
+            panic!("Expected for loop");
+        };
+        assert_ne!(inner.exits.id, outer.exits.id);
+        assert!(!inner.exits.break_label);
+        assert_eq!(
+            inner.block.statements[1],
+            TypedStatement::Continue(Some(outer.exits.id))
+        );
+        let TypedStatement::Match(match_stmt) = &outer.block.statements[1] else {
+            panic!("Expected match");
+        };
+        // `continue` is not caught by a `switch`, `break` is
+        assert_eq!(
+            match_stmt.arms[0].body.statements[0],
+            TypedStatement::Continue(None)
+        );
+        assert_eq!(
+            match_stmt.arms[1].body.statements[0],
+            TypedStatement::Break(Some(outer.exits.id))
+        );
+    }
+
+    #[test]
+    fn test_jump_errors() {
+        let code = r#"
+            fn main(flag: bool) {
+                break;
+                if flag {
+                    continue;
+                }
+                outer: while flag {
+                    break inner;
+                    outer: while flag {}
+                }
+                inner: while flag {}
+                continue inner;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "'break' outside of a loop",
+                "'continue' outside of a loop",
+                "use of undeclared label 'inner'",
+                "label 'outer' is already used by an enclosing loop",
+                "use of undeclared label 'inner'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unresolved_comptime_if() {
+        let code = r#"
+            fn main(flag: bool) {
+                comptime if true {}
+                if flag {} else comptime if true {}
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "comptime if has not been resolved",
+                "comptime if has not been resolved",
+            ]
+        );
+    }
 
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn test_literal_range() {
+        let code = r#"
+            fn main() {
+                const low: i8 = -128;
+                const mask: u32 = 0xFFFF_0000;
+                const byte = 0xFFu8;
+                const big: u8 = 300;
+                const under: i8 = -129;
+                const negative: u16 = -1;
+                const suffixed = 256u8;
+                const wide: u64 = 18446744073709551615;
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "literal 300 does not fit in u8",
+                "literal -129 does not fit in i8",
+                "literal -1 does not fit in u16",
+                "literal 256 does not fit in u8",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_string_literals() {
+        let code = r#"
+            extern fn log(message: str);
+            extern fn puts(text: *u8) -> i32;
+
+            fn main() {
+                log("ready");
+                const written = puts(c"ready");
+                const len = "ready".len;
+                const first = "ready".ptr[0];
+            }
+        "#;
+        let program = check(code).unwrap();
+        // Both literals share one copy of their contents
+        assert_eq!(program.strings, ["ready"]);
+        assert!(program.uses_str);
+
+        let program = check(r#"extern fn puts(text: *u8) -> i32;"#).unwrap();
+        assert!(program.strings.is_empty());
+        assert!(!program.uses_str);
+
+        let code = r#"
+            extern fn log(message: str);
+            extern fn puts(text: *u8) -> i32;
+
+            fn main() {
+                log(c"ready");
+                const written = puts("ready");
+                const size = "ready".size;
+                const joined = "a" + "b";
+            }
+        "#;
+        let errors = check(code).unwrap_err();
+        assert_eq!(
+            messages(&errors),
+            vec![
+                "mismatched types: expected str, found *u8",
+                "mismatched types: expected *u8, found str",
+                "struct 'str' has no field named 'size'",
+                "operator '+' cannot be applied to str and str",
+            ]
+        );
     }
 }
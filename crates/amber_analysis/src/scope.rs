@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use amber_ast::Type;
+
+/// What the checker knows about a name bound in a scope
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarInfo {
+    pub ty: Type,
+    pub is_mutable: bool,
+    /// Set while the type of an unannotated binding can still be decided by
+    /// how the binding is used; indexes the checker's `inference.types`
+    pub inferred: Option<usize>,
+}
+
+/// Lexical scope stack; the first frame holds global bindings
+pub struct Scope {
+    frames: Vec<HashMap<String, VarInfo>>,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Self {
+            frames: vec![HashMap::new()],
+        }
+    }
+}
+
+impl Scope {
+    pub fn push(&mut self) {
+        self.frames.push(HashMap::new());
+    }
+
+    pub fn pop(&mut self) {
+        if self.frames.len() > 1 {
+            self.frames.pop();
+        }
+    }
+
+    pub fn define(&mut self, name: &str, info: VarInfo) {
+        self.frames
+            .last_mut()
+            .expect("scope always has a global frame")
+            .insert(name.to_string(), info);
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&VarInfo> {
+        self.frames.iter().rev().find_map(|frame| frame.get(name))
+    }
+
+    pub fn lookup_mut(&mut self, name: &str) -> Option<&mut VarInfo> {
+        self.frames
+            .iter_mut()
+            .rev()
+            .find_map(|frame| frame.get_mut(name))
+    }
+}
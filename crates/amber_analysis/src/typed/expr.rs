@@ -0,0 +1,87 @@
+use amber_ast::{BinaryOp, Literal, Prefix, Span, Type};
+
+/// An expression whose type has been resolved by the checker
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedExpr {
+    pub kind: TypedExprKind,
+    pub ty: Type,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedExprKind {
+    Literal(Literal),
+    /// String literal, a `str` or a C string `*u8`, whose bytes are
+    /// `TypedProgram::strings[index]`
+    StringLiteral {
+        index: usize,
+        value: String,
+    },
+    Identifier(String),
+    Prefix {
+        op: Prefix,
+        expr: Box<TypedExpr>,
+    },
+    /// Address of a place, e.g. the receiver passed as `self`
+    AddressOf {
+        expr: Box<TypedExpr>,
+        is_mut: bool,
+    },
+    Index {
+        base: Box<TypedExpr>,
+        index: Box<TypedExpr>,
+    },
+    Binary {
+        left: Box<TypedExpr>,
+        op: BinaryOp,
+        right: Box<TypedExpr>,
+    },
+    Ternary {
+        condition: Box<TypedExpr>,
+        then_expr: Box<TypedExpr>,
+        else_expr: Box<TypedExpr>,
+    },
+    ArrayLiteral(Vec<TypedExpr>),
+    /// `Enum::Variant`
+    EnumVariant {
+        enum_name: String,
+        variant: String,
+    },
+    /// `base.field`, or `base->field` when `through_pointer` is set
+    Field {
+        base: Box<TypedExpr>,
+        field: String,
+        through_pointer: bool,
+    },
+    /// Struct value with its fields in source order
+    StructLiteral {
+        name: String,
+        fields: Vec<(String, TypedExpr)>,
+    },
+    /// Tagged-union value with the payload fields in source order; tuple
+    /// payloads use the field names `_0`, `_1`, ...
+    UnionLiteral {
+        union_name: String,
+        variant: String,
+        fields: Vec<(String, TypedExpr)>,
+    },
+    /// Call with all arguments in C order; a method receiver is passed as
+    /// the first argument
+    Call {
+        callee: Callee,
+        args: Vec<TypedExpr>,
+    },
+}
+
+/// Function resolved as the target of a call
+#[derive(Debug, Clone, PartialEq)]
+pub enum Callee {
+    Function(String),
+    Method { target: String, name: String },
+}
+
+impl TypedExpr {
+    pub fn new(kind: TypedExprKind, ty: Type, span: Span) -> Self {
+        Self { kind, ty, span }
+    }
+}
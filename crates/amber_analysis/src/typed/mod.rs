@@ -0,0 +1,10 @@
+mod expr;
+mod stmt;
+
+pub use expr::{Callee, TypedExpr, TypedExprKind};
+pub use stmt::{
+    TypedBinding, TypedBlock, TypedElseBranch, TypedEnum, TypedForIterable, TypedForLoop, TypedFunction,
+    TypedIfElse, TypedImpl, TypedLoopExits, TypedMatch, TypedMatchArm, TypedPattern,
+    TypedPayloadBinding, TypedProgram, TypedStatement, TypedUnion, TypedUnionVariant,
+    TypedWhileLoop,
+};
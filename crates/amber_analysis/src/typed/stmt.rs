@@ -0,0 +1,183 @@
+use amber_ast::{Modifier, Param, Span, StructDef, Type};
+
+use crate::typed::TypedExpr;
+
+/// A program that passed semantic analysis, ready for code generation
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedProgram {
+    pub statements: Vec<TypedStatement>,
+    /// Contents of the string literals, each stored once
+    pub strings: Vec<String>,
+    /// Whether anything has the type `str`, which C needs a typedef for
+    pub uses_str: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedBlock {
+    pub statements: Vec<TypedStatement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedStatement {
+    Binding(TypedBinding),
+    IfElse(TypedIfElse),
+    WhileLoop(TypedWhileLoop),
+    ForLoop(TypedForLoop),
+    Match(TypedMatch),
+    Block(TypedBlock),
+    ExprStatement(TypedExpr),
+    Struct(StructDef),
+    Enum(TypedEnum),
+    Union(TypedUnion),
+    Function(TypedFunction),
+    Impl(TypedImpl),
+    Assignment {
+        target: TypedExpr,
+        value: TypedExpr,
+    },
+    Return(Option<TypedExpr>),
+    /// `Some(id)` jumps past the end of loop `id`, which a plain C `break`
+    /// cannot reach
+    Break(Option<usize>),
+    /// `Some(id)` jumps to the end of the body of loop `id`, which a plain C
+    /// `continue` cannot reach
+    Continue(Option<usize>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedBinding {
+    pub modifier: Option<Modifier>,
+    pub is_mutable: bool,
+    pub name: String,
+    /// Type as written in the source, or else as inferred
+    pub ty: Type,
+    pub value: Option<TypedExpr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedIfElse {
+    pub condition: TypedExpr,
+    pub then_block: TypedBlock,
+    pub else_branch: Option<TypedElseBranch>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedElseBranch {
+    If(Box<TypedIfElse>),
+    Block(TypedBlock),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedWhileLoop {
+    pub condition: TypedExpr,
+    pub block: TypedBlock,
+    pub exits: TypedLoopExits,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedForLoop {
+    pub variable: String,
+    /// Type of the loop variable
+    pub ty: Type,
+    pub iterable: TypedForIterable,
+    pub block: TypedBlock,
+    pub exits: TypedLoopExits,
+}
+
+/// Jump targets a loop needs for the `break`/`continue` statements that
+/// cannot use the plain C statements
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TypedLoopExits {
+    /// Unique among the loops of a program
+    pub id: usize,
+    /// Some `break` jumps past the end of the loop
+    pub break_label: bool,
+    /// Some `continue` jumps to the end of the loop's body
+    pub continue_label: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedForIterable {
+    /// `start..end`; both bounds have the loop variable's type
+    Range { start: TypedExpr, end: TypedExpr },
+    /// An array-typed place, read one element at a time
+    Array(TypedExpr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedMatch {
+    pub scrutinee: TypedExpr,
+    /// Whether the scrutinee is a tagged union, matched on its tag
+    pub tagged: bool,
+    pub arms: Vec<TypedMatchArm>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedMatchArm {
+    pub patterns: Vec<TypedPattern>,
+    pub body: TypedBlock,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypedPattern {
+    Wildcard,
+    /// A constant of the scrutinee's type: a literal or an enum variant
+    Value(TypedExpr),
+    /// A tagged-union variant, binding some of its payload fields
+    Variant {
+        union_name: String,
+        variant: String,
+        bindings: Vec<TypedPayloadBinding>,
+    },
+}
+
+/// Payload field copied into a new immutable binding when an arm matches
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedPayloadBinding {
+    pub field: String,
+    pub name: String,
+    pub ty: Type,
+}
+
+/// Enum with every discriminant resolved
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedEnum {
+    pub name: String,
+    /// Underlying integer type as written; `None` leaves it to C's `enum`
+    pub repr: Option<Type>,
+    pub variants: Vec<(String, i64)>,
+}
+
+/// Tagged union; each variant's tag is its position in the declaration
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedUnion {
+    pub name: String,
+    /// Integer type of the tag as written; `None` leaves it to C's `enum`
+    pub repr: Option<Type>,
+    pub variants: Vec<TypedUnionVariant>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedUnionVariant {
+    pub name: String,
+    pub tag: i64,
+    /// Payload fields; tuple payloads use the names `_0`, `_1`, ...
+    pub fields: Vec<(String, Type)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedFunction {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub return_type: Option<Type>,
+    pub body: Option<TypedBlock>,
+    pub is_extern: bool,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypedImpl {
+    pub target: String,
+    pub methods: Vec<TypedFunction>,
+}
@@ -0,0 +1,18 @@
+use crate::{Expression, Span, Type};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumDef {
+    pub name: String,
+    /// Underlying integer type, e.g. `u8` in `enum State: u8 { ... }`
+    pub repr: Option<Type>,
+    pub variants: Vec<EnumVariant>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumVariant {
+    pub name: String,
+    /// Explicit discriminant; otherwise one more than the previous variant
+    pub value: Option<Expression>,
+    pub span: Span,
+}
@@ -1,13 +1,15 @@
-use crate::Type;
+use crate::{Span, Type};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StructDef {
     pub name: String,
     pub fields: Vec<StructField>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StructField {
     pub name: String,
     pub ty: Type,
+    pub span: Span,
 }
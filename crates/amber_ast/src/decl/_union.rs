@@ -0,0 +1,28 @@
+use crate::{Span, StructField, Type};
+
+/// Tagged union, e.g. `union Packet: u8 { Ping, Data(u8, u16), Move { x: i32 } }`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnionDef {
+    pub name: String,
+    /// Integer type of the tag
+    pub repr: Option<Type>,
+    pub variants: Vec<UnionVariant>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnionVariant {
+    pub name: String,
+    pub payload: VariantPayload,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariantPayload {
+    /// `Ping`, carrying no data
+    Unit,
+    /// `Data(u8, u16)`, with positional fields
+    Tuple(Vec<Type>),
+    /// `Move { x: i32, y: i32 }`, with named fields
+    Struct(Vec<StructField>),
+}
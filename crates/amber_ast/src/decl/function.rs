@@ -1,4 +1,4 @@
-use crate::Type;
+use crate::{Span, Type};
 use crate::program::Block;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -8,10 +8,18 @@ pub struct Function {
     pub return_type: Option<Type>,
     pub body: Option<Block>,
     pub is_extern: bool,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Param {
-    SelfParam,
-    Typed { name: String, ty: Type },
+    /// `self` or `mut self`, the receiver of a method as `*Target` or
+    /// `*mut Target`
+    SelfParam {
+        is_mut: bool,
+    },
+    Typed {
+        name: String,
+        ty: Type,
+    },
 }
@@ -1,5 +1,8 @@
+use crate::Span;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ImplBlock {
     pub target: String,
     pub methods: Vec<super::Function>,
+    pub span: Span,
 }
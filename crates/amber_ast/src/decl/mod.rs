@@ -1,6 +1,10 @@
+mod _enum;
 mod _struct;
 mod function;
 mod impl_block;
+mod _union;
+pub use _enum::{EnumDef, EnumVariant};
 pub use _struct::{StructDef, StructField};
 pub use function::{Function, Param};
 pub use impl_block::ImplBlock;
+pub use _union::{UnionDef, UnionVariant, VariantPayload};
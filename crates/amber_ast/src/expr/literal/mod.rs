@@ -1,6 +1,6 @@
 mod numeric;
 
-pub use numeric::NumericLiteral;
+pub use numeric::{IntSuffix, NumericLiteral};
 
 use std::fmt;
 
@@ -11,8 +11,15 @@ pub enum Literal {
     Numeric(NumericLiteral),
     /// Boolean literals: true or false
     Bool(bool),
+    /// Character literals, e.g. `'a'` or `'\n'`; always a single byte
     Char(char),
-    // String(String),
+    /// Byte literals, e.g. `b'A'`, of type `u8`
+    Byte(u8),
+    /// String literals, e.g. `"hello\n"`, of type `str`
+    String(String),
+    /// Null-terminated string literals for C interop, e.g. `c"%d\n"`, of
+    /// type `*u8`
+    CString(String),
     // Array(Vec<Literal>),
 }
 
@@ -23,6 +30,9 @@ impl Literal {
             Literal::Numeric(num) => num.inferred_type(),
             Literal::Bool(_) => "bool",
             Literal::Char(_) => "char",
+            Literal::Byte(_) => "u8",
+            Literal::String(_) => "str",
+            Literal::CString(_) => "*u8",
         }
     }
 
@@ -31,6 +41,15 @@ impl Literal {
         matches!(self, Literal::Numeric(_))
     }
 
+    /// Value of an integer literal, with or without a suffix
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            Literal::Numeric(num) => num.as_integer(),
+            Literal::Byte(b) => Some(i128::from(*b)),
+            _ => None,
+        }
+    }
+
     /// Check if this is a boolean literal
     pub fn is_bool(&self) -> bool {
         matches!(self, Literal::Bool(_))
@@ -42,11 +61,35 @@ impl fmt::Display for Literal {
         match self {
             Literal::Numeric(num) => write!(f, "{}", num),
             Literal::Bool(b) => write!(f, "{}", b),
-            Literal::Char(c) => write!(f, "{}", c),
+            Literal::Char(c) => write!(f, "'{}'", escape_char(*c, '\'')),
+            Literal::Byte(b) => write!(f, "b'{}'", escape_char(char::from(*b), '\'')),
+            Literal::String(s) => write!(f, "\"{}\"", escape_str(s)),
+            Literal::CString(s) => write!(f, "c\"{}\"", escape_str(s)),
         }
     }
 }
 
+/// Body of a string literal as written between `"`s in Amber source
+fn escape_str(s: &str) -> String {
+    s.chars().map(|c| escape_char(c, '"')).collect()
+}
+
+/// `c` as written between `quote`s in Amber source
+fn escape_char(c: char, quote: char) -> String {
+    match c {
+        '\n' => "\\n".to_string(),
+        '\r' => "\\r".to_string(),
+        '\t' => "\\t".to_string(),
+        '\0' => "\\0".to_string(),
+        '\\' => "\\\\".to_string(),
+        _ if c == quote => format!("\\{}", c),
+        ' '..='~' => c.to_string(),
+        // Strings hold UTF-8 text, characters only single bytes
+        '\u{80}'.. if quote == '"' => c.to_string(),
+        _ => format!("\\x{:02X}", u32::from(c)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,5 +120,15 @@ mod tests {
 
         assert_eq!(int_lit.to_string(), "42");
         assert_eq!(bool_lit.to_string(), "true");
+        assert_eq!(Literal::Char('a').to_string(), "'a'");
+        assert_eq!(Literal::Char('\n').to_string(), "'\\n'");
+        assert_eq!(Literal::Char('\'').to_string(), "'\\''");
+        assert_eq!(Literal::Char('\x7F').to_string(), "'\\x7F'");
+        assert_eq!(Literal::Byte(b'A').to_string(), "b'A'");
+        assert_eq!(
+            Literal::String("say \"hi\"\n".to_string()).to_string(),
+            r#""say \"hi\"\n""#
+        );
+        assert_eq!(Literal::CString("%d'".to_string()).to_string(), r#"c"%d'""#);
     }
 }
@@ -1,17 +1,78 @@
 use std::fmt;
 
+use crate::Type;
+
 /// Represents numeric literals: integers and floating-point numbers
 #[derive(Clone, Debug, PartialEq, Copy)]
 pub enum NumericLiteral {
-    Integer(i64),
+    /// Integer whose type is decided by where it is used
+    Integer(i128),
+    /// Integer written with a type suffix, e.g. `10u8`
+    Suffixed(i128, IntSuffix),
     Float(f32),
     Double(f64),
 }
 
+/// Integer type named by a literal suffix
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntSuffix {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+}
+
+impl IntSuffix {
+    /// Suffix written as `suffix`, e.g. `u8`
+    pub fn parse(suffix: &str) -> Option<Self> {
+        Some(match suffix {
+            "u8" => IntSuffix::U8,
+            "u16" => IntSuffix::U16,
+            "u32" => IntSuffix::U32,
+            "u64" => IntSuffix::U64,
+            "i8" => IntSuffix::I8,
+            "i16" => IntSuffix::I16,
+            "i32" => IntSuffix::I32,
+            "i64" => IntSuffix::I64,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntSuffix::U8 => "u8",
+            IntSuffix::U16 => "u16",
+            IntSuffix::U32 => "u32",
+            IntSuffix::U64 => "u64",
+            IntSuffix::I8 => "i8",
+            IntSuffix::I16 => "i16",
+            IntSuffix::I32 => "i32",
+            IntSuffix::I64 => "i64",
+        }
+    }
+
+    pub fn ty(&self) -> Type {
+        match self {
+            IntSuffix::U8 => Type::U8,
+            IntSuffix::U16 => Type::U16,
+            IntSuffix::U32 => Type::U32,
+            IntSuffix::U64 => Type::U64,
+            IntSuffix::I8 => Type::I8,
+            IntSuffix::I16 => Type::I16,
+            IntSuffix::I32 => Type::I32,
+            IntSuffix::I64 => Type::I64,
+        }
+    }
+}
+
 impl NumericLiteral {
     pub fn to_i64(&self) -> i64 {
         match self {
-            NumericLiteral::Integer(i) => *i,
+            NumericLiteral::Integer(i) | NumericLiteral::Suffixed(i, _) => *i as i64,
             NumericLiteral::Float(f) => *f as i64,
             NumericLiteral::Double(d) => *d as i64,
         }
@@ -19,20 +80,32 @@ impl NumericLiteral {
 
     pub fn to_f64(&self) -> f64 {
         match self {
-            NumericLiteral::Integer(i) => *i as f64,
+            NumericLiteral::Integer(i) | NumericLiteral::Suffixed(i, _) => *i as f64,
             NumericLiteral::Float(f) => *f as f64,
             NumericLiteral::Double(d) => *d,
         }
     }
 
     pub fn is_integer(&self) -> bool {
-        matches!(self, NumericLiteral::Integer(_))
+        matches!(
+            self,
+            NumericLiteral::Integer(_) | NumericLiteral::Suffixed(..)
+        )
+    }
+
+    /// Value of an integer literal, with or without a suffix
+    pub fn as_integer(&self) -> Option<i128> {
+        match self {
+            NumericLiteral::Integer(i) | NumericLiteral::Suffixed(i, _) => Some(*i),
+            _ => None,
+        }
     }
 
     /// Get the inferred C type for this literal
     pub fn inferred_type(&self) -> &'static str {
         match self {
             NumericLiteral::Integer(_) => "i64",
+            NumericLiteral::Suffixed(_, suffix) => suffix.name(),
             NumericLiteral::Float(_) => "f32",
             NumericLiteral::Double(_) => "f64",
         }
@@ -43,6 +116,7 @@ impl fmt::Display for NumericLiteral {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             NumericLiteral::Integer(i) => write!(f, "{}", i),
+            NumericLiteral::Suffixed(i, suffix) => write!(f, "{}{}", i, suffix.name()),
             NumericLiteral::Float(fl) => write!(f, "{}f", fl),
             NumericLiteral::Double(d) => write!(f, "{}d", d),
         }
@@ -50,6 +124,7 @@ impl fmt::Display for NumericLiteral {
 }
 
 #[cfg(test)]
+#[allow(clippy::approx_constant)]
 mod tests {
     use super::*;
 
@@ -71,6 +146,10 @@ mod tests {
     #[test]
     fn test_numeric_literal_display() {
         assert_eq!(NumericLiteral::Integer(42).to_string(), "42");
+        assert_eq!(
+            NumericLiteral::Suffixed(255, IntSuffix::U8).to_string(),
+            "255u8"
+        );
         assert_eq!(NumericLiteral::Float(3.14).to_string(), "3.14f");
         assert_eq!(NumericLiteral::Double(2.71828).to_string(), "2.71828d");
     }
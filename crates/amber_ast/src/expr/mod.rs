@@ -3,13 +3,32 @@ mod literal;
 mod unary;
 
 pub use binary::BinaryOp;
-pub use literal::{Literal, NumericLiteral};
+pub use literal::{IntSuffix, Literal, NumericLiteral};
 pub use unary::{UnaryOp, Prefix, Postfix};
 
+use crate::Span;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    pub kind: ExpressionKind,
+    pub span: Span,
+}
+
+impl Expression {
+    pub fn new(kind: ExpressionKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
-pub enum Expression {
+pub enum ExpressionKind {
     Literal(Literal),
     Identifier(String),
+    /// `Target::name`, naming an item that belongs to a type
+    Path {
+        target: String,
+        name: String,
+    },
     UnaryExpr {
         op: UnaryOp,
         expr: Box<Expression>,
@@ -24,4 +43,40 @@ pub enum Expression {
         then_expr: Box<Expression>,
         else_expr: Box<Expression>,
     },
+    Call {
+        callee: Box<Expression>,
+        args: Vec<Expression>,
+    },
+    /// `receiver.method(args)`
+    MethodCall {
+        receiver: Box<Expression>,
+        method: String,
+        args: Vec<Expression>,
+    },
+    /// `base.field`
+    FieldAccess {
+        base: Box<Expression>,
+        field: String,
+    },
+    /// `[a, b, c]`
+    ArrayLiteral(Vec<Expression>),
+    /// `Name { field: value, ... }`
+    StructLiteral {
+        name: String,
+        fields: Vec<FieldInit>,
+    },
+    /// `Union::Variant { field: value, ... }`
+    VariantLiteral {
+        target: String,
+        variant: String,
+        fields: Vec<FieldInit>,
+    },
+}
+
+/// One `field: value` pair of a struct literal
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldInit {
+    pub name: String,
+    pub value: Expression,
+    pub span: Span,
 }
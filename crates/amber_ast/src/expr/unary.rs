@@ -14,7 +14,26 @@ pub enum Prefix {
     BitNot, // ^x (bitwise not)
     PreInc, // ++x
     PreDec, // --x
-    Deref,  // *x
+    Deref,     // *x
+    AddrOf,    // &x
+    AddrOfMut, // &mut x
+}
+
+impl Prefix {
+    /// Source-level spelling of the operator
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Prefix::Neg => "-",
+            Prefix::Pos => "+",
+            Prefix::Not => "!",
+            Prefix::BitNot => "~",
+            Prefix::PreInc => "++",
+            Prefix::PreDec => "--",
+            Prefix::Deref => "*",
+            Prefix::AddrOf => "&",
+            Prefix::AddrOfMut => "&mut",
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -1,11 +1,19 @@
 mod decl;
 mod expr;
 mod program;
+mod span;
 mod stmt;
 mod types;
 
-pub use decl::{Function, ImplBlock, Param, StructDef, StructField};
-pub use expr::{BinaryOp, Expression, Literal, NumericLiteral, UnaryOp, Prefix, Postfix};
+pub use decl::{
+    EnumDef, EnumVariant, Function, ImplBlock, Param, StructDef, StructField, UnionDef,
+    UnionVariant, VariantPayload,
+};
+pub use expr::{BinaryOp, Expression, ExpressionKind, FieldInit, IntSuffix, Literal, NumericLiteral, UnaryOp, Prefix, Postfix};
 pub use program::{Block, Program};
-pub use stmt::{IfElse, Modifier, Statement, VariableBinding, WhileLoop};
+pub use span::{FileId, Span, source_span};
+pub use stmt::{
+    ElseBranch, FieldBinding, ForIterable, ForLoop, IfElse, Match, MatchArm, Modifier, Pattern,
+    PatternKind, PayloadPattern, Statement, VariableBinding, WhileLoop,
+};
 pub use types::Type;
@@ -1,4 +1,4 @@
-use crate::Statement;
+use crate::{Span, Statement};
 #[derive(Debug, Clone, PartialEq)]
 pub struct Program {
     pub statements: Vec<Statement>,
@@ -7,4 +7,5 @@ pub struct Program {
 #[derive(Debug, Clone, PartialEq)]
 pub struct Block {
     pub statements: Vec<Statement>,
+    pub span: Span,
 }
@@ -0,0 +1,55 @@
+use miette::SourceSpan;
+
+/// Identifies a source file within a compilation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FileId(pub u32);
+
+/// Location of a node in its source file, as a byte offset and length
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub file: FileId,
+    pub offset: usize,
+    pub len: usize,
+}
+
+impl Span {
+    pub fn new(file: FileId, offset: usize, len: usize) -> Self {
+        Self { file, offset, len }
+    }
+
+    pub fn end(&self) -> usize {
+        self.offset + self.len
+    }
+
+    /// Smallest span covering both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        let offset = self.offset.min(other.offset);
+        let end = self.end().max(other.end());
+        Span::new(self.file, offset, end - offset)
+    }
+}
+
+/// Convert a span into the form miette labels expect
+pub fn source_span(span: Span) -> SourceSpan {
+    SourceSpan::new(span.offset.into(), span.len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_span_cover() {
+        let file = FileId(1);
+        let left = Span::new(file, 4, 3);
+        let right = Span::new(file, 10, 2);
+        assert_eq!(left.to(right), Span::new(file, 4, 8));
+        assert_eq!(right.to(left), Span::new(file, 4, 8));
+    }
+
+    #[test]
+    fn test_source_span() {
+        let span = source_span(Span::new(FileId(1), 4, 3));
+        assert_eq!((span.offset(), span.len()), (4, 3));
+    }
+}
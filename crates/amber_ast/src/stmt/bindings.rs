@@ -1,5 +1,5 @@
 use crate::stmt::Modifier;
-use crate::{Expression, Type};
+use crate::{Expression, Span, Type};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct VariableBinding {
@@ -8,4 +8,5 @@ pub struct VariableBinding {
     pub name: String,
     pub ty: Option<Type>, // type
     pub value: Option<Expression>,
+    pub span: Span,
 }
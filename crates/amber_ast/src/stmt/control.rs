@@ -1,14 +1,106 @@
-use crate::{Block, Expression};
+use super::Modifier;
+use crate::{Block, Expression, Literal, Span};
 
 #[derive(Clone, Debug, PartialEq)]
-pub struct IfElse{
+pub struct IfElse {
+    /// `comptime if`, resolved by the comptime pass so that only the
+    /// selected branch is kept. Applies to every `else if` of the chain.
+    pub modifier: Option<Modifier>,
     pub condition: Expression,
     pub then_block: Block,
-    pub else_block: Option<Block>
+    pub else_branch: Option<ElseBranch>,
+    pub span: Span,
+}
+
+/// What follows `else`: another `if`, continuing the chain, or a final block
+#[derive(Clone, Debug, PartialEq)]
+pub enum ElseBranch {
+    If(Box<IfElse>),
+    Block(Block),
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct WhileLoop {
+    /// `name: while ...`, targeted by `break name` and `continue name`
+    pub label: Option<String>,
     pub condition: Expression,
     pub block: Block,
-}
\ No newline at end of file
+    pub span: Span,
+}
+
+/// `for i in 0..n { ... }` or `for x in array { ... }`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForLoop {
+    /// `name: for ...`, targeted by `break name` and `continue name`
+    pub label: Option<String>,
+    pub variable: String,
+    pub iterable: ForIterable,
+    /// `comptime for`, unrolled by the comptime pass
+    pub is_comptime: bool,
+    pub block: Block,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ForIterable {
+    /// `start..end`, excluding `end`
+    Range { start: Expression, end: Expression },
+    /// Every element of an array, in order
+    Array(Expression),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Match {
+    pub scrutinee: Expression,
+    pub arms: Vec<MatchArm>,
+    pub span: Span,
+}
+
+/// `pattern | pattern => { ... }`
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchArm {
+    pub patterns: Vec<Pattern>,
+    pub body: Block,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatternKind {
+    /// `_`, matching any value
+    Wildcard,
+    Literal(Literal),
+    /// An enum variant, e.g. `State::Idle`
+    Variant {
+        target: String,
+        name: String,
+    },
+    /// A tagged-union variant with its payload bound to names, e.g.
+    /// `Packet::Data(len, _)` or `Packet::Move { x, y: dy }`
+    Destructure {
+        target: String,
+        name: String,
+        payload: PayloadPattern,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PayloadPattern {
+    /// One name per positional field; `None` for `_`
+    Tuple(Vec<Option<String>>),
+    Struct(Vec<FieldBinding>),
+}
+
+/// `field` or `field: name` inside a struct payload pattern
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldBinding {
+    pub field: String,
+    /// Name the field is bound to; `None` for `field: _`
+    pub binding: Option<String>,
+    pub span: Span,
+}
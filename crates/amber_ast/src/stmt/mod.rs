@@ -2,8 +2,11 @@ mod bindings;
 mod control;
 
 pub use bindings::VariableBinding;
-pub use control::{IfElse, WhileLoop};
-use crate::{Expression, Function, ImplBlock, StructDef};
+pub use control::{
+    ElseBranch, FieldBinding, ForIterable, ForLoop, IfElse, Match, MatchArm, Pattern, PatternKind,
+    PayloadPattern, WhileLoop,
+};
+use crate::{Block, EnumDef, Expression, Function, ImplBlock, Span, StructDef, UnionDef};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Modifier {
@@ -17,10 +20,56 @@ pub enum Statement {
     Binding(VariableBinding),
     IfElse(IfElse),
     WhileLoop(WhileLoop),
+    ForLoop(ForLoop),
+    Match(Match),
+    /// A nested scope, e.g. one iteration of an unrolled `comptime for`
+    Block(Block),
     ExprStatement(Expression),
     Struct(StructDef),
+    Enum(EnumDef),
+    Union(UnionDef),
     Function(Function),
     Impl(ImplBlock),
-    Assignment { target: Expression, value: Expression },
-    Return(Option<Expression>),
+    Assignment {
+        target: Expression,
+        value: Expression,
+        span: Span,
+    },
+    Return {
+        value: Option<Expression>,
+        span: Span,
+    },
+    /// `break` out of the innermost loop, or the loop with the given label
+    Break {
+        label: Option<String>,
+        span: Span,
+    },
+    /// `continue` with the next iteration of the innermost or labeled loop
+    Continue {
+        label: Option<String>,
+        span: Span,
+    },
+}
+
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Binding(binding) => binding.span,
+            Statement::IfElse(if_else) => if_else.span,
+            Statement::WhileLoop(while_loop) => while_loop.span,
+            Statement::ForLoop(for_loop) => for_loop.span,
+            Statement::Block(block) => block.span,
+            Statement::Match(match_stmt) => match_stmt.span,
+            Statement::ExprStatement(expr) => expr.span,
+            Statement::Struct(def) => def.span,
+            Statement::Enum(def) => def.span,
+            Statement::Union(def) => def.span,
+            Statement::Function(func) => func.span,
+            Statement::Impl(block) => block.span,
+            Statement::Assignment { span, .. }
+            | Statement::Return { span, .. }
+            | Statement::Break { span, .. }
+            | Statement::Continue { span, .. } => *span,
+        }
+    }
 }
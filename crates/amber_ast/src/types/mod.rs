@@ -1,3 +1,7 @@
+use std::fmt;
+
+use crate::Expression;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     U8,
@@ -13,10 +17,15 @@ pub enum Type {
     Bool,
     Char,
     Void,
+    /// String slice: a pointer to bytes and their length
+    Str,
     Named(String),
 
     Pointer { inner: Box<Type>, is_mut: bool },
     Array { inner: Box<Type>, len: usize },
+    /// Array whose length is a constant expression such as `[N]u8`; the
+    /// comptime pass evaluates it into `Array`
+    ComptimeArray { inner: Box<Type>, len: Box<Expression> },
 }
 
 impl Type {
@@ -39,4 +48,59 @@ impl Type {
     pub fn is_floating(&self) -> bool {
         matches!(self, Type::F32 | Type::F64)
     }
+
+    pub fn is_integer(&self) -> bool {
+        self.is_numeric() && !self.is_floating()
+    }
+
+    pub fn is_pointer(&self) -> bool {
+        matches!(self, Type::Pointer { .. })
+    }
+
+    /// Smallest and largest value of an integer type
+    pub fn integer_range(&self) -> Option<(i128, i128)> {
+        let range = match self {
+            Type::U8 => (0, u8::MAX as i128),
+            Type::U16 => (0, u16::MAX as i128),
+            Type::U32 => (0, u32::MAX as i128),
+            Type::U64 => (0, u64::MAX as i128),
+            Type::I8 => (i8::MIN as i128, i8::MAX as i128),
+            Type::I16 => (i16::MIN as i128, i16::MAX as i128),
+            Type::I32 => (i32::MIN as i128, i32::MAX as i128),
+            Type::I64 => (i64::MIN as i128, i64::MAX as i128),
+            _ => return None,
+        };
+        Some(range)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::U8 => write!(f, "u8"),
+            Type::U16 => write!(f, "u16"),
+            Type::U32 => write!(f, "u32"),
+            Type::U64 => write!(f, "u64"),
+            Type::I8 => write!(f, "i8"),
+            Type::I16 => write!(f, "i16"),
+            Type::I32 => write!(f, "i32"),
+            Type::I64 => write!(f, "i64"),
+            Type::F32 => write!(f, "f32"),
+            Type::F64 => write!(f, "f64"),
+            Type::Bool => write!(f, "bool"),
+            Type::Char => write!(f, "char"),
+            Type::Void => write!(f, "void"),
+            Type::Str => write!(f, "str"),
+            Type::Named(name) => write!(f, "{}", name),
+            Type::Pointer { inner, is_mut } => {
+                if *is_mut {
+                    write!(f, "*mut {}", inner)
+                } else {
+                    write!(f, "*{}", inner)
+                }
+            }
+            Type::Array { inner, len } => write!(f, "[{}]{}", len, inner),
+            Type::ComptimeArray { inner, .. } => write!(f, "[_]{}", inner),
+        }
+    }
 }
@@ -0,0 +1,51 @@
+use amber_ast::source_span;
+use amber_vm::FoldError;
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use thiserror::Error;
+
+/// Errors reported by one compilation stage, rendered as labeled snippets of
+/// the source file they refer to
+#[derive(Debug, Error, Diagnostic)]
+#[error("{message}")]
+pub struct SourceDiagnostics {
+    message: String,
+    #[source_code]
+    src: NamedSource<String>,
+    #[related]
+    errors: Vec<Box<dyn Diagnostic + Send + Sync>>,
+}
+
+impl SourceDiagnostics {
+    pub fn new<E>(message: String, name: &str, source: &str, errors: Vec<E>) -> Self
+    where
+        E: Diagnostic + Send + Sync + 'static,
+    {
+        Self {
+            message,
+            src: NamedSource::new(name, source.to_string()),
+            errors: errors
+                .into_iter()
+                .map(|err| Box::new(err) as Box<dyn Diagnostic + Send + Sync>)
+                .collect(),
+        }
+    }
+}
+
+/// A comptime evaluation error, labeled at the code that raised it
+#[derive(Debug, Error, Diagnostic)]
+#[error("{message}")]
+#[diagnostic(code(amber_vm::comptime_error))]
+pub struct ComptimeDiagnostic {
+    message: String,
+    #[label("evaluated here")]
+    span: SourceSpan,
+}
+
+impl From<FoldError> for ComptimeDiagnostic {
+    fn from(err: FoldError) -> Self {
+        Self {
+            message: err.error.to_string(),
+            span: source_span(err.span),
+        }
+    }
+}
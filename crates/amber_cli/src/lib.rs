@@ -1,15 +1,28 @@
+mod diagnostics;
+mod trace;
+
 use amber_parser::build_ast_with_name;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+use amber_analysis::analyze_with;
+use amber_ast::Type;
 use amber_codegen::generate_program;
-use clap::Parser;
+use amber_vm::{Overflow, TraceEntry, fold_program_traced, resolve_program};
+use clap::{Parser, ValueEnum};
+use diagnostics::{ComptimeDiagnostic, SourceDiagnostics};
 use miette::{Context, IntoDiagnostic, Result};
+pub use trace::TraceFormat;
+use trace::render_trace;
 
 pub fn run_cli() -> Result<()> {
     let cli = Cli::parse();
+    let compiler = AmberCompiler {
+        overflow: cli.overflow.into(),
+        trace: cli.trace_comptime,
+        default_int: cli.default_int,
+    };
     let plan = CompilationPlan::from_cli(cli)?;
-    let compiler = AmberCompiler::default();
     run_compilation(&compiler, plan)
 }
 
@@ -23,6 +36,66 @@ struct Cli {
     /// Optional destination for the generated C file
     #[arg(short, long, value_name = "OUTPUT")]
     output: Option<PathBuf>,
+
+    /// What comptime integer arithmetic does when a result leaves its type's range
+    #[arg(long, value_enum, default_value_t = OverflowMode::Error)]
+    overflow: OverflowMode,
+
+    /// Print every binding folded at compile time, with its value and the
+    /// bindings it was computed from
+    #[arg(long, value_enum, value_name = "FORMAT")]
+    trace_comptime: Option<TraceFormat>,
+
+    /// Type of integer literals and of the bindings they initialize when
+    /// nothing else decides it
+    #[arg(long, value_enum, value_name = "TYPE", default_value_t = IntWidth::I64)]
+    default_int: IntWidth,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum OverflowMode {
+    /// Stop with an error
+    Error,
+    /// Wrap each result around to its type, as the generated C does
+    Wrap,
+}
+
+impl From<OverflowMode> for Overflow {
+    fn from(mode: OverflowMode) -> Self {
+        match mode {
+            OverflowMode::Error => Overflow::Error,
+            OverflowMode::Wrap => Overflow::Wrap,
+        }
+    }
+}
+
+/// Integer type given to literals that nothing else gives a type to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum IntWidth {
+    I8,
+    I16,
+    I32,
+    #[default]
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl From<IntWidth> for Type {
+    fn from(width: IntWidth) -> Self {
+        match width {
+            IntWidth::I8 => Type::I8,
+            IntWidth::I16 => Type::I16,
+            IntWidth::I32 => Type::I32,
+            IntWidth::I64 => Type::I64,
+            IntWidth::U8 => Type::U8,
+            IntWidth::U16 => Type::U16,
+            IntWidth::U32 => Type::U32,
+            IntWidth::U64 => Type::U64,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -52,26 +125,69 @@ impl CompilationPlan {
 }
 
 #[derive(Default)]
-pub struct AmberCompiler;
+pub struct AmberCompiler {
+    /// Overflow behavior of comptime integer arithmetic
+    pub overflow: Overflow,
+    /// Format of the comptime trace printed by `run_compilation`, if any
+    pub trace: Option<TraceFormat>,
+    /// Type of integer literals that nothing else gives a type to
+    pub default_int: IntWidth,
+}
 
 impl AmberCompiler {
     pub fn compile_from_file(&self, plan: &CompilationPlan) -> Result<String> {
-        let source = fs::read_to_string(&plan.input)
-            .into_diagnostic()
-            .with_context(|| format!("failed to read '{}'", plan.input.display()))?;
+        let source = read_source(&plan.input)?;
         self.compile_source(&source, &plan.input)
     }
 
     pub fn compile_source(&self, source: &str, origin: &Path) -> Result<String> {
-        let program = build_ast_with_name(source, origin.display().to_string())
-            .map_err(|err| miette::miette!("failed to parse '{}': {}", origin.display(), err))?;
-        generate_program(&program).map_err(|err| {
-            miette::miette!("failed to generate C for '{}': {}", origin.display(), err)
-        })
+        self.compile_source_with_trace(source, origin)
+            .map(|(c_code, _)| c_code)
+    }
+
+    /// Compile like `compile_source`, also returning the bindings folded at
+    /// compile time
+    pub fn compile_source_with_trace(
+        &self,
+        source: &str,
+        origin: &Path,
+    ) -> Result<(String, Vec<TraceEntry>)> {
+        let name = origin.display().to_string();
+        let program = build_ast_with_name(source, name.clone())?;
+        let comptime_error = |err| {
+            SourceDiagnostics::new(
+                format!("failed to evaluate comptime code in '{}'", name),
+                &name,
+                source,
+                vec![ComptimeDiagnostic::from(err)],
+            )
+        };
+        let check = |program| {
+            analyze_with(program, self.default_int.into()).map_err(|errors| {
+                SourceDiagnostics::new(format!("failed to check '{}'", name), &name, source, errors)
+            })
+        };
+        // The program is checked as written before any of it is folded, so
+        // folding never hides an ill-typed expression behind its value
+        let resolved = resolve_program(&program, self.overflow).map_err(comptime_error)?;
+        check(&resolved)?;
+        let (program, trace) =
+            fold_program_traced(&program, self.overflow).map_err(comptime_error)?;
+        let program = check(&program)?;
+        let c_code = generate_program(&program).map_err(|err| {
+            SourceDiagnostics::new(
+                format!("failed to generate C for '{}'", name),
+                &name,
+                source,
+                vec![err],
+            )
+        })?;
+        Ok((c_code, trace))
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod tests {
     use super::*;
     use miette::GraphicalReportHandler;
@@ -90,17 +206,34 @@ mod tests {
         println!("OUTPUT:\n{}", rendered);
         assert!(rendered.contains("failed to parse"));
         assert!(rendered.contains("expected"));
-        assert!(rendered.contains("syntax.amb"));
+        assert!(rendered.contains("syntax.amb:1:"));
+        assert!(rendered.contains("const a = 1"));
     }
 }
 
+/// Compile the plan's input and write the C file. With a trace format set,
+/// the trace goes to stdout and the status line to stderr.
 pub fn run_compilation(compiler: &AmberCompiler, plan: CompilationPlan) -> Result<()> {
-    let c_code = compiler.compile_from_file(&plan)?;
+    let source = read_source(&plan.input)?;
+    let (c_code, trace) = compiler.compile_source_with_trace(&source, &plan.input)?;
     persist_output(&plan.output, &c_code)?;
-    println!("Generated {}", plan.output.display());
+    match compiler.trace {
+        Some(format) => {
+            let name = plan.input.display().to_string();
+            print!("{}", render_trace(&trace, &name, &source, format));
+            eprintln!("Generated {}", plan.output.display());
+        }
+        None => println!("Generated {}", plan.output.display()),
+    }
     Ok(())
 }
 
+fn read_source(path: &Path) -> Result<String> {
+    fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("failed to read '{}'", path.display()))
+}
+
 fn persist_output(path: &Path, contents: &str) -> Result<()> {
     if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
         fs::create_dir_all(dir)
@@ -0,0 +1,138 @@
+use amber_vm::TraceEntry;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How the comptime trace is printed
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum TraceFormat {
+    /// One indented block per binding
+    Text,
+    /// A JSON array with one object per binding
+    Json,
+}
+
+/// One traced binding, as it is printed
+#[derive(Debug, Serialize)]
+struct TraceRecord {
+    name: String,
+    scope: Option<String>,
+    /// `file:line:column` of the initializer
+    location: String,
+    expression: String,
+    value: String,
+    #[serde(rename = "type")]
+    ty: Option<String>,
+    depends_on: Vec<String>,
+}
+
+impl TraceRecord {
+    fn new(entry: &TraceEntry, name: &str, source: &str) -> Self {
+        let start = entry.span.offset.min(source.len());
+        let end = entry.span.end().min(source.len());
+        let (line, column) = line_column(source, start);
+        Self {
+            name: entry.name.clone(),
+            scope: entry.scope.clone(),
+            location: format!("{}:{}:{}", name, line, column),
+            expression: source.get(start..end).unwrap_or_default().to_string(),
+            value: entry.value.to_string(),
+            ty: entry.ty.as_ref().map(|ty| ty.to_string()),
+            depends_on: entry.depends_on.clone(),
+        }
+    }
+}
+
+/// Render `trace` of the file `name` with contents `source`
+pub fn render_trace(trace: &[TraceEntry], name: &str, source: &str, format: TraceFormat) -> String {
+    let records: Vec<TraceRecord> = trace
+        .iter()
+        .map(|entry| TraceRecord::new(entry, name, source))
+        .collect();
+    match format {
+        TraceFormat::Json => {
+            serde_json::to_string_pretty(&records).expect("trace records serialize") + "\n"
+        }
+        TraceFormat::Text => records
+            .iter()
+            .map(render_text)
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+fn render_text(record: &TraceRecord) -> String {
+    let mut text = match &record.scope {
+        Some(scope) => format!("{}: {} at {}\n", scope, record.name, record.location),
+        None => format!("{} at {}\n", record.name, record.location),
+    };
+    text.push_str(&format!("  expression: {}\n", record.expression));
+    text.push_str(&format!("  value:      {}\n", record.value));
+    text.push_str(&format!(
+        "  type:       {}\n",
+        record.ty.as_deref().unwrap_or("(inferred later)")
+    ));
+    if !record.depends_on.is_empty() {
+        text.push_str(&format!("  depends on: {}\n", record.depends_on.join(", ")));
+    }
+    text
+}
+
+/// 1-based line and column of the byte `offset` in `source`
+fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AmberCompiler;
+    use std::path::Path;
+
+    const SOURCE: &str = "comptime const CLOCK_HZ: u32 = 16000000;
+fn main() {
+    comptime const UBRR: u32 = CLOCK_HZ / (16 * 9600) - 1;
+}
+";
+
+    fn trace() -> Vec<TraceEntry> {
+        let (_, trace) = AmberCompiler::default()
+            .compile_source_with_trace(SOURCE, Path::new("uart.amb"))
+            .unwrap();
+        trace
+    }
+
+    #[test]
+    fn test_render_text() {
+        let text = render_trace(&trace(), "uart.amb", SOURCE, TraceFormat::Text);
+        assert_eq!(
+            text,
+            "CLOCK_HZ at uart.amb:1:32
+  expression: 16000000
+  value:      16000000
+  type:       u32
+
+main: UBRR at uart.amb:3:32
+  expression: CLOCK_HZ / (16 * 9600) - 1
+  value:      103
+  type:       u32
+  depends on: CLOCK_HZ
+"
+        );
+    }
+
+    #[test]
+    fn test_render_json() {
+        let json = render_trace(&trace(), "uart.amb", SOURCE, TraceFormat::Json);
+        let records: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(records[0]["scope"], serde_json::Value::Null);
+        assert_eq!(records[1]["name"], "UBRR");
+        assert_eq!(records[1]["scope"], "main");
+        assert_eq!(records[1]["location"], "uart.amb:3:32");
+        assert_eq!(records[1]["value"], "103");
+        assert_eq!(records[1]["type"], "u32");
+        assert_eq!(records[1]["depends_on"][0], "CLOCK_HZ");
+    }
+}
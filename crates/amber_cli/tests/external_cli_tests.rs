@@ -1,8 +1,10 @@
 use std::fs;
 use std::path::PathBuf;
+use miette::{GraphicalReportHandler, GraphicalTheme};
 use tempfile::TempDir;
 
-use amber_cli::{AmberCompiler, CompilationPlan, run_compilation};
+use amber_cli::{AmberCompiler, CompilationPlan, IntWidth, TraceFormat, run_compilation};
+use amber_vm::Overflow;
 
 #[test]
 fn test_cli_compilation_from_file_success() {
@@ -199,4 +201,214 @@ fn test_cli_invalid_syntax_error() {
     
     // This should fail because of invalid syntax
     assert!(result.is_err());
-}
\ No newline at end of file
+}
+#[test]
+fn test_cli_comptime_binding_is_folded() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let input_path = temp_dir.path().join("comptime_test.amb");
+
+    let test_program = r#"
+comptime const CLOCK: u32 = 16000000;
+comptime const BAUD: u32 = CLOCK / 9600;
+"#;
+    fs::write(&input_path, test_program).expect("Failed to write test file");
+
+    let output_path = temp_dir.path().join("comptime_output.c");
+    let plan = CompilationPlan {
+        input: input_path,
+        output: output_path.clone(),
+    };
+
+    let compiler = AmberCompiler::default();
+    let result = run_compilation(&compiler, plan);
+    assert!(result.is_ok(), "Compilation should succeed: {:?}", result.err());
+
+    let output_content = fs::read_to_string(&output_path).expect("Failed to read output file");
+    assert!(output_content.contains("const uint32_t BAUD = 1666U;"));
+}
+
+#[test]
+fn test_cli_comptime_float_binding() {
+    let compiler = AmberCompiler::default();
+    let output = compiler
+        .compile_source(
+            "comptime const RATIO: f32 = 3 / 2;",
+            std::path::Path::new("float.amb"),
+        )
+        .expect("Compilation should succeed");
+    assert!(output.contains("const float RATIO = 1.0f;"));
+}
+
+#[test]
+fn test_cli_comptime_array_length() {
+    let compiler = AmberCompiler::default();
+    let output = compiler
+        .compile_source(
+            "comptime const N: u32 = 4;\nvar buf: [N * 2]u8;\nvar rows: [N][2]*mut u8;\n",
+            std::path::Path::new("arrays.amb"),
+        )
+        .expect("Compilation should succeed");
+    assert!(output.contains("uint8_t buf[8];"));
+    assert!(output.contains("uint8_t* rows[4][2];"));
+}
+
+#[test]
+fn test_cli_type_error_is_reported() {
+    let compiler = AmberCompiler::default();
+    let result = compiler.compile_source(
+        "fn main() {\n    const flag: bool = 1 + 2;\n}\n",
+        std::path::Path::new("types.amb"),
+    );
+
+    let mut rendered = String::new();
+    GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+        .render_report(&mut rendered, result.unwrap_err().as_ref())
+        .unwrap();
+    assert!(rendered.contains("failed to check"));
+    assert!(rendered.contains("mismatched types: expected bool, found i64"));
+    // The label points into the offending line of the named file
+    assert!(rendered.contains("types.amb:2:"));
+    assert!(rendered.contains("const flag: bool = 1 + 2;"));
+}
+
+#[test]
+fn test_cli_type_errors_are_reported_before_folding() {
+    // Each initializer would fold to a value of the declared type if it
+    // were evaluated before being checked
+    let cases = [
+        (
+            "const x: bool = false && (1 + true > 0);",
+            "operator '+' cannot be applied to i64 and bool",
+        ),
+        (
+            "const x: u8 = true ? 5 : false;",
+            "mismatched types: expected i64, found bool",
+        ),
+        (
+            "const x: u8 = 1 == 1 ? 2 : \"no\";",
+            "mismatched types: expected i64, found str",
+        ),
+        (
+            "const x = true ? 1 : 2.0;",
+            "mismatched types: expected i64, found f64",
+        ),
+        (
+            "comptime const X: bool = false && undefined_name;",
+            "cannot find 'undefined_name' in this scope",
+        ),
+    ];
+    let compiler = AmberCompiler::default();
+    for (binding, message) in cases {
+        let source = format!("fn main() {{\n    {}\n}}\n", binding);
+        let result = compiler.compile_source(&source, std::path::Path::new("folded.amb"));
+
+        let mut rendered = String::new();
+        GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+            .render_report(&mut rendered, result.unwrap_err().as_ref())
+            .unwrap();
+        assert!(rendered.contains("failed to check 'folded.amb'"), "{}", binding);
+        assert!(rendered.contains(message), "{}: {}", binding, rendered);
+    }
+}
+
+#[test]
+fn test_cli_comptime_error_is_reported() {
+    let compiler = AmberCompiler::default();
+    let result = compiler.compile_source(
+        "fn main() {\n    comptime const wide: u8 = 200u16;\n}\n",
+        std::path::Path::new("comptime.amb"),
+    );
+
+    let mut rendered = String::new();
+    GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+        .render_report(&mut rendered, result.unwrap_err().as_ref())
+        .unwrap();
+    assert!(rendered.contains("failed to evaluate comptime code in 'comptime.amb'"));
+    assert!(rendered.contains("mismatched types: expected u8, found u16"));
+    // The label points at the initializer that failed to evaluate
+    assert!(rendered.contains("comptime.amb:2:31"));
+}
+
+#[test]
+fn test_cli_reports_every_syntax_error() {
+    let compiler = AmberCompiler::default();
+    let result = compiler.compile_source(
+        "const a: i32 = ;\nfn main() {\n    var b: i32 = 1 +;\n}\n",
+        std::path::Path::new("broken.amb"),
+    );
+
+    let mut rendered = String::new();
+    GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+        .render_report(&mut rendered, result.unwrap_err().as_ref())
+        .unwrap();
+    assert!(rendered.contains("failed to parse 'broken.amb'"));
+    assert!(rendered.contains("broken.amb:1:"));
+    assert!(rendered.contains("broken.amb:3:"));
+}
+
+#[test]
+fn test_cli_comptime_overflow_mode() {
+    let source = "comptime const WRAPPED: u8 = 250 + 10;";
+    let err = AmberCompiler::default()
+        .compile_source(source, std::path::Path::new("overflow.amb"))
+        .unwrap_err();
+    let mut rendered = String::new();
+    GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+        .render_report(&mut rendered, err.as_ref())
+        .unwrap();
+    assert!(rendered.contains("u8 arithmetic overflows"));
+
+    let compiler = AmberCompiler {
+        overflow: Overflow::Wrap,
+        ..AmberCompiler::default()
+    };
+    let output = compiler
+        .compile_source(source, std::path::Path::new("overflow.amb"))
+        .expect("Compilation should succeed");
+    assert!(output.contains("const uint8_t WRAPPED = 4;"));
+}
+
+#[test]
+fn test_cli_comptime_trace() {
+    let temp_dir = TempDir::new().expect("Failed to create temp directory");
+    let input_path = temp_dir.path().join("trace.amb");
+    let test_program = r#"comptime const CLOCK_HZ: u32 = 16000000;
+comptime const UBRR: u32 = CLOCK_HZ / (16 * 9600) - 1;
+"#;
+    fs::write(&input_path, test_program).expect("Failed to write test file");
+
+    let compiler = AmberCompiler::default();
+    let (_, trace) = compiler
+        .compile_source_with_trace(test_program, &input_path)
+        .expect("Compilation should succeed");
+    assert_eq!(trace.len(), 2);
+    assert_eq!(trace[1].depends_on, ["CLOCK_HZ"]);
+
+    let plan = CompilationPlan {
+        input: input_path,
+        output: temp_dir.path().join("trace.c"),
+    };
+    let compiler = AmberCompiler {
+        trace: Some(TraceFormat::Json),
+        ..AmberCompiler::default()
+    };
+    assert!(run_compilation(&compiler, plan).is_ok());
+}
+
+#[test]
+fn test_cli_default_int_width() {
+    let source = "fn main() {\n    var count = 0;\n    count = count + 1;\n}";
+    let output = AmberCompiler::default()
+        .compile_source(source, std::path::Path::new("count.amb"))
+        .expect("Compilation should succeed");
+    assert!(output.contains("int64_t count = 0LL;"));
+
+    let compiler = AmberCompiler {
+        default_int: IntWidth::I32,
+        ..AmberCompiler::default()
+    };
+    let output = compiler
+        .compile_source(source, std::path::Path::new("count.amb"))
+        .expect("Compilation should succeed");
+    assert!(output.contains("int32_t count = 0;"));
+}
@@ -1,8 +1,14 @@
 use crate::buffer::CodeBuffer;
 use crate::errors::CodegenError;
+use crate::expression::render_c_string;
 use crate::statements::emit_block;
-use crate::types::type_to_c;
-use amber_ast::{Function, ImplBlock, Param, StructDef, StructField};
+use crate::types::{builtin_type_to_c, declaration_to_c};
+use amber_analysis::{
+    Callee, TypedBlock, TypedElseBranch, TypedEnum, TypedExpr, TypedExprKind, TypedForIterable,
+    TypedFunction, TypedIfElse, TypedImpl, TypedPattern, TypedProgram, TypedStatement, TypedUnion,
+};
+use amber_ast::{Param, StructDef, StructField, Type, source_span};
+use std::collections::{HashMap, HashSet};
 
 pub fn emit_struct(buffer: &mut CodeBuffer, def: &StructDef) -> Result<(), CodegenError> {
     buffer.push_line("typedef struct {");
@@ -15,22 +21,123 @@ pub fn emit_struct(buffer: &mut CodeBuffer, def: &StructDef) -> Result<(), Codeg
     Ok(())
 }
 
+/// Emit the `str` typedef if the program uses it, then the contents of every
+/// string literal as a `const char` array
+pub fn emit_string_data(buffer: &mut CodeBuffer, program: &TypedProgram) {
+    if program.uses_str {
+        buffer.push_line("typedef struct {");
+        buffer.push_line("    const uint8_t* ptr;");
+        buffer.push_line("    uint32_t len;");
+        buffer.push_line(&format!("}} {};", builtin_type_to_c(&Type::Str)));
+        buffer.push_line("");
+    }
+    for (index, value) in program.strings.iter().enumerate() {
+        let line = format!(
+            "static const char {}[] = {};",
+            c_string_data_name(index),
+            render_c_string(value)
+        );
+        buffer.push_line(&line);
+    }
+    if !program.strings.is_empty() {
+        buffer.push_line("");
+    }
+}
+
+pub fn emit_enum(buffer: &mut CodeBuffer, def: &TypedEnum) -> Result<(), CodegenError> {
+    let constants: Vec<(String, i64)> = def
+        .variants
+        .iter()
+        .map(|(variant, value)| (c_variant_name(&def.name, variant), *value))
+        .collect();
+    emit_constants(buffer, &def.name, def.repr.as_ref(), &constants);
+    buffer.push_line("");
+    Ok(())
+}
+
+/// Emit a tagged union as `typedef struct { Name_Tag tag; union { ... } as; }`,
+/// where `as` has one struct member per variant that carries a payload. The
+/// tag type and its constants follow the same rules as enums.
+pub fn emit_union(buffer: &mut CodeBuffer, def: &TypedUnion) -> Result<(), CodegenError> {
+    let tag_type = c_tag_type(&def.name);
+    let constants: Vec<(String, i64)> = def
+        .variants
+        .iter()
+        .map(|variant| (c_variant_name(&def.name, &variant.name), variant.tag))
+        .collect();
+    emit_constants(buffer, &tag_type, def.repr.as_ref(), &constants);
+    buffer.push_line("");
+
+    buffer.push_line("typedef struct {");
+    buffer.push_line(&format!("    {} tag;", tag_type));
+    // A C union needs at least one member
+    let has_payload = def.variants.iter().any(|v| !v.fields.is_empty());
+    if has_payload {
+        buffer.push_line("    union {");
+        for variant in def.variants.iter().filter(|v| !v.fields.is_empty()) {
+            buffer.push_line("        struct {");
+            for (name, ty) in &variant.fields {
+                let field = declaration_to_c(ty, name, false);
+                buffer.push_line(&format!("            {};", field));
+            }
+            buffer.push_line(&format!("        }} {};", variant.name));
+        }
+        buffer.push_line("    } as;");
+    }
+    buffer.push_line(&format!("}} {};", def.name));
+    buffer.push_line("");
+    Ok(())
+}
+
+/// Emit an integer type with named constants. With an underlying type the
+/// constants are macros of a fixed-width typedef, since a C `enum` is always
+/// `int`-sized.
+fn emit_constants(
+    buffer: &mut CodeBuffer,
+    type_name: &str,
+    repr: Option<&Type>,
+    constants: &[(String, i64)],
+) {
+    match repr {
+        Some(repr) => {
+            let typedef = declaration_to_c(repr, type_name, false);
+            buffer.push_line(&format!("typedef {};", typedef));
+            for (name, value) in constants {
+                buffer.push_line(&format!("#define {} (({}){})", name, type_name, value));
+            }
+        }
+        None => {
+            buffer.push_line("typedef enum {");
+            for (name, value) in constants {
+                buffer.push_line(&format!("    {} = {},", name, value));
+            }
+            buffer.push_line(&format!("}} {};", type_name));
+        }
+    }
+}
+
 pub fn emit_struct_field(buffer: &mut CodeBuffer, field: &StructField) {
-    let line = format!("    {} {};", type_to_c(&field.ty), field.name);
+    let line = format!("    {};", declaration_to_c(&field.ty, &field.name, false));
     buffer.push_line(&line);
 }
 
 pub fn emit_function(
     buffer: &mut CodeBuffer,
-    func: &Function,
+    func: &TypedFunction,
     impl_target: Option<&str>,
+    prototypes: &mut Prototypes,
 ) -> Result<(), CodegenError> {
     let signature = function_signature(func, impl_target)?;
+    // The definition itself declares the function, so recursion needs no prototype
+    prototypes
+        .declared
+        .insert(c_function_name(&func.name, impl_target));
 
     if func.is_extern {
         if func.body.is_some() {
             return Err(CodegenError::ExternFunctionWithBody {
                 name: func.name.clone(),
+                span: source_span(func.span),
             });
         }
         buffer.push_line(&format!("extern {};", signature));
@@ -41,7 +148,9 @@ pub fn emit_function(
             .as_ref()
             .ok_or_else(|| CodegenError::MissingFunctionBody {
                 name: func.name.clone(),
+                span: source_span(func.span),
             })?;
+        prototypes.declare_callees(buffer, func);
         buffer.push_line(&format!("{} {{", signature));
         emit_block(buffer, body, 1)?;
         buffer.push_line("}");
@@ -50,63 +159,280 @@ pub fn emit_function(
     Ok(())
 }
 
-pub fn emit_impl(buffer: &mut CodeBuffer, block: &ImplBlock) -> Result<(), CodegenError> {
+/// Tracks which functions C has already seen declared, so a function that
+/// calls one defined further down is preceded by that callee's prototype
+pub struct Prototypes {
+    signatures: HashMap<String, String>,
+    declared: HashSet<String>,
+}
+
+impl Prototypes {
+    pub fn new(program: &TypedProgram) -> Result<Self, CodegenError> {
+        let mut signatures = HashMap::new();
+        for statement in &program.statements {
+            for (func, impl_target) in defined_functions(statement) {
+                signatures.insert(
+                    c_function_name(&func.name, impl_target),
+                    function_signature(func, impl_target)?,
+                );
+            }
+        }
+        Ok(Self {
+            signatures,
+            declared: HashSet::new(),
+        })
+    }
+
+    /// Emit prototypes for callees of `func` that are not declared yet
+    fn declare_callees(&mut self, buffer: &mut CodeBuffer, func: &TypedFunction) {
+        let mut calls = Vec::new();
+        if let Some(body) = &func.body {
+            collect_block_calls(body, &mut calls);
+        }
+        let mut emitted = false;
+        for name in calls {
+            if let Some(signature) = self.signatures.get(&name)
+                && self.declared.insert(name)
+            {
+                buffer.push_line(&format!("{};", signature));
+                emitted = true;
+            }
+        }
+        if emitted {
+            buffer.push_line("");
+        }
+    }
+}
+
+/// Functions a top-level statement defines, with their impl target
+fn defined_functions(statement: &TypedStatement) -> Vec<(&TypedFunction, Option<&str>)> {
+    match statement {
+        TypedStatement::Function(func) => vec![(func, None)],
+        TypedStatement::Impl(block) => block
+            .methods
+            .iter()
+            .map(|method| (method, Some(block.target.as_str())))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn collect_statement_calls(statement: &TypedStatement, calls: &mut Vec<String>) {
+    match statement {
+        TypedStatement::Binding(binding) => {
+            if let Some(value) = &binding.value {
+                collect_expr_calls(value, calls);
+            }
+        }
+        TypedStatement::IfElse(if_else) => collect_if_else_calls(if_else, calls),
+        TypedStatement::WhileLoop(while_loop) => {
+            collect_expr_calls(&while_loop.condition, calls);
+            collect_block_calls(&while_loop.block, calls);
+        }
+        TypedStatement::ForLoop(for_loop) => {
+            match &for_loop.iterable {
+                TypedForIterable::Range { start, end } => {
+                    collect_expr_calls(start, calls);
+                    collect_expr_calls(end, calls);
+                }
+                TypedForIterable::Array(array) => collect_expr_calls(array, calls),
+            }
+            collect_block_calls(&for_loop.block, calls);
+        }
+        TypedStatement::Block(block) => collect_block_calls(block, calls),
+        TypedStatement::Match(match_stmt) => {
+            collect_expr_calls(&match_stmt.scrutinee, calls);
+            for arm in &match_stmt.arms {
+                for pattern in &arm.patterns {
+                    // Variant patterns only name fields and bindings
+                    if let TypedPattern::Value(value) = pattern {
+                        collect_expr_calls(value, calls);
+                    }
+                }
+                collect_block_calls(&arm.body, calls);
+            }
+        }
+        TypedStatement::ExprStatement(expr) | TypedStatement::Return(Some(expr)) => {
+            collect_expr_calls(expr, calls)
+        }
+        TypedStatement::Assignment { target, value } => {
+            collect_expr_calls(target, calls);
+            collect_expr_calls(value, calls);
+        }
+        // Declarations only appear at the top level
+        TypedStatement::Struct(_)
+        | TypedStatement::Enum(_)
+        | TypedStatement::Union(_)
+        | TypedStatement::Function(_)
+        | TypedStatement::Impl(_)
+        | TypedStatement::Return(None)
+        | TypedStatement::Break(_)
+        | TypedStatement::Continue(_) => {}
+    }
+}
+
+fn collect_if_else_calls(if_else: &TypedIfElse, calls: &mut Vec<String>) {
+    collect_expr_calls(&if_else.condition, calls);
+    collect_block_calls(&if_else.then_block, calls);
+    match &if_else.else_branch {
+        Some(TypedElseBranch::If(next)) => collect_if_else_calls(next, calls),
+        Some(TypedElseBranch::Block(block)) => collect_block_calls(block, calls),
+        None => {}
+    }
+}
+
+fn collect_block_calls(block: &TypedBlock, calls: &mut Vec<String>) {
+    for statement in &block.statements {
+        collect_statement_calls(statement, calls);
+    }
+}
+
+fn collect_expr_calls(expr: &TypedExpr, calls: &mut Vec<String>) {
+    match &expr.kind {
+        TypedExprKind::Literal(_)
+        | TypedExprKind::StringLiteral { .. }
+        | TypedExprKind::Identifier(_)
+        | TypedExprKind::EnumVariant { .. } => {}
+        TypedExprKind::Prefix { expr, .. }
+        | TypedExprKind::AddressOf { expr, .. }
+        | TypedExprKind::Field { base: expr, .. } => collect_expr_calls(expr, calls),
+        TypedExprKind::ArrayLiteral(elements) => {
+            for element in elements {
+                collect_expr_calls(element, calls);
+            }
+        }
+        TypedExprKind::StructLiteral { fields, .. }
+        | TypedExprKind::UnionLiteral { fields, .. } => {
+            for (_, value) in fields {
+                collect_expr_calls(value, calls);
+            }
+        }
+        TypedExprKind::Index { base, index } => {
+            collect_expr_calls(base, calls);
+            collect_expr_calls(index, calls);
+        }
+        TypedExprKind::Binary { left, right, .. } => {
+            collect_expr_calls(left, calls);
+            collect_expr_calls(right, calls);
+        }
+        TypedExprKind::Ternary {
+            condition,
+            then_expr,
+            else_expr,
+        } => {
+            collect_expr_calls(condition, calls);
+            collect_expr_calls(then_expr, calls);
+            collect_expr_calls(else_expr, calls);
+        }
+        TypedExprKind::Call { callee, args } => {
+            calls.push(callee_c_name(callee));
+            for arg in args {
+                collect_expr_calls(arg, calls);
+            }
+        }
+    }
+}
+
+pub fn emit_impl(
+    buffer: &mut CodeBuffer,
+    block: &TypedImpl,
+    prototypes: &mut Prototypes,
+) -> Result<(), CodegenError> {
     for method in &block.methods {
         if method.is_extern {
             return Err(CodegenError::ExternImplMethod {
                 target: block.target.clone(),
                 name: method.name.clone(),
+                span: source_span(method.span),
             });
         }
-        emit_function(buffer, method, Some(&block.target))?;
+        emit_function(buffer, method, Some(&block.target), prototypes)?;
     }
     Ok(())
 }
 
 pub fn function_signature(
-    func: &Function,
+    func: &TypedFunction,
     impl_target: Option<&str>,
 ) -> Result<String, CodegenError> {
-    let return_type = func
-        .return_type
-        .as_ref()
-        .map(type_to_c)
-        .unwrap_or_else(|| "void".to_string());
-    let func_name = if let Some(target) = impl_target {
-        format!("{}_{}", target, func.name)
-    } else {
-        func.name.clone()
-    };
-    let params = format_params(&func.params, impl_target)?;
-    Ok(format!("{} {}({})", return_type, func_name, params))
+    let func_name = c_function_name(&func.name, impl_target);
+    let params = format_params(func, impl_target)?;
+    // The name and parameters are the declarator of the return type, so a
+    // returned `*[4]u8` reads `const uint8_t (*f(...))[4]`
+    let declarator = format!("{}({})", func_name, params);
+    Ok(match &func.return_type {
+        Some(ty) => declaration_to_c(ty, &declarator, false),
+        None => format!("void {}", declarator),
+    })
+}
+
+/// C symbol of a function; impl methods are mangled to `Target_method`
+pub fn c_function_name(name: &str, impl_target: Option<&str>) -> String {
+    match impl_target {
+        Some(target) => format!("{}_{}", target, name),
+        None => name.to_string(),
+    }
 }
 
-pub fn format_params(params: &[Param], impl_target: Option<&str>) -> Result<String, CodegenError> {
+/// C constant of an enum variant or union tag, prefixed like impl methods:
+/// `Enum_Variant`
+pub fn c_variant_name(enum_name: &str, variant: &str) -> String {
+    format!("{}_{}", enum_name, variant)
+}
+
+/// C array holding the contents of the string literal `index`
+pub fn c_string_data_name(index: usize) -> String {
+    format!("amber_string_{}", index)
+}
+
+/// C type of a tagged union's tag: `Union_Tag`
+pub fn c_tag_type(union_name: &str) -> String {
+    format!("{}_Tag", union_name)
+}
+
+/// C symbol a call resolves to
+pub fn callee_c_name(callee: &Callee) -> String {
+    match callee {
+        Callee::Function(name) => c_function_name(name, None),
+        Callee::Method { target, name } => c_function_name(name, Some(target)),
+    }
+}
+
+pub fn format_params(
+    func: &TypedFunction,
+    impl_target: Option<&str>,
+) -> Result<String, CodegenError> {
     let mut parts = Vec::new();
     let mut self_count = 0;
 
-    for param in params {
+    for param in &func.params {
         match param {
-            Param::SelfParam => {
+            Param::SelfParam { is_mut } => {
                 self_count += 1;
                 if self_count > 1 {
                     return Err(CodegenError::MultipleSelfParams {
-                        name: "unknown".to_string(),
+                        name: func.name.clone(),
+                        span: source_span(func.span),
                     });
                 }
                 if impl_target.is_none() {
                     return Err(CodegenError::SelfParamOutsideImpl {
-                        name: "unknown".to_string(),
+                        name: func.name.clone(),
+                        span: source_span(func.span),
                     });
                 }
-                // Self param becomes Target* self
+                // Self param becomes `Target* self`, or `const Target* self`
+                // without `mut`
                 if let Some(target) = impl_target {
-                    parts.push(format!("{}* self", target));
+                    let ty = Type::Pointer {
+                        inner: Box::new(Type::Named(target.to_string())),
+                        is_mut: *is_mut,
+                    };
+                    parts.push(declaration_to_c(&ty, "self", false));
                 }
             }
             Param::Typed { name, ty } => {
-                let param_type = type_to_c(ty);
-                parts.push(format!("{} {}", param_type, name));
+                parts.push(declaration_to_c(ty, name, false));
             }
         }
     }
@@ -1,17 +1,42 @@
+use miette::{Diagnostic, SourceSpan};
 use thiserror::Error;
 
-#[derive(Debug, Error, PartialEq, Eq)]
+#[derive(Debug, Error, Diagnostic, PartialEq, Eq)]
 pub enum CodegenError {
-    #[error("binding '{name}' requires an explicit type")]
-    MissingType { name: String },
     #[error("function '{name}' is missing a body")]
-    MissingFunctionBody { name: String },
+    #[diagnostic(code(amber_codegen::missing_function_body))]
+    MissingFunctionBody {
+        name: String,
+        #[label("declared here")]
+        span: SourceSpan,
+    },
     #[error("extern function '{name}' cannot have a body")]
-    ExternFunctionWithBody { name: String },
+    #[diagnostic(code(amber_codegen::extern_with_body))]
+    ExternFunctionWithBody {
+        name: String,
+        #[label("declared here")]
+        span: SourceSpan,
+    },
     #[error("`self` parameter is only allowed inside impl blocks (function '{name}')")]
-    SelfParamOutsideImpl { name: String },
+    #[diagnostic(code(amber_codegen::self_outside_impl))]
+    SelfParamOutsideImpl {
+        name: String,
+        #[label("in this function")]
+        span: SourceSpan,
+    },
     #[error("function '{name}' contains multiple `self` parameters")]
-    MultipleSelfParams { name: String },
+    #[diagnostic(code(amber_codegen::multiple_self_params))]
+    MultipleSelfParams {
+        name: String,
+        #[label("in this function")]
+        span: SourceSpan,
+    },
     #[error("impl method '{target}::{name}' cannot be declared extern")]
-    ExternImplMethod { target: String, name: String },
+    #[diagnostic(code(amber_codegen::extern_impl_method))]
+    ExternImplMethod {
+        target: String,
+        name: String,
+        #[label("declared extern here")]
+        span: SourceSpan,
+    },
 }
@@ -1,20 +1,48 @@
-use amber_ast::{BinaryOp, Expression, Literal, NumericLiteral, Postfix, UnaryOp, Prefix};
-pub fn render_expr(expr: &Expression) -> String {
-    match expr {
-        Expression::Literal(lit) => render_literal(lit),
-        Expression::Identifier(ident) => ident.clone(),
-        Expression::BinaryExpr { left, op, right } => {
-            format!(
-                "({} {} {})",
-                render_expr(left),
-                render_binary_op(op),
-                render_expr(right)
-            )
+use crate::declarations::{c_string_data_name, c_variant_name, callee_c_name};
+use crate::types::type_to_c;
+use amber_analysis::{TypedExpr, TypedExprKind};
+use amber_ast::{BinaryOp, Literal, NumericLiteral, Prefix, Type};
+pub fn render_expr(expr: &TypedExpr) -> String {
+    match &expr.kind {
+        TypedExprKind::Literal(lit) => render_literal(lit, &expr.ty),
+        TypedExprKind::StringLiteral { .. } if expr.ty == Type::Str => {
+            format!("(({}){})", type_to_c(&expr.ty), render_initializer(expr))
+        }
+        TypedExprKind::StringLiteral { index, .. } => format!("({})", string_data_pointer(*index)),
+        TypedExprKind::Identifier(ident) => ident.clone(),
+        TypedExprKind::EnumVariant { enum_name, variant } => c_variant_name(enum_name, variant),
+        TypedExprKind::Binary { left, op, right } => {
+            let right = match op {
+                BinaryOp::Shl | BinaryOp::Shr => render_amount(right),
+                _ => render_expr(right),
+            };
+            let rendered = format!("({} {} {})", render_expr(left), render_binary_op(op), right);
+            // A quotient only leaves its range as `MIN / -1` of a signed type
+            match op {
+                BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Shl => {
+                    narrow(rendered, &expr.ty)
+                }
+                BinaryOp::Div | BinaryOp::Mod if matches!(expr.ty, Type::I8 | Type::I16) => {
+                    narrow(rendered, &expr.ty)
+                }
+                _ => rendered,
+            }
+        }
+        TypedExprKind::Prefix { op, expr: operand } => {
+            let rendered = format!("({}{})", render_prefix_op(op), render_expr(operand));
+            match op {
+                // A negated literal is a constant that already fits
+                Prefix::Neg if !matches!(operand.kind, TypedExprKind::Literal(_)) => {
+                    narrow(rendered, &expr.ty)
+                }
+                Prefix::BitNot => narrow(rendered, &expr.ty),
+                _ => rendered,
+            }
         }
-        Expression::UnaryExpr { op, expr } => {
-            format!("({})", render_unary_op(op, expr))
+        TypedExprKind::Index { base, index } => {
+            format!("({}[{}])", render_expr(base), render_amount(index))
         }
-        Expression::TernaryExpr {
+        TypedExprKind::Ternary {
             condition,
             then_expr,
             else_expr,
@@ -26,12 +54,107 @@ pub fn render_expr(expr: &Expression) -> String {
                 render_expr(else_expr)
             )
         }
+        TypedExprKind::Field {
+            base,
+            field,
+            through_pointer,
+        } => {
+            let access = if *through_pointer { "->" } else { "." };
+            format!("({}{}{})", render_expr(base), access, field)
+        }
+        TypedExprKind::ArrayLiteral(_) => {
+            format!("({}){}", type_to_c(&expr.ty), render_initializer(expr))
+        }
+        TypedExprKind::StructLiteral { name, .. }
+        | TypedExprKind::UnionLiteral {
+            union_name: name, ..
+        } => {
+            format!("({}){}", name, render_initializer(expr))
+        }
+        TypedExprKind::AddressOf { expr, .. } => format!("&{}", render_expr(expr)),
+        TypedExprKind::Call { callee, args } => {
+            let args: Vec<String> = args.iter().map(render_expr).collect();
+            format!("{}({})", callee_c_name(callee), args.join(", "))
+        }
+    }
+}
+
+/// C computes arithmetic on types narrower than `int` in `int`; cast the
+/// result back to `ty` so it wraps at every operation, like comptime
+/// evaluation does
+fn narrow(rendered: String, ty: &Type) -> String {
+    match ty {
+        Type::U8 | Type::I8 | Type::U16 | Type::I16 => {
+            format!("(({}){})", type_to_c(ty), rendered)
+        }
+        _ => rendered,
+    }
+}
+
+/// Render an index or shift amount, whose type does not change the result,
+/// so an integer literal needs no suffix
+fn render_amount(expr: &TypedExpr) -> String {
+    match &expr.kind {
+        TypedExprKind::Literal(Literal::Numeric(num)) if num.is_integer() => {
+            render_numeric_literal(num)
+        }
+        _ => render_expr(expr),
+    }
+}
+
+/// Render the value a binding is initialized with. Struct, union and array
+/// literals become brace initializers, which are also valid for global
+/// bindings.
+pub fn render_initializer(expr: &TypedExpr) -> String {
+    match &expr.kind {
+        TypedExprKind::StringLiteral { index, value } if expr.ty == Type::Str => {
+            format!("{{ {}, {} }}", string_data_pointer(*index), value.len())
+        }
+        TypedExprKind::ArrayLiteral(elements) => {
+            let elements: Vec<String> = elements.iter().map(render_initializer).collect();
+            format!("{{ {} }}", elements.join(", "))
+        }
+        TypedExprKind::StructLiteral { fields, .. } => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(name, value)| format!(".{} = {}", name, render_initializer(value)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        // The payload is written through the variant's member of `as`
+        TypedExprKind::UnionLiteral {
+            union_name,
+            variant,
+            fields,
+        } => {
+            let tag = format!(".tag = {}", c_variant_name(union_name, variant));
+            if fields.is_empty() {
+                return format!("{{ {} }}", tag);
+            }
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(name, value)| format!(".{} = {}", name, render_initializer(value)))
+                .collect();
+            format!(
+                "{{ {}, .as.{} = {{ {} }} }}",
+                tag,
+                variant,
+                fields.join(", ")
+            )
+        }
+        _ => render_expr(expr),
     }
 }
 
-pub fn render_literal(lit: &Literal) -> String {
+/// Render a literal of type `ty`
+pub fn render_literal(lit: &Literal, ty: &Type) -> String {
     match lit {
-        Literal::Numeric(num) => render_numeric_literal(num),
+        // `9223372036854775808LL` does not fit, so the minimum cannot be
+        // written as a negated constant
+        Literal::Numeric(num) if num.as_integer() == Some(i128::from(i64::MIN)) => {
+            "(-9223372036854775807LL - 1)".to_string()
+        }
+        Literal::Numeric(num) => render_numeric_literal(num) + integer_suffix(num, ty),
         Literal::Bool(b) => {
             if *b {
                 "true".to_string()
@@ -39,42 +162,88 @@ pub fn render_literal(lit: &Literal) -> String {
                 "false".to_string()
             }
         }
-        &Literal::Char(c) => c.to_string(),
+        &Literal::Char(c) => format!("'{}'", escape_c_byte(c as u8)),
+        // A C character constant is a `char` promoted to `int`, which turns
+        // the upper half negative where `char` is signed
+        &Literal::Byte(b) if b.is_ascii() => format!("'{}'", escape_c_byte(b)),
+        Literal::Byte(b) => b.to_string(),
+        Literal::String(s) | Literal::CString(s) => render_c_string(s),
+    }
+}
+
+/// The string data `index` as the `*u8` a `str` points to
+fn string_data_pointer(index: usize) -> String {
+    format!("(const uint8_t*){}", c_string_data_name(index))
+}
+
+/// `value` as a C string literal
+pub fn render_c_string(value: &str) -> String {
+    let mut rendered = String::from("\"");
+    let mut previous = 0;
+    for byte in value.bytes() {
+        match byte {
+            b'\'' => rendered.push('\''),
+            // `??` starts a trigraph in C99
+            b'?' if previous == b'?' => rendered.push_str("\\?"),
+            _ => rendered.push_str(&escape_c_byte(byte)),
+        }
+        previous = byte;
+    }
+    rendered.push('"');
+    rendered
+}
+
+/// `byte` as written between quotes in C. Other unprintable bytes become
+/// octal escapes, which unlike `\x` escapes end after three digits.
+fn escape_c_byte(byte: u8) -> String {
+    match byte {
+        b'\n' => "\\n".to_string(),
+        b'\r' => "\\r".to_string(),
+        b'\t' => "\\t".to_string(),
+        b'\\' => "\\\\".to_string(),
+        b'\'' => "\\'".to_string(),
+        b'"' => "\\\"".to_string(),
+        b' '..=b'~' => char::from(byte).to_string(),
+        _ => format!("\\{:03o}", byte),
     }
 }
 
 pub fn render_numeric_literal(lit: &NumericLiteral) -> String {
     match lit {
-        NumericLiteral::Integer(i) => i.to_string(),
-        NumericLiteral::Float(f) => {
-            if f.is_finite() {
-                format!("{}f", f)
-            } else {
-                format!("{}f", f)
-            }
-        }
-        NumericLiteral::Double(d) => d.to_string(),
+        NumericLiteral::Integer(i) | NumericLiteral::Suffixed(i, _) => i.to_string(),
+        // Debug formatting always keeps a decimal point or exponent, so the
+        // literal stays floating in C even for whole values like `1.0`
+        NumericLiteral::Float(f) => format!("{:?}f", f),
+        NumericLiteral::Double(d) => format!("{:?}", d),
     }
 }
 
-pub fn render_unary_op(op: &UnaryOp, expression: &Expression) -> String {
+/// C suffix of an integer literal of type `ty`. Smaller types are promoted
+/// to `int` anyway, but `uint32_t` and 64-bit constants need a suffix for
+/// masks and shifts to work like their type, and a `uint64_t` above
+/// `LLONG_MAX` is not a valid constant without `ULL`.
+fn integer_suffix(num: &NumericLiteral, ty: &Type) -> &'static str {
+    if !num.is_integer() {
+        return "";
+    }
+    match ty {
+        Type::U32 => "U",
+        Type::I64 => "LL",
+        Type::U64 => "ULL",
+        _ => "",
+    }
+}
+
+pub fn render_prefix_op(op: &Prefix) -> &'static str {
     match op {
-        UnaryOp::PrefixOp(pre_op) => {
-            let operator = match pre_op {
-                Prefix::Neg => "-",
-                Prefix::Pos => "+",
-                Prefix::Not => "!",
-                Prefix::BitNot => "~",
-                Prefix::PreInc => "++",
-                Prefix::PreDec => "--",
-                Prefix::Deref => "*",
-            };
-            format!("{}{}", operator, render_expr(expression))
-        }
-        UnaryOp::PostfixOp(post_op) => {
-            let operator = match post_op { Postfix::Index { index } => { format!("[{}]", render_expr(index)) } };
-            format!("{}{}", render_expr(expression), operator)
-        }
+        Prefix::Neg => "-",
+        Prefix::Pos => "+",
+        Prefix::Not => "!",
+        Prefix::BitNot => "~",
+        Prefix::PreInc => "++",
+        Prefix::PreDec => "--",
+        Prefix::Deref => "*",
+        Prefix::AddrOf | Prefix::AddrOfMut => "&",
     }
 }
 
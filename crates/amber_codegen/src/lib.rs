@@ -2,16 +2,17 @@ mod buffer;
 mod declarations;
 mod errors;
 mod expression;
+mod report;
 mod statements;
 mod types;
 
 pub use errors::CodegenError;
 
-use amber_ast::Program;
+use amber_analysis::TypedProgram;
 use buffer::CodeBuffer;
 
-/// Generate C code from an Amber AST program
-pub fn generate_program(program: &Program) -> Result<String, CodegenError> {
+/// Generate C code from a type-checked Amber program
+pub fn generate_program(program: &TypedProgram) -> Result<String, CodegenError> {
     let mut buffer = CodeBuffer::default();
     statements::emit_program(&mut buffer, program)?;
     Ok(buffer.finish())
@@ -21,13 +22,25 @@ pub fn generate_program(program: &Program) -> Result<String, CodegenError> {
 mod tests {
     use super::*;
     use amber_ast::{
-        Block, Function, ImplBlock, Literal, Modifier, NumericLiteral, Param, Program,
-        Statement, StructDef, StructField, Type,
+        Block, Expression, ExpressionKind, Function, ImplBlock, Literal, Modifier, NumericLiteral,
+        Param, Program, Span, Statement, StructDef, StructField, Type,
     };
 
-    fn return_block(expr: amber_ast::Expression) -> Block {
+    fn expr(kind: ExpressionKind) -> Expression {
+        Expression::new(kind, Span::default())
+    }
+
+    fn ident(name: &str) -> Box<Expression> {
+        Box::new(expr(ExpressionKind::Identifier(name.to_string())))
+    }
+
+    fn return_block(value: Option<Expression>) -> Block {
         Block {
-            statements: vec![Statement::Return(Some(expr))],
+            statements: vec![Statement::Return {
+                value,
+                span: Span::default(),
+            }],
+            span: Span::default(),
         }
     }
 
@@ -41,12 +54,15 @@ mod tests {
                         StructField {
                             name: "x".to_string(),
                             ty: Type::I32,
+                            span: Span::default(),
                         },
                         StructField {
                             name: "y".to_string(),
                             ty: Type::I32,
+                            span: Span::default(),
                         },
                     ],
+                    span: Span::default(),
                 }),
                 Statement::Function(Function {
                     name: "add".to_string(),
@@ -62,11 +78,12 @@ mod tests {
                     ],
                     return_type: Some(Type::I32),
                     is_extern: false,
-                    body: Some(return_block(amber_ast::Expression::BinaryExpr {
-                        left: Box::new(amber_ast::Expression::Identifier("a".to_string())),
+                    body: Some(return_block(Some(expr(ExpressionKind::BinaryExpr {
+                        left: ident("a"),
                         op: amber_ast::BinaryOp::Add,
-                        right: Box::new(amber_ast::Expression::Identifier("b".to_string())),
-                    })),
+                        right: ident("b"),
+                    })))),
+                    span: Span::default(),
                 }),
                 Statement::Function(Function {
                     name: "HAL_Delay".to_string(),
@@ -77,6 +94,7 @@ mod tests {
                     return_type: None,
                     is_extern: true,
                     body: None,
+                    span: Span::default(),
                 }),
                 Statement::Impl(ImplBlock {
                     target: "Point".to_string(),
@@ -84,7 +102,7 @@ mod tests {
                         Function {
                             name: "sum".to_string(),
                             params: vec![
-                                Param::SelfParam,
+                                Param::SelfParam { is_mut: false },
                                 Param::Typed {
                                     name: "x".to_string(),
                                     ty: Type::I32,
@@ -96,38 +114,41 @@ mod tests {
                             ],
                             return_type: Some(Type::I32),
                             is_extern: false,
-                            body: Some(return_block(amber_ast::Expression::BinaryExpr {
-                                left: Box::new(amber_ast::Expression::Identifier("x".to_string())),
+                            body: Some(return_block(Some(expr(ExpressionKind::BinaryExpr {
+                                left: ident("x"),
                                 op: amber_ast::BinaryOp::Add,
-                                right: Box::new(amber_ast::Expression::Identifier("y".to_string())),
-                            })),
+                                right: ident("y"),
+                            })))),
+                            span: Span::default(),
                         },
                         Function {
                             name: "reset".to_string(),
-                            params: vec![Param::SelfParam],
+                            params: vec![Param::SelfParam { is_mut: true }],
                             return_type: None,
                             is_extern: false,
-                            body: Some(Block {
-                                statements: vec![Statement::Return(None)],
-                            }),
+                            body: Some(return_block(None)),
+                            span: Span::default(),
                         },
                     ],
+                    span: Span::default(),
                 }),
                 Statement::Binding(amber_ast::VariableBinding {
                     modifier: Some(Modifier::Comptime),
                     is_mutable: false,
                     name: "BAUD".to_string(),
                     ty: Some(Type::I32),
-                    value: Some(amber_ast::Expression::Literal(Literal::Numeric(
+                    value: Some(expr(ExpressionKind::Literal(Literal::Numeric(
                         NumericLiteral::Integer(9600),
-                    ))),
+                    )))),
+                    span: Span::default(),
                 }),
             ],
         };
 
-        let output = generate_program(&program).unwrap();
+        let typed = amber_analysis::analyze(&program).unwrap();
+        let output = generate_program(&typed).unwrap();
 
-        let expected = "#include <stdint.h>\n#include <stdbool.h>\n\ntypedef struct {\n    int32_t x;\n    int32_t y;\n} Point;\n\nint32_t add(int32_t a, int32_t b) {\n    return (a + b);\n}\n\nextern void HAL_Delay(uint32_t ms);\n\nint32_t Point_sum(Point* self, int32_t x, int32_t y) {\n    return (x + y);\n}\n\nvoid Point_reset(Point* self) {\n    return;\n}\n\nconst int32_t BAUD = 9600;\n\n";
+        let expected = "#include <stdint.h>\n#include <stdbool.h>\n\n/* Frozen at compile time:\n *   BAUD = 9600\n */\n\ntypedef struct {\n    int32_t x;\n    int32_t y;\n} Point;\n\nint32_t add(int32_t a, int32_t b) {\n    return (a + b);\n}\n\nextern void HAL_Delay(uint32_t ms);\n\nint32_t Point_sum(const Point* self, int32_t x, int32_t y) {\n    return (x + y);\n}\n\nvoid Point_reset(Point* self) {\n    return;\n}\n\nconst int32_t BAUD = 9600;\n\n";
 
         assert_eq!(output, expected);
     }
@@ -0,0 +1,118 @@
+use crate::buffer::CodeBuffer;
+use crate::expression::render_initializer;
+use amber_analysis::{
+    TypedBlock, TypedElseBranch, TypedExpr, TypedExprKind, TypedFunction, TypedIfElse,
+    TypedProgram, TypedStatement,
+};
+use amber_ast::{Literal, Modifier, Type};
+
+/// Most elements of a frozen array listed in the report
+const MAX_LISTED: usize = 8;
+
+/// Emit a comment listing every binding whose value was computed at compile
+/// time, with the function it belongs to. Nothing is emitted if there is none.
+pub fn emit_frozen_report(buffer: &mut CodeBuffer, program: &TypedProgram) {
+    let mut frozen = Vec::new();
+    for statement in &program.statements {
+        match statement {
+            TypedStatement::Function(func) => collect_function(func, &func.name, &mut frozen),
+            TypedStatement::Impl(block) => {
+                for method in &block.methods {
+                    let scope = format!("{}::{}", block.target, method.name);
+                    collect_function(method, &scope, &mut frozen);
+                }
+            }
+            _ => collect_statement(statement, None, &mut frozen),
+        }
+    }
+    if frozen.is_empty() {
+        return;
+    }
+
+    buffer.push_line("/* Frozen at compile time:");
+    for line in frozen {
+        buffer.push_line(&format!(" *   {}", line));
+    }
+    buffer.push_line(" */");
+    buffer.push_line("");
+}
+
+fn collect_function(func: &TypedFunction, scope: &str, frozen: &mut Vec<String>) {
+    if let Some(body) = &func.body {
+        collect_block(body, Some(scope), frozen);
+    }
+}
+
+fn collect_block(block: &TypedBlock, scope: Option<&str>, frozen: &mut Vec<String>) {
+    for statement in &block.statements {
+        collect_statement(statement, scope, frozen);
+    }
+}
+
+fn collect_statement(statement: &TypedStatement, scope: Option<&str>, frozen: &mut Vec<String>) {
+    match statement {
+        TypedStatement::Binding(binding) if binding.modifier == Some(Modifier::Comptime) => {
+            if let Some(value) = &binding.value {
+                let entry = format!("{} = {}", binding.name, summarize(value));
+                frozen.push(match scope {
+                    Some(scope) => format!("{}: {}", scope, entry),
+                    None => entry,
+                });
+            }
+        }
+        TypedStatement::IfElse(if_else) => collect_if_else(if_else, scope, frozen),
+        TypedStatement::WhileLoop(while_loop) => collect_block(&while_loop.block, scope, frozen),
+        TypedStatement::ForLoop(for_loop) => collect_block(&for_loop.block, scope, frozen),
+        TypedStatement::Block(block) => collect_block(block, scope, frozen),
+        TypedStatement::Match(match_stmt) => {
+            for arm in &match_stmt.arms {
+                collect_block(&arm.body, scope, frozen);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_if_else(if_else: &TypedIfElse, scope: Option<&str>, frozen: &mut Vec<String>) {
+    collect_block(&if_else.then_block, scope, frozen);
+    match &if_else.else_branch {
+        Some(TypedElseBranch::If(next)) => collect_if_else(next, scope, frozen),
+        Some(TypedElseBranch::Block(block)) => collect_block(block, scope, frozen),
+        None => {}
+    }
+}
+
+/// Initializer of a frozen value, with long arrays cut short
+fn summarize(expr: &TypedExpr) -> String {
+    match &expr.kind {
+        TypedExprKind::ArrayLiteral(elements) => {
+            let listed: Vec<String> = elements.iter().take(MAX_LISTED).map(summarize).collect();
+            if elements.len() > MAX_LISTED {
+                format!(
+                    "{{ {}, ... }} ({} elements)",
+                    listed.join(", "),
+                    elements.len()
+                )
+            } else {
+                format!("{{ {} }}", listed.join(", "))
+            }
+        }
+        TypedExprKind::StructLiteral { fields, .. } => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(name, value)| format!(".{} = {}", name, summarize(value)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        // Shown as written, without ending the comment early
+        TypedExprKind::StringLiteral { value, .. } => {
+            let literal = if expr.ty == Type::Str {
+                Literal::String(value.clone())
+            } else {
+                Literal::CString(value.clone())
+            };
+            literal.to_string().replace("*/", "*\\/")
+        }
+        _ => render_initializer(expr),
+    }
+}
@@ -1,35 +1,50 @@
 use crate::buffer::CodeBuffer;
+use crate::declarations::{Prototypes, c_variant_name};
 use crate::errors::CodegenError;
-use crate::expression::render_expr;
-use crate::types::{binding_qualifier, type_to_c};
-use amber_ast::{Block, Expression, Statement, Type};
-pub fn emit_program(
-    buffer: &mut CodeBuffer,
-    program: &amber_ast::Program,
-) -> Result<(), CodegenError> {
+use crate::expression::{render_expr, render_initializer};
+use crate::types::{declaration_to_c, type_to_c};
+use amber_analysis::{
+    TypedBinding, TypedBlock, TypedElseBranch, TypedExpr, TypedExprKind, TypedForIterable,
+    TypedForLoop, TypedLoopExits, TypedMatch, TypedMatchArm, TypedPattern, TypedProgram,
+    TypedStatement,
+};
+use amber_ast::{Modifier, Type};
+pub fn emit_program(buffer: &mut CodeBuffer, program: &TypedProgram) -> Result<(), CodegenError> {
+    let mut prototypes = Prototypes::new(program)?;
+    crate::report::emit_frozen_report(buffer, program);
+    crate::declarations::emit_string_data(buffer, program);
     for statement in &program.statements {
-        emit_statement(buffer, statement)?;
+        emit_statement(buffer, statement, &mut prototypes)?;
     }
     Ok(())
 }
 
-pub fn emit_statement(buffer: &mut CodeBuffer, statement: &Statement) -> Result<(), CodegenError> {
+pub fn emit_statement(
+    buffer: &mut CodeBuffer,
+    statement: &TypedStatement,
+    prototypes: &mut Prototypes,
+) -> Result<(), CodegenError> {
     match statement {
-        Statement::Binding(binding) => emit_variable_binding(
-            buffer,
-            binding.is_mutable,
-            &binding.name,
-            binding.ty.as_ref(),
-            binding.value.as_ref(),
-        ),
-        Statement::ExprStatement(expr) => emit_expr_statement(buffer, expr),
-        Statement::Struct(def) => crate::declarations::emit_struct(buffer, def),
-        Statement::Function(func) => crate::declarations::emit_function(buffer, func, None),
-        Statement::Impl(block) => crate::declarations::emit_impl(buffer, block),
-        Statement::IfElse(_) | Statement::WhileLoop(_) => {
+        TypedStatement::Binding(binding) => emit_variable_binding(buffer, binding),
+        TypedStatement::ExprStatement(expr) => emit_expr_statement(buffer, expr),
+        TypedStatement::Struct(def) => crate::declarations::emit_struct(buffer, def),
+        TypedStatement::Enum(def) => crate::declarations::emit_enum(buffer, def),
+        TypedStatement::Union(def) => crate::declarations::emit_union(buffer, def),
+        TypedStatement::Function(func) => {
+            crate::declarations::emit_function(buffer, func, None, prototypes)
+        }
+        TypedStatement::Impl(block) => crate::declarations::emit_impl(buffer, block, prototypes),
+        TypedStatement::IfElse(_)
+        | TypedStatement::WhileLoop(_)
+        | TypedStatement::ForLoop(_)
+        | TypedStatement::Match(_)
+        | TypedStatement::Block(_) => {
             panic!("unexpected statement at top level: should be inside block")
         }
-        Statement::Assignment { .. } | Statement::Return(_) => {
+        TypedStatement::Assignment { .. }
+        | TypedStatement::Return(_)
+        | TypedStatement::Break(_)
+        | TypedStatement::Continue(_) => {
             panic!("unexpected statement at top level: {:?}", statement)
         }
     }
@@ -37,62 +52,48 @@ pub fn emit_statement(buffer: &mut CodeBuffer, statement: &Statement) -> Result<
 
 pub fn emit_variable_binding(
     buffer: &mut CodeBuffer,
-    is_mutable: bool,
-    name: &str,
-    ty: Option<&Type>,
-    value: Option<&Expression>,
+    binding: &TypedBinding,
 ) -> Result<(), CodegenError> {
-    let line = render_variable_binding_line(is_mutable, name, ty, value)?;
+    let line = render_variable_binding_line(binding);
     buffer.push_line(&line);
     buffer.push_line("");
     Ok(())
 }
 
-pub fn render_variable_binding_line(
-    is_mutable: bool,
-    name: &str,
-    ty: Option<&Type>,
-    value: Option<&Expression>,
-) -> Result<String, CodegenError> {
-    let ty = ty.ok_or_else(|| CodegenError::MissingType {
-        name: name.to_string(),
-    })?;
-    let mut line;
-    match ty {
-        Type::Pointer { inner: _inner, is_mut } => {
-            let data_qualifier = if *is_mut { "" } else { "const " };
-            let bind_qualifier = if is_mutable { "" } else { "const " };
-            line = format!("{}{} {}", data_qualifier, type_to_c(ty), bind_qualifier);
-        }
-        _ => {
-            let qualifier = binding_qualifier(is_mutable);
-            line = format!("{}{} ", qualifier, type_to_c(ty));
-        }
+pub fn render_variable_binding_line(binding: &TypedBinding) -> String {
+    let is_mutable = binding.is_mutable;
+    let mut line = declaration_to_c(&binding.ty, &binding.name, !is_mutable);
+    // A table or struct frozen at compile time is kept in read-only storage
+    // instead of being rebuilt on every use
+    if binding.modifier == Some(Modifier::Comptime)
+        && let Some(TypedExprKind::ArrayLiteral(_) | TypedExprKind::StructLiteral { .. }) =
+            binding.value.as_ref().map(|expr| &expr.kind)
+    {
+        line.insert_str(0, "static ");
     }
-    line.push_str(name);
 
-    if let Some(expr) = value {
+    if let Some(expr) = &binding.value {
         line.push_str(" = ");
-        line.push_str(&render_expr(expr));
+        line.push_str(&render_initializer(expr));
     }
     line.push(';');
-    Ok(line)
+    line
 }
 
-pub fn emit_expr_statement(buffer: &mut CodeBuffer, expr: &Expression) -> Result<(), CodegenError> {
+pub fn emit_expr_statement(buffer: &mut CodeBuffer, expr: &TypedExpr) -> Result<(), CodegenError> {
     let line = render_expr_statement_line(expr);
     buffer.push_line(&line);
     buffer.push_line("");
     Ok(())
 }
 
-pub fn render_expr_statement_line(expr: &Expression) -> String {
+pub fn render_expr_statement_line(expr: &TypedExpr) -> String {
     format!("{};", render_expr(expr))
 }
 
 pub fn emit_block(
     buffer: &mut CodeBuffer,
-    block: &Block,
+    block: &TypedBlock,
     indent: usize,
 ) -> Result<(), CodegenError> {
     for statement in &block.statements {
@@ -103,31 +104,26 @@ pub fn emit_block(
 
 pub fn emit_block_statement(
     buffer: &mut CodeBuffer,
-    statement: &Statement,
+    statement: &TypedStatement,
     indent: usize,
 ) -> Result<(), CodegenError> {
     match statement {
-        Statement::Binding(binding) => {
-            let line = render_variable_binding_line(
-                binding.is_mutable,
-                &binding.name,
-                binding.ty.as_ref(),
-                binding.value.as_ref(),
-            )?;
+        TypedStatement::Binding(binding) => {
+            let line = render_variable_binding_line(binding);
             buffer.push_indented_line(indent, &line);
             Ok(())
         }
-        Statement::ExprStatement(expr) => {
+        TypedStatement::ExprStatement(expr) => {
             let line = render_expr_statement_line(expr);
             buffer.push_indented_line(indent, &line);
             Ok(())
         }
-        Statement::Assignment { target, value } => {
+        TypedStatement::Assignment { target, value } => {
             let line = format!("{} = {};", render_expr(target), render_expr(value));
             buffer.push_indented_line(indent, &line);
             Ok(())
         }
-        Statement::Return(expr) => {
+        TypedStatement::Return(expr) => {
             if let Some(e) = expr {
                 let line = format!("return {};", render_expr(e));
                 buffer.push_indented_line(indent, &line);
@@ -136,24 +132,220 @@ pub fn emit_block_statement(
             }
             Ok(())
         }
-        Statement::IfElse(if_stmt) => {
+        TypedStatement::IfElse(if_stmt) => {
             let cond_str = render_expr(&if_stmt.condition);
             buffer.push_indented_line(indent, &format!("if ({}) {{", cond_str));
             emit_block(buffer, &if_stmt.then_block, indent + 1)?;
-            if let Some(else_block) = &if_stmt.else_block {
+            // An `else if` chain stays flat instead of nesting each `if`
+            let mut branch = &if_stmt.else_branch;
+            while let Some(TypedElseBranch::If(next)) = branch {
+                let cond_str = render_expr(&next.condition);
+                buffer.push_indented_line(indent, &format!("}} else if ({}) {{", cond_str));
+                emit_block(buffer, &next.then_block, indent + 1)?;
+                branch = &next.else_branch;
+            }
+            if let Some(TypedElseBranch::Block(else_block)) = branch {
                 buffer.push_indented_line(indent, "} else {");
                 emit_block(buffer, else_block, indent + 1)?;
             }
             buffer.push_indented_line(indent, "}");
             Ok(())
         }
-        Statement::WhileLoop(while_stmt) => {
+        TypedStatement::WhileLoop(while_stmt) => {
             let cond_str = render_expr(&while_stmt.condition);
             buffer.push_indented_line(indent, &format!("while ({}) {{", cond_str));
-            emit_block(buffer, &while_stmt.block, indent + 1)?;
+            emit_loop_body(buffer, &while_stmt.block, &while_stmt.exits, indent)
+        }
+        TypedStatement::Break(target) => {
+            let line = match target {
+                Some(id) => format!("goto {};", break_label(*id)),
+                None => "break;".to_string(),
+            };
+            buffer.push_indented_line(indent, &line);
+            Ok(())
+        }
+        TypedStatement::Continue(target) => {
+            let line = match target {
+                Some(id) => format!("goto {};", continue_label(*id)),
+                None => "continue;".to_string(),
+            };
+            buffer.push_indented_line(indent, &line);
+            Ok(())
+        }
+        TypedStatement::ForLoop(for_loop) => emit_for_loop(buffer, for_loop, indent),
+        TypedStatement::Block(block) => {
+            buffer.push_indented_line(indent, "{");
+            emit_block(buffer, block, indent + 1)?;
             buffer.push_indented_line(indent, "}");
             Ok(())
         }
+        TypedStatement::Match(match_stmt) => emit_match(buffer, match_stmt, indent),
         _ => panic!("Unexpected block statement: {:?}", statement),
     }
 }
+
+/// Index variable of a loop over an array
+const ARRAY_INDEX: &str = "_amber_i";
+
+/// Hoisted end of a range whose bound is not a plain name or literal
+const RANGE_END: &str = "_amber_end";
+
+/// Emit a `for` loop as a C `for`. A range end that is not a plain name or
+/// literal is evaluated once, alongside the loop variable; an array loop
+/// counts an index and copies the element into the loop variable.
+fn emit_for_loop(
+    buffer: &mut CodeBuffer,
+    for_loop: &TypedForLoop,
+    indent: usize,
+) -> Result<(), CodegenError> {
+    let variable = &for_loop.variable;
+    match &for_loop.iterable {
+        TypedForIterable::Range { start, end } => {
+            let mut init = format!(
+                "{} = {}",
+                declaration_to_c(&for_loop.ty, variable, false),
+                render_expr(start)
+            );
+            let bound = match end.kind {
+                TypedExprKind::Literal(_) | TypedExprKind::Identifier(_) => render_expr(end),
+                _ => {
+                    init.push_str(&format!(", {} = {}", RANGE_END, render_expr(end)));
+                    RANGE_END.to_string()
+                }
+            };
+            let line = format!(
+                "for ({}; {} < {}; {}++) {{",
+                init, variable, bound, variable
+            );
+            buffer.push_indented_line(indent, &line);
+        }
+        TypedForIterable::Array(array) => {
+            let Type::Array { len, .. } = array.ty else {
+                unreachable!("analysis only allows loops over arrays");
+            };
+            let line = format!(
+                "for ({} {i} = 0; {i} < {}; {i}++) {{",
+                type_to_c(&Type::U32),
+                len,
+                i = ARRAY_INDEX
+            );
+            buffer.push_indented_line(indent, &line);
+            let element = format!(
+                "{} = {}[{}];",
+                declaration_to_c(&for_loop.ty, variable, true),
+                render_expr(array),
+                ARRAY_INDEX
+            );
+            buffer.push_indented_line(indent + 1, &element);
+        }
+    }
+    emit_loop_body(buffer, &for_loop.block, &for_loop.exits, indent)
+}
+
+/// Emit a loop's body and closing brace, with the labels its `goto`-based
+/// `break` and `continue` statements jump to. C has no labeled loops, so a
+/// jump to the end of the body continues the loop and a jump past the loop
+/// leaves it.
+fn emit_loop_body(
+    buffer: &mut CodeBuffer,
+    block: &TypedBlock,
+    exits: &TypedLoopExits,
+    indent: usize,
+) -> Result<(), CodegenError> {
+    emit_block(buffer, block, indent + 1)?;
+    if exits.continue_label {
+        buffer.push_indented_line(indent + 1, &format!("{}:;", continue_label(exits.id)));
+    }
+    buffer.push_indented_line(indent, "}");
+    if exits.break_label {
+        buffer.push_indented_line(indent, &format!("{}:;", break_label(exits.id)));
+    }
+    Ok(())
+}
+
+fn break_label(id: usize) -> String {
+    format!("_amber_break_{}", id)
+}
+
+fn continue_label(id: usize) -> String {
+    format!("_amber_continue_{}", id)
+}
+
+/// Name of the copy a tagged union is matched through, so the scrutinee is
+/// evaluated once and payload bindings can read from it
+const MATCH_VALUE: &str = "_amber_match";
+
+/// Emit a match as a `switch`. Every arm ends in `break`, so there is no
+/// fallthrough between arms. A tagged union is copied into a local first and
+/// the switch is on its tag.
+fn emit_match(
+    buffer: &mut CodeBuffer,
+    match_stmt: &TypedMatch,
+    indent: usize,
+) -> Result<(), CodegenError> {
+    let scrutinee = render_expr(&match_stmt.scrutinee);
+    if !match_stmt.tagged {
+        buffer.push_indented_line(indent, &format!("switch ({}) {{", scrutinee));
+        emit_match_arms(buffer, &match_stmt.arms, indent)?;
+        buffer.push_indented_line(indent, "}");
+        return Ok(());
+    }
+
+    let copy = declaration_to_c(&match_stmt.scrutinee.ty, MATCH_VALUE, true);
+    buffer.push_indented_line(indent, "{");
+    buffer.push_indented_line(indent + 1, &format!("{} = {};", copy, scrutinee));
+    buffer.push_indented_line(indent + 1, &format!("switch ({}.tag) {{", MATCH_VALUE));
+    emit_match_arms(buffer, &match_stmt.arms, indent + 1)?;
+    buffer.push_indented_line(indent + 1, "}");
+    buffer.push_indented_line(indent, "}");
+    Ok(())
+}
+
+fn emit_match_arms(
+    buffer: &mut CodeBuffer,
+    arms: &[TypedMatchArm],
+    indent: usize,
+) -> Result<(), CodegenError> {
+    for arm in arms {
+        let (last, labels) = arm
+            .patterns
+            .split_last()
+            .expect("match arms have at least one pattern");
+        for pattern in labels {
+            buffer.push_indented_line(indent + 1, &format!("{}:", render_case_label(pattern)));
+        }
+        buffer.push_indented_line(indent + 1, &format!("{}: {{", render_case_label(last)));
+        // Only an arm with a single pattern can bind payload fields
+        if let TypedPattern::Variant {
+            variant, bindings, ..
+        } = last
+        {
+            for binding in bindings {
+                let line = format!(
+                    "{} = {}.as.{}.{};",
+                    declaration_to_c(&binding.ty, &binding.name, true),
+                    MATCH_VALUE,
+                    variant,
+                    binding.field
+                );
+                buffer.push_indented_line(indent + 2, &line);
+            }
+        }
+        emit_block(buffer, &arm.body, indent + 2)?;
+        buffer.push_indented_line(indent + 2, "break;");
+        buffer.push_indented_line(indent + 1, "}");
+    }
+    Ok(())
+}
+
+fn render_case_label(pattern: &TypedPattern) -> String {
+    match pattern {
+        TypedPattern::Wildcard => "default".to_string(),
+        TypedPattern::Value(value) => format!("case {}", render_expr(value)),
+        TypedPattern::Variant {
+            union_name,
+            variant,
+            ..
+        } => format!("case {}", c_variant_name(union_name, variant)),
+    }
+}
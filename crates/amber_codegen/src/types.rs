@@ -1,26 +1,69 @@
 use amber_ast::Type;
 use std::ops::Deref;
 
-pub fn binding_qualifier(is_mutable: bool) -> String {
-    let mut flags = Vec::new();
-    if !is_mutable {
-        flags.push("const");
+/// Abstract C type name, as used in casts and return types
+pub fn type_to_c(ty: &Type) -> String {
+    declaration_to_c(ty, "", false)
+}
+
+/// Declare `name` with type `ty`. `is_const` makes the declared object
+/// itself read-only; pointers pass `!is_mut` down to their pointee, and
+/// arrays pass it to their elements.
+pub fn declaration_to_c(ty: &Type, name: &str, is_const: bool) -> String {
+    let (specifier, declarator) = split_declaration(ty, name.to_string(), is_const);
+    // Pointer stars stay on the type side, `uint8_t* const p`, while the
+    // name and any array suffix are separated from them
+    let mut rest = declarator.as_str();
+    while let Some(next) = rest.strip_prefix('*').or_else(|| strip_const(rest)) {
+        rest = next;
     }
-    if flags.is_empty() {
-        String::new()
+    let stars = &declarator[..declarator.len() - rest.len()];
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with('[') {
+        format!("{}{}{}", specifier, stars, rest)
     } else {
-        format!("{} ", flags.join(" "))
+        format!("{}{} {}", specifier, stars, rest)
     }
 }
 
-pub fn type_to_c(ty: &Type) -> String {
+/// Strip a leading ` const` qualifier, but not the start of a name like `constant`
+fn strip_const(declarator: &str) -> Option<&str> {
+    declarator
+        .strip_prefix(" const")
+        .filter(|rest| !rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_'))
+}
+
+/// Build the type specifier and declarator for `declarator` of type `ty`,
+/// working from the outermost type inwards
+fn split_declaration(ty: &Type, declarator: String, is_const: bool) -> (String, String) {
     match ty {
-        Type::Named(name) => name.clone(),
-        Type::Pointer { inner, is_mut: _is_mut } => {
-            let inner_type = type_to_c(inner.deref());
-            format!("{}*", inner_type)
+        Type::Pointer { inner, is_mut } => {
+            let qualifier = match (is_const, declarator.chars().next()) {
+                (false, _) => "",
+                (true, None | Some('*')) => " const",
+                (true, _) => " const ",
+            };
+            let declarator = match inner.deref() {
+                // `*[4]u8` needs parentheses: `uint8_t (*p)[4]`
+                Type::Array { .. } => format!("(*{}{})", qualifier, declarator),
+                _ => format!("*{}{}", qualifier, declarator),
+            };
+            split_declaration(inner, declarator, !is_mut)
+        }
+        Type::Array { inner, len } => {
+            split_declaration(inner, format!("{}[{}]", declarator, len), is_const)
+        }
+        _ => {
+            let specifier = match ty {
+                Type::Named(name) => name.clone(),
+                _ => builtin_type_to_c(ty),
+            };
+            if is_const {
+                (format!("const {}", specifier), declarator)
+            } else {
+                (specifier, declarator)
+            }
         }
-        _ => builtin_type_to_c(ty),
     }
 }
 
@@ -39,6 +82,7 @@ pub fn builtin_type_to_c(ty: &Type) -> String {
         Type::Bool => "bool".into(),
         Type::Char => "char".into(),
         Type::Void => "void".into(),
+        Type::Str => "amber_str".into(),
         _ => panic!("{:?} is not a  builtin type", ty),
     }
 }
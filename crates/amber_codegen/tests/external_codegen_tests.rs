@@ -1,7 +1,11 @@
 use std::fs;
+use std::io::ErrorKind;
+use std::process::Command;
 
+use amber_analysis::analyze;
 use amber_codegen::generate_program;
 use amber_parser::build_ast_with_name;
+use amber_vm::{Overflow, fold_program, resolve_program};
 
 // Helper function to read test files and generate C code
 fn test_amber_file(fixture_name: &str) -> Result<String, String> {
@@ -12,9 +16,36 @@ fn test_amber_file(fixture_name: &str) -> Result<String, String> {
     let program = build_ast_with_name(&source, fixture_path.clone())
         .map_err(|e| format!("Failed to parse '{}': {}", fixture_path, e))?;
 
+    let resolved = resolve_program(&program, Overflow::default())
+        .map_err(|e| format!("Failed to resolve '{}': {}", fixture_path, e))?;
+    analyze(&resolved).map_err(|e| format!("Failed to analyze '{}': {:?}", fixture_path, e))?;
+
+    let program =
+        fold_program(&program).map_err(|e| format!("Failed to fold '{}': {}", fixture_path, e))?;
+
+    let program =
+        analyze(&program).map_err(|e| format!("Failed to analyze '{}': {:?}", fixture_path, e))?;
+
     generate_program(&program).map_err(|e| format!("Failed to generate C code: {}", e))
 }
 
+// Check generated C with the system compiler, when there is one
+fn assert_compiles(fixture_name: &str, code: &str) {
+    let path = std::env::temp_dir().join(format!("amber_{}_{}.c", fixture_name, std::process::id()));
+    fs::write(&path, code).expect("generated C should be writable");
+    let output = Command::new("cc").args(["-std=c99", "-fsyntax-only"]).arg(&path).output();
+    fs::remove_file(&path).ok();
+    match output {
+        Ok(output) => assert!(
+            output.status.success(),
+            "generated C does not compile:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        ),
+        Err(e) if e.kind() == ErrorKind::NotFound => eprintln!("no C compiler, skipping compile check"),
+        Err(e) => panic!("failed to run the C compiler: {}", e),
+    }
+}
+
 #[test]
 fn test_variables_codegen() {
     let result = test_amber_file("variables").expect("Variables test should succeed");
@@ -34,6 +65,18 @@ fn test_functions_codegen() {
     assert!(result.contains("void print_hello(void)"));
     assert!(result.contains("extern void external_func(uint32_t x);"));
     assert!(result.contains("void main(void)"));
+
+    // Calls render as plain C calls
+    assert!(result.contains("const int32_t result = add(a, b);"));
+    assert!(result.contains("print_hello();"));
+    assert!(result.contains("external_func(100U);"));
+
+    // A function called before its definition is declared ahead of the caller
+    let prototype = result
+        .find("int32_t twice(int32_t value);")
+        .expect("twice should be forward declared");
+    assert!(prototype < result.find("void main(void)").unwrap());
+    assert!(!result.contains("int32_t add(int32_t a, int32_t b);"));
 }
 
 #[test]
@@ -47,7 +90,29 @@ fn test_structs_codegen() {
     assert!(result.contains("} Point;"));
 
     // Check for method implementations
-    assert!(result.contains("int32_t Point_get_x(Point* self, int32_t value)"));
+    assert!(result.contains("int32_t Point_get_x(const Point* self, int32_t value)"));
+    assert!(result.contains("void Point_bump(Point* self)"));
+
+    // Method calls pass the receiver's address, or `self` as is
+    assert!(result.contains("const int32_t a = Point_total(&p, 1);"));
+    assert!(result.contains("return (Point_get_x(self, value) + Point_scale(self, value));"));
+    assert!(result.contains("const int32_t b = Point_origin();"));
+    assert!(result.contains("Point_bump(&p);"));
+
+    // A method called before its definition is declared ahead of the caller
+    assert!(result.contains(
+        "int32_t Point_scale(const Point* self, int32_t value);\n\nint32_t Point_total(const Point* self, int32_t value) {"
+    ));
+
+    // Struct literals initialize bindings with designated initializers and
+    // are compound literals elsewhere
+    assert!(result.contains("const Point ORIGIN = { .x = 0, .y = 0 };"));
+    assert!(result.contains("Point p = { .x = 1, .y = 2 };"));
+    assert!(result.contains("p = (Point){ .x = a, .y = b };"));
+
+    // Fields are accessed with `.` on values and `->` through pointers
+    assert!(result.contains("(p.y) = (p.x);"));
+    assert!(result.contains("(p->x) = ((p->x) + 1);"));
 }
 
 #[test]
@@ -59,6 +124,10 @@ fn test_control_flow_codegen() {
 
     // Check for if statement
     assert!(result.contains("if ((counter > 0))"));
+    assert!(result.contains("    } else {\n        const int32_t result = 0;\n    }"));
+
+    // An else-if chain is emitted flat
+    assert!(result.contains("    if ((counter > 10)) {\n        counter = 10;\n    } else if ((counter > 5)) {\n        counter = 5;\n    } else if ((counter < 0)) {\n        counter = 0;\n    } else {\n        counter = 1;\n    }"));
 }
 
 #[test]
@@ -98,5 +167,282 @@ fn test_pointer() {
     assert!(result.contains("uint8_t* const p1;"));
     assert!(result.contains("uint8_t* p2;"));
     assert!(result.contains("const uint8_t* p3;"));
-    assert!(result.contains("(*p3) = 1;"))
+    assert!(result.contains("(*p2) = 1;"));
+
+    // Address-of yields pointers with the requested mutability
+    assert!(result.contains("const uint8_t* const readonly = &value;"));
+    assert!(result.contains("uint8_t* const writable = &value;"));
+
+    // `const` qualifies the right level of nested pointers
+    assert!(result.contains("const uint8_t** pp = &p3;"));
+    assert!(result.contains("uint8_t* const* const cp = &p2;"));
+    assert!(result.contains("(*(*cp)) = 4;"));
+
+    // A returned pointer to an array wraps the function declarator
+    assert!(result.contains("const uint8_t (*same_row(const uint8_t (*row)[4]))[4] {"));
+    assert_compiles("pointer", &result);
+}
+
+#[test]
+fn test_arrays() {
+    let result = test_amber_file("arrays").expect("arrays test should succeed");
+    println!("{}", result);
+    assert!(result.contains("    uint8_t payload[4];"));
+    assert!(result.contains("const uint16_t TABLE[3] = { 10, 20, 30 };"));
+    assert!(result.contains("uint8_t buf[4] = { 1, 2, 3, 4 };"));
+    assert!(result.contains("const int32_t grid[2][3] = { { 1, 2, 3 }, { 4, 5, 6 } };"));
+    assert!(
+        result.contains("Packet packet = { .header = { 10, 11 }, .payload = { 0, 0, 0, 0 } };")
+    );
+
+    // Arrays of pointers and pointers to arrays
+    assert!(result.contains("const uint8_t* const ptrs[2] = { &(buf[0]), &(buf[1]) };"));
+    assert!(result.contains("uint8_t* slots[2];"));
+    assert!(result.contains("const uint8_t (* const view)[4] = &buf;"));
+    assert!(result.contains("uint8_t sum(const uint8_t (*values)[4])"));
+    assert!(result.contains("void clear(uint8_t (*values)[4])"));
+    assert!(result.contains("((*values)[0]) = 0;"));
+}
+
+#[test]
+fn test_enums() {
+    let result = test_amber_file("enums").expect("enums test should succeed");
+    println!("{}", result);
+
+    // An underlying type gives a fixed-width typedef with constants
+    assert!(result.contains("typedef uint8_t State;"));
+    assert!(result.contains("#define State_Running ((State)4)"));
+    assert!(result.contains("#define State_Done ((State)5)"));
+
+    // Otherwise a plain C enum
+    assert!(result.contains("typedef enum {\n    Direction_North = 0,\n    Direction_South = 10,\n} Direction;"));
+
+    // Match arms become cases that never fall through
+    assert!(result.contains("switch (state) {"));
+    assert!(result.contains("        case State_Running:\n        case State_Done: {"));
+    assert!(result.contains("            result = State_Done;\n            break;"));
+    assert!(result.contains("        default: {"));
+    assert!(result.contains("        case 0U: {"));
+    assert!(result.contains("if (((machine.state) == State_Done))"));
+}
+
+#[test]
+fn test_unions() {
+    let result = test_amber_file("unions").expect("unions test should succeed");
+    println!("{}", result);
+
+    // Tags follow the enum rules, then the payloads share a C union
+    assert!(result.contains("typedef uint8_t Packet_Tag;\n#define Packet_Ping ((Packet_Tag)0)"));
+    assert!(result.contains("typedef enum {\n    Event_Reset = 0,\n    Event_Key = 1,\n} Event_Tag;"));
+    assert!(result.contains("typedef struct {\n    Packet_Tag tag;\n    union {\n        struct {\n            uint8_t _0;\n            const uint8_t* _1;\n        } Data;"));
+    assert!(result.contains("        } Move;\n    } as;\n} Packet;"));
+
+    // Values set the tag and the matching payload member
+    assert!(result.contains("return (Packet){ .tag = Packet_Ping };"));
+    assert!(result.contains("return (Packet){ .tag = Packet_Data, .as.Data = { ._0 = len, ._1 = bytes } };"));
+    assert!(result.contains("const Packet step = { .tag = Packet_Move, .as.Move = { .x = 1, .y = 2 } };"));
+
+    // Matching switches on the tag of a copy and binds payload fields from it
+    assert!(result.contains("        const Packet _amber_match = packet;\n        switch (_amber_match.tag) {"));
+    assert!(result.contains("            case Packet_Move: {\n                const int32_t x = _amber_match.as.Move.x;\n                const int32_t dy = _amber_match.as.Move.y;"));
+}
+
+#[test]
+fn test_for_loops() {
+    let result = test_amber_file("for_loops").expect("for_loops test should succeed");
+    println!("{}", result);
+
+    // Ranges count up to an end that is evaluated once
+    assert!(result.contains("for (uint8_t i = 0; i < limit; i++) {\n        total = ((uint8_t)(total + i));\n    }"));
+    assert!(result.contains("for (uint32_t i = first, _amber_end = (first + 2U); i < _amber_end; i++) {"));
+
+    // Array loops copy each element into the loop variable
+    assert!(result.contains("for (uint32_t _amber_i = 0; _amber_i < 4; _amber_i++) {\n        const uint32_t value = (*values)[_amber_i];"));
+
+    // A comptime loop becomes one block per iteration
+    assert!(result.contains("    {\n        const uint8_t MASK = 1;\n        (masks[0]) = MASK;\n    }"));
+    assert!(result.contains("    {\n        const uint8_t MASK = 8;\n        (masks[3]) = MASK;\n    }"));
+}
+
+#[test]
+fn test_type_inference() {
+    let result = test_amber_file("type_inference").expect("type_inference test should succeed");
+    println!("{}", result);
+
+    // Without any use that asks for a type, integers default to int64_t
+    assert!(result.contains("const int64_t baud_rate = 9600LL;"));
+    assert!(result.contains("    int64_t count = 0LL;"));
+
+    // The first use decides the type of an untyped literal binding
+    assert!(result.contains("const uint8_t RETRIES = 3;"));
+    assert!(result.contains("    const uint8_t mask = 128;"));
+    assert!(result.contains("    const uint16_t step = 4;"));
+    assert!(result.contains("    const uint8_t limit = 10;"));
+    assert!(result.contains("    const double half = 0.5;"));
+
+    // Any other initializer gives its own type
+    assert!(result.contains("    const bool sent = uart_write(mask);"));
+
+    // Without an initializer, the first assignment gives the type
+    assert!(result.contains("    bool status;\n    status = uart_write(mask);"));
+}
+
+#[test]
+fn test_int_literals() {
+    let result = test_amber_file("int_literals").expect("int_literals test should succeed");
+    println!("{}", result);
+
+    // Other bases and separators are written out in decimal
+    assert!(result.contains("const uint8_t PIN_MASK = 32;"));
+    assert!(result.contains("const uint16_t PERMISSIONS = 420;"));
+
+    // 32-bit unsigned and 64-bit constants carry their C suffix
+    assert!(result.contains("const uint32_t HIGH_HALF = 4294901760U;"));
+    assert!(result.contains("const uint64_t SEED = 16045690984503111693ULL;"));
+    assert!(result.contains("const uint32_t LIMIT = 1000000U;"));
+    assert!(result.contains("    return (1U << n);"));
+    assert!(result.contains("    return (1LL << n);"));
+
+    // A type suffix gives an unannotated binding its type
+    assert!(result.contains("    uint8_t port = 0;"));
+    assert!(result.contains("    const int8_t low = -128;"));
+
+    // The negation of 9223372036854775808LL would overflow
+    assert!(result.contains("    const int64_t lowest = (-9223372036854775807LL - 1);"));
+    assert!(result.contains("    offset = (-9223372036854775807LL - 1);"));
+    assert_compiles("int_literals", &result);
+}
+
+#[test]
+fn test_chars() {
+    let result = test_amber_file("chars").expect("chars test should succeed");
+    println!("{}", result);
+
+    // Characters are quoted, with escapes C understands
+    assert!(result.contains(r"const char NEWLINE = '\n';"));
+    assert!(result.contains(r"const char TERMINATOR = '\000';"));
+    assert!(result.contains(r"const char DELETE = '\177';"));
+    assert!(result.contains(r"const char QUOTE = '\'';"));
+    assert!(result.contains(r"const char BACKSLASH = '\\';"));
+    assert!(result.contains("        case 'a': {"));
+    assert!(result.contains("        case '\\t':\n        case ' ': {"));
+
+    // Byte literals are u8, written as numbers past ASCII
+    assert!(result.contains("const uint8_t START = 'A';"));
+    assert!(result.contains("const uint8_t HIGH = 255;"));
+    assert!(result.contains(r"    const bool sent = uart_write('\r');"));
+    assert!(result.contains("    uint8_t next = 66;"));
+}
+
+#[test]
+fn test_strings() {
+    let result = test_amber_file("strings").expect("strings test should succeed");
+    println!("{}", result);
+
+    // `str` is a pointer and a length, declared once before its first use
+    assert!(result.contains("typedef struct {\n    const uint8_t* ptr;\n    uint32_t len;\n} amber_str;"));
+    assert!(result.contains("    amber_str text;\n} LogLine;"));
+    assert!(result.contains("void uart_print(amber_str text) {"));
+    assert!(result.contains("_amber_end = (text.len); i < _amber_end; i++) {"));
+
+    // Every distinct literal is stored once, escaped for C
+    assert!(result.contains(r#"static const char amber_string_0[] = "amber v0.1 \"dev\"\n";"#));
+    assert!(result.contains(r#"static const char amber_string_1[] = "> ";"#));
+    assert!(result.contains(r#"static const char amber_string_2[] = "ready?\?!\t\177";"#));
+    assert!(result.contains(r#"static const char amber_string_3[] = "boot ok\n";"#));
+    assert!(!result.contains("amber_string_4"));
+
+    // A `str` refers to the data with its length; a C string is just the pointer
+    assert!(result.contains("const amber_str BANNER = { (const uint8_t*)amber_string_0, 17 };"));
+    assert!(result.contains("    uart_print(((amber_str){ (const uint8_t*)amber_string_2, 10 }));"));
+    assert!(result.contains("    const LogLine line = { .level = 2, .text = { (const uint8_t*)amber_string_2, 10 } };"));
+    assert!(result.contains("const uint8_t* const PROMPT = ((const uint8_t*)amber_string_1);"));
+    assert!(result.contains("    const int32_t status = printf(((const uint8_t*)amber_string_3));"));
+
+    // The frozen report shows the string as written
+    assert!(result.contains(r#" *   BANNER = "amber v0.1 \"dev\"\n""#));
+}
+
+#[test]
+fn test_break_continue() {
+    let result = test_amber_file("break_continue").expect("break_continue test should succeed");
+    println!("{}", result);
+
+    // Jumps to the innermost loop use the plain statements
+    assert!(result.contains("        if ((value == target)) {\n            break;\n        }"));
+    assert!(result.contains("            case Command_Skip: {\n                continue;"));
+
+    // Labeled jumps go to the end of the outer loop's body or past the loop
+    assert!(result.contains("                goto _amber_continue_1;"));
+    assert!(result.contains("                goto _amber_break_1;"));
+    assert!(result.contains("        }\n        _amber_continue_1:;\n    }\n    _amber_break_1:;\n    return hits;"));
+
+    // A `break` inside a match must not stop at the `switch`
+    assert!(result.contains("            case Command_Stop: {\n                goto _amber_break_3;"));
+    assert!(result.contains("    }\n    _amber_break_3:;\n}"));
+}
+
+#[test]
+fn test_comptime_if() {
+    let result = test_amber_file("comptime_if").expect("comptime_if test should succeed");
+    println!("{}", result);
+
+    // Only the selected declaration is emitted
+    assert!(result.contains("int32_t scale(int32_t x) {\n    return (x >> 1);\n}"));
+    assert!(!result.contains("float scale"));
+    assert!(!result.contains("(x / 2)"));
+
+    // Inside a body the selected statements join the enclosing block, and
+    // calls fold through the selected function
+    assert!(result.contains("void main(void) {\n    const bool verbose = true;\n    const int32_t half = 4;\n}"));
+}
+
+#[test]
+fn test_comptime_calls() {
+    let result = test_amber_file("comptime_calls").expect("comptime_calls test should succeed");
+    println!("{}", result);
+
+    // Calls are replaced by their results, and the functions are still emitted
+    assert!(result.contains("const uint32_t UBRR = 103U;"));
+    assert!(result.contains("const uint32_t HEADER_CRC = 151U;"));
+    assert!(result.contains("    const uint32_t FAST_UBRR = 8U;"));
+    assert!(result.contains("uint32_t crc8(uint32_t byte) {"));
+
+    // Every frozen binding is listed ahead of the code
+    assert!(result.contains("/* Frozen at compile time:\n *   CLOCK_HZ = 16000000U\n *   UBRR = 103U\n *   HEADER_CRC = 151U\n *   main: FAST_UBRR = 8U\n */"));
+}
+
+#[test]
+fn test_comptime_tables() {
+    let result = test_amber_file("comptime_tables").expect("comptime_tables test should succeed");
+    println!("{}", result);
+
+    // Tables and structs built by comptime calls become static initializers
+    assert!(result.contains("static const uint8_t CRC_TABLE[256] = { 0, 7, 14, 9, 28, 27, 18, 21, 56, 63,"));
+    assert!(result.contains(", 250, 253, 244, 243 };"));
+    assert!(result.contains("static const UartConfig UART = { .baud = 9600U, .divisor = 103U, .parity = false };"));
+    // Reads of frozen values fold like any other comptime expression
+    assert!(result.contains("    const uint32_t divisor = 103U;"));
+    assert!(result.contains("        crc = (CRC_TABLE[(crc ^ byte)]);"));
+
+    // Long tables are cut short in the report
+    assert!(result.contains(" *   CRC_TABLE = { 0, 7, 14, 9, 28, 27, 18, 21, ... } (256 elements)\n"));
+    assert!(result.contains(" *   UART = { .baud = 9600U, .divisor = 103U, .parity = false }\n"));
+}
+
+#[test]
+fn test_narrow_arithmetic() {
+    let result = test_amber_file("narrow_arithmetic").expect("narrow_arithmetic test should succeed");
+    println!("{}", result);
+
+    // Results that C would leave in `int` are cast back to their type
+    assert!(result.contains("    return (((uint8_t)(a + b)) / 2);"));
+    assert!(result.contains("    return ((uint8_t)(~x));"));
+    assert!(result.contains("    return ((int8_t)(-x));"));
+    assert!(result.contains("    return ((int16_t)(a / b));"));
+    assert!(result.contains("    return ((a + b) / 2U);"));
+    assert!(result.contains("    const uint8_t mask = (complement(sum) & 15);"));
+    // A call that overflows at compile time is left for the C to compute
+    assert!(result.contains("    const uint8_t overflowed = average(200, 100);"));
+    assert_compiles("narrow_arithmetic", &result);
 }
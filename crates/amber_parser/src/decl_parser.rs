@@ -1,48 +1,161 @@
 use pest::iterators::Pair;
 
-use amber_ast::{Function, ImplBlock, Param, StructDef, StructField};
+use amber_ast::{
+    EnumDef, EnumVariant, FileId, Function, ImplBlock, Param, StructDef, StructField, UnionDef,
+    UnionVariant, VariantPayload,
+};
 
+use crate::error::SyntaxError;
+use crate::expr_parser::parse_expr;
 use crate::stmt_parser::parse_block;
-use crate::utils::parse_type;
+use crate::utils::{parse_type, span_of};
 use crate::Rule;
 
 /// Parse a struct definition
-pub fn parse_struct(pair: Pair<Rule>) -> StructDef {
-    let mut inner = pair.into_inner();
+pub fn parse_struct(pair: Pair<Rule>, file: FileId) -> Result<StructDef, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut inner = pair.clone().into_inner();
     let name = inner
         .find(|p| p.as_rule() == Rule::ident)
-        .expect("struct must have a name")
+        .ok_or_else(|| SyntaxError::missing("struct definition", "a name", &pair))?
         .as_str()
         .to_string();
 
     let mut fields = Vec::new();
     for part in inner {
         if part.as_rule() == Rule::struct_fields {
-            fields = part.into_inner().map(parse_struct_field).collect();
+            fields = part
+                .into_inner()
+                .map(|p| parse_struct_field(p, file))
+                .collect::<Result<_, _>>()?;
         }
     }
 
-    StructDef { name, fields }
+    Ok(StructDef { name, fields, span })
 }
 
 /// Parse a single struct field
-fn parse_struct_field(pair: Pair<Rule>) -> StructField {
-    let mut inner = pair.into_inner();
+fn parse_struct_field(pair: Pair<Rule>, file: FileId) -> Result<StructField, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut inner = pair.clone().into_inner();
     let name = inner
         .next()
-        .expect("struct field needs name")
+        .ok_or_else(|| SyntaxError::missing("struct field", "a name", &pair))?
         .as_str()
         .to_string();
-    let ty_pair = inner.next().expect("struct field needs type");
+    let ty_pair = inner
+        .next()
+        .ok_or_else(|| SyntaxError::missing("struct field", "a type", &pair))?;
 
-    StructField {
+    Ok(StructField {
         name,
-        ty: parse_type(ty_pair),
+        ty: parse_type(ty_pair, file)?,
+        span,
+    })
+}
+
+/// Parse an enum definition with an optional underlying type
+pub fn parse_enum(pair: Pair<Rule>, file: FileId) -> Result<EnumDef, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut name = String::new();
+    let mut repr = None;
+    let mut variants = Vec::new();
+
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::ident => name = part.as_str().to_string(),
+            Rule::type_def => repr = Some(parse_type(part, file)?),
+            Rule::enum_variant => variants.push(parse_enum_variant(part, file)?),
+            _ => {}
+        }
     }
+
+    Ok(EnumDef {
+        name,
+        repr,
+        variants,
+        span,
+    })
+}
+
+/// Parse a single enum variant
+fn parse_enum_variant(pair: Pair<Rule>, file: FileId) -> Result<EnumVariant, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut inner = pair.clone().into_inner();
+    let name = inner
+        .next()
+        .ok_or_else(|| SyntaxError::missing("enum variant", "a name", &pair))?
+        .as_str()
+        .to_string();
+    let value = inner.next().map(|p| parse_expr(p, file)).transpose()?;
+
+    Ok(EnumVariant { name, value, span })
 }
 
-/// Parse a function definition
-pub fn parse_function(pair: Pair<Rule>) -> Function {
+/// Parse a tagged union definition with an optional tag type
+pub fn parse_union(pair: Pair<Rule>, file: FileId) -> Result<UnionDef, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut name = String::new();
+    let mut repr = None;
+    let mut variants = Vec::new();
+
+    for part in pair.into_inner() {
+        match part.as_rule() {
+            Rule::ident => name = part.as_str().to_string(),
+            Rule::type_def => repr = Some(parse_type(part, file)?),
+            Rule::union_variant => variants.push(parse_union_variant(part, file)?),
+            _ => {}
+        }
+    }
+
+    Ok(UnionDef {
+        name,
+        repr,
+        variants,
+        span,
+    })
+}
+
+/// Parse a single union variant and its payload
+fn parse_union_variant(pair: Pair<Rule>, file: FileId) -> Result<UnionVariant, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut inner = pair.clone().into_inner();
+    let name = inner
+        .next()
+        .ok_or_else(|| SyntaxError::missing("union variant", "a name", &pair))?
+        .as_str()
+        .to_string();
+    let payload = match inner.next() {
+        None => VariantPayload::Unit,
+        Some(part) if part.as_rule() == Rule::tuple_payload => VariantPayload::Tuple(
+            part.into_inner()
+                .map(|p| parse_type(p, file))
+                .collect::<Result<_, _>>()?,
+        ),
+        Some(part) => {
+            let fields = part
+                .into_inner()
+                .flat_map(|p| p.into_inner())
+                .map(|p| parse_struct_field(p, file))
+                .collect::<Result<_, _>>()?;
+            VariantPayload::Struct(fields)
+        }
+    };
+
+    Ok(UnionVariant {
+        name,
+        payload,
+        span,
+    })
+}
+
+/// Parse a function definition. Errors inside its body are collected in `errors`.
+pub fn parse_function(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+) -> Result<Function, SyntaxError> {
+    let span = span_of(&pair, file);
     let mut name = String::new();
     let mut params = Vec::new();
     let mut return_type = None;
@@ -54,75 +167,99 @@ pub fn parse_function(pair: Pair<Rule>) -> Function {
             Rule::extern_modifier => is_extern = true,
             Rule::ident => name = part.as_str().to_string(),
             Rule::parameter_list => {
-                params = part.into_inner().map(parse_param).collect();
+                params = part
+                    .into_inner()
+                    .map(|p| parse_param(p, file))
+                    .collect::<Result<_, _>>()?;
             }
             Rule::return_type => {
-                let mut inner = part.into_inner();
-                let ty_pair = inner.next().expect("return type must contain a type");
-                return_type = Some(parse_type(ty_pair));
+                let ty_pair = part
+                    .clone()
+                    .into_inner()
+                    .next()
+                    .ok_or_else(|| SyntaxError::missing("return type", "a type", &part))?;
+                return_type = Some(parse_type(ty_pair, file)?);
             }
             Rule::function_body => {
-                if let Some(block_pair) = part.into_inner().next() {
-                    if block_pair.as_rule() == Rule::block {
-                        body = Some(parse_block(block_pair));
-                    }
+                if let Some(block_pair) = part.into_inner().next()
+                    && block_pair.as_rule() == Rule::block
+                {
+                    body = Some(parse_block(block_pair, file, errors));
                 }
             }
             _ => {}
         }
     }
 
-    Function {
+    Ok(Function {
         name,
         params,
         return_type,
         body,
         is_extern,
-    }
+        span,
+    })
 }
 
 /// Parse a function parameter
-fn parse_param(pair: Pair<Rule>) -> Param {
+fn parse_param(pair: Pair<Rule>, file: FileId) -> Result<Param, SyntaxError> {
     match pair.as_rule() {
         Rule::param => {
-            let inner = pair.into_inner().next().expect("param must have inner");
-            parse_param(inner)
+            let inner = pair
+                .clone()
+                .into_inner()
+                .next()
+                .ok_or_else(|| SyntaxError::missing("parameter", "a name", &pair))?;
+            parse_param(inner, file)
         }
-        Rule::param_self => Param::SelfParam,
-        Rule::param_typed => parse_typed_param(pair),
-        _ => panic!("Unexpected parameter {:?}", pair.as_rule()),
+        Rule::param_self => Ok(Param::SelfParam {
+            is_mut: pair.into_inner().any(|part| part.as_rule() == Rule::kw_mut),
+        }),
+        Rule::param_typed => parse_typed_param(pair, file),
+        _ => Err(SyntaxError::unexpected("parameter list", &pair)),
     }
 }
 
 /// Parse a typed parameter
-fn parse_typed_param(pair: Pair<Rule>) -> Param {
-    let mut inner = pair.into_inner();
+fn parse_typed_param(pair: Pair<Rule>, file: FileId) -> Result<Param, SyntaxError> {
+    let mut inner = pair.clone().into_inner();
     let name = inner
         .next()
-        .expect("param needs a name")
+        .ok_or_else(|| SyntaxError::missing("parameter", "a name", &pair))?
         .as_str()
         .to_string();
-    let ty_pair = inner.next().expect("param needs a type");
-    Param::Typed {
+    let ty_pair = inner
+        .next()
+        .ok_or_else(|| SyntaxError::missing("parameter", "a type", &pair))?;
+    Ok(Param::Typed {
         name,
-        ty: parse_type(ty_pair),
-    }
+        ty: parse_type(ty_pair, file)?,
+    })
 }
 
-/// Parse an impl block
-pub fn parse_impl(pair: Pair<Rule>) -> ImplBlock {
-    let mut inner = pair.into_inner();
+/// Parse an impl block. Errors inside method bodies are collected in `errors`.
+pub fn parse_impl(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+) -> Result<ImplBlock, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut inner = pair.clone().into_inner();
     let target = inner
         .find(|p| p.as_rule() == Rule::ident)
-        .expect("impl block needs target")
+        .ok_or_else(|| SyntaxError::missing("impl block", "a target type", &pair))?
         .as_str()
         .to_string();
     let methods = inner
         .filter(|p| p.as_rule() == Rule::function_def)
-        .map(parse_function)
-        .collect();
+        .map(|p| parse_function(p, file, errors))
+        .collect::<Result<_, _>>()?;
 
-    ImplBlock { target, methods }
+    Ok(ImplBlock {
+        target,
+        methods,
+        span,
+    })
 }
 
 #[cfg(test)]
@@ -151,6 +288,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_enum_definition() {
+        let code = r#"
+            enum State: u8 {
+                Idle,
+                Running = 4,
+                Done,
+            }
+            enum Mode { A }
+        "#;
+        let program = build_ast(code).unwrap();
+        let Statement::Enum(def) = &program.statements[0] else {
+            panic!("Expected enum definition");
+        };
+        assert_eq!(def.name, "State");
+        assert_eq!(def.repr, Some(Type::U8));
+        let names: Vec<_> = def.variants.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, ["Idle", "Running", "Done"]);
+        assert!(def.variants[0].value.is_none());
+        assert!(def.variants[1].value.is_some());
+
+        let Statement::Enum(def) = &program.statements[1] else {
+            panic!("Expected enum definition");
+        };
+        assert_eq!(def.repr, None);
+        assert_eq!(def.variants.len(), 1);
+    }
+
+    #[test]
+    fn test_union_definition() {
+        let code = r#"
+            union Packet: u8 {
+                Ping,
+                Data(u8, *u8),
+                Move { x: i32, y: i32 },
+            }
+        "#;
+        let program = build_ast(code).unwrap();
+        let Statement::Union(def) = &program.statements[0] else {
+            panic!("Expected union definition");
+        };
+        assert_eq!(def.name, "Packet");
+        assert_eq!(def.repr, Some(Type::U8));
+        assert_eq!(def.variants[0].payload, VariantPayload::Unit);
+        assert_eq!(
+            def.variants[1].payload,
+            VariantPayload::Tuple(vec![
+                Type::U8,
+                Type::Pointer {
+                    inner: Box::new(Type::U8),
+                    is_mut: false,
+                },
+            ])
+        );
+        let VariantPayload::Struct(fields) = &def.variants[2].payload else {
+            panic!("Expected struct payload");
+        };
+        let names: Vec<_> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["x", "y"]);
+
+        // Payloads cannot be empty
+        assert!(build_ast("union Empty { A() }").is_err());
+    }
+
     #[test]
     fn test_function_definition() {
         let code = r#"
@@ -173,7 +374,7 @@ mod tests {
                 assert!(
                     func.body
                         .as_ref()
-                        .is_some_and(|body| matches!(body.statements[0], Statement::Return(_)))
+                        .is_some_and(|body| matches!(body.statements[0], Statement::Return { .. }))
                 );
             }
             _ => panic!("Expected function definition"),
@@ -216,7 +417,10 @@ mod tests {
 
                 let second = &block.methods[1];
                 assert_eq!(second.name, "translate");
-                assert!(matches!(second.params.first(), Some(Param::SelfParam)));
+                assert!(matches!(
+                    second.params.first(),
+                    Some(Param::SelfParam { is_mut: false })
+                ));
             }
             _ => panic!("Expected impl block"),
         }
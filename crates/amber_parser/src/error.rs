@@ -1,5 +1,6 @@
 use miette::{Diagnostic, NamedSource, SourceOffset, SourceSpan};
 use pest::error::{Error as PestError, InputLocation};
+use pest::iterators::Pair;
 use thiserror::Error;
 
 use crate::Rule;
@@ -12,14 +13,64 @@ pub enum ParseError {
         #[from]
         source: std::io::Error,
     },
-    #[error("parse error in {name}: {source}")]
+    #[error("failed to parse '{}'", .src.name())]
     #[diagnostic(code(amber_parser::parse_error))]
-    Pest {
-        name: String,
-        source: PestError<Rule>,
+    Syntax {
         #[source_code]
         src: NamedSource<String>,
-        #[label("around here")]
+        #[related]
+        errors: Vec<SyntaxError>,
+    },
+}
+
+/// A single problem found while parsing, located in the parsed source
+#[derive(Error, Diagnostic, Debug, Clone, PartialEq)]
+pub enum SyntaxError {
+    #[error("invalid syntax")]
+    #[diagnostic(code(amber_parser::invalid_syntax))]
+    InvalidSyntax {
+        message: String,
+        #[label("{message}")]
+        span: SourceSpan,
+    },
+
+    #[error("unmatched closing brace")]
+    #[diagnostic(code(amber_parser::unmatched_brace))]
+    UnmatchedBrace {
+        #[label("no block to close here")]
+        span: SourceSpan,
+    },
+
+    #[error("unexpected {found} in {context}")]
+    #[diagnostic(code(amber_parser::unexpected_rule))]
+    UnexpectedRule {
+        context: &'static str,
+        found: String,
+        #[label("unexpected {found}")]
+        span: SourceSpan,
+    },
+
+    #[error("{context} is missing {what}")]
+    #[diagnostic(code(amber_parser::missing_part))]
+    MissingPart {
+        context: &'static str,
+        what: &'static str,
+        #[label("in this {context}")]
+        span: SourceSpan,
+    },
+
+    #[error("invalid numeric literal '{literal}'")]
+    #[diagnostic(code(amber_parser::invalid_number))]
+    InvalidNumber {
+        literal: String,
+        #[label("does not fit its type")]
+        span: SourceSpan,
+    },
+
+    #[error("array length must be a non-negative integer")]
+    #[diagnostic(code(amber_parser::invalid_array_length))]
+    InvalidArrayLength {
+        #[label("length given here")]
         span: SourceSpan,
     },
 }
@@ -30,19 +81,61 @@ impl ParseError {
         name: impl AsRef<str>,
         input: impl Into<String>,
     ) -> Self {
-        let span_location = source.location.clone();
-        let name = name.as_ref().to_string();
-        ParseError::Pest {
-            name: name.clone(),
-            source,
-            src: NamedSource::new(name.clone(), input.into()),
-            span: location_to_span(span_location),
+        let input = input.into();
+        let error = SyntaxError::from_pest(&source, &input, 0);
+        ParseError::Syntax {
+            src: NamedSource::new(name, input),
+            errors: vec![error],
+        }
+    }
+}
+
+impl SyntaxError {
+    /// Convert a pest error raised while parsing `input`, which starts at
+    /// byte `offset` of the whole file
+    pub fn from_pest(source: &PestError<Rule>, input: &str, offset: usize) -> Self {
+        let span = location_to_span(source.location.clone(), input);
+        SyntaxError::InvalidSyntax {
+            message: source.variant.message().into_owned(),
+            span: SourceSpan::new((span.offset() + offset).into(), span.len()),
+        }
+    }
+
+    pub(crate) fn unexpected(context: &'static str, pair: &Pair<Rule>) -> Self {
+        SyntaxError::UnexpectedRule {
+            context,
+            found: format!("{:?}", pair.as_rule()),
+            span: pair_span(pair),
+        }
+    }
+
+    pub(crate) fn missing(context: &'static str, what: &'static str, pair: &Pair<Rule>) -> Self {
+        SyntaxError::MissingPart {
+            context,
+            what,
+            span: pair_span(pair),
         }
     }
 }
 
-fn location_to_span(location: InputLocation) -> SourceSpan {
+/// Label span covering a grammar pair
+pub(crate) fn pair_span(pair: &Pair<Rule>) -> SourceSpan {
+    let span = pair.as_span();
+    SourceSpan::new(span.start().into(), span.end() - span.start())
+}
+
+fn location_to_span(location: InputLocation, input: &str) -> SourceSpan {
     match location {
+        // Errors at end of input (e.g. a missing `;`) are reported right after
+        // the last token, where there is a character for the label to point at
+        InputLocation::Pos(pos) if pos >= input.trim_end().len() => {
+            let end = input.trim_end().len();
+            if end < input.len() {
+                SourceSpan::new(end.into(), 0)
+            } else {
+                SourceSpan::new(end.saturating_sub(1).into(), end.min(1))
+            }
+        }
         InputLocation::Pos(pos) => SourceSpan::new((pos).into(), 0),
         InputLocation::Span((start, end)) => {
             let len = end.saturating_sub(start);
@@ -1,49 +1,117 @@
 use pest::iterators::Pair;
 
-use amber_ast::{BinaryOp, Expression, Literal, NumericLiteral, Prefix, UnaryOp};
-use amber_ast::Postfix::Index;
 use crate::Rule;
+use crate::error::{SyntaxError, pair_span};
 use crate::pratt::expr_parser;
+use crate::utils::{parse_char_literal, parse_int_literal, parse_string_literal, span_of};
+use amber_ast::{
+    BinaryOp, Expression, ExpressionKind, FieldInit, FileId, Literal, NumericLiteral, Prefix,
+    UnaryOp,
+};
+use amber_ast::Postfix::Index;
 
 /// Parse a primary expression (literal, identifier, or parenthesized expression)
-fn parse_primary(primary: Pair<Rule>) -> Expression {
-    match primary.as_rule() {
+pub(crate) fn parse_primary(primary: Pair<Rule>, file: FileId) -> Result<Expression, SyntaxError> {
+    let span = span_of(&primary, file);
+    let invalid_number = || SyntaxError::InvalidNumber {
+        literal: primary.as_str().to_string(),
+        span: pair_span(&primary),
+    };
+    let kind = match primary.as_rule() {
         Rule::atom => {
-            let mut inner = primary.into_inner();
-            let inner = inner.next().expect("atom must contain value");
-            parse_primary(inner)
+            let inner = primary
+                .clone()
+                .into_inner()
+                .next()
+                .ok_or_else(|| SyntaxError::missing("expression", "a value", &primary))?;
+            return parse_primary(inner, file);
         }
         Rule::int_lit => {
-            let val: i64 = primary.as_str().parse().unwrap();
-            Expression::Literal(Literal::Numeric(NumericLiteral::Integer(val)))
+            let val = parse_int_literal(primary.as_str()).ok_or_else(invalid_number)?;
+            ExpressionKind::Literal(Literal::Numeric(val))
         }
         Rule::float_lit => {
             let literal = primary.as_str();
-            let cleaned = literal.trim_end_matches('f');
-            let val: f64 = cleaned.parse().unwrap();
+            let cleaned = literal.trim_end_matches(['f', 'd']);
+            let val: f64 = cleaned.parse().map_err(|_| invalid_number())?;
             if literal.ends_with('f') {
-                Expression::Literal(Literal::Numeric(NumericLiteral::Float(val as f32)))
+                ExpressionKind::Literal(Literal::Numeric(NumericLiteral::Float(val as f32)))
             } else {
-                Expression::Literal(Literal::Numeric(NumericLiteral::Double(val)))
+                ExpressionKind::Literal(Literal::Numeric(NumericLiteral::Double(val)))
             }
         }
         Rule::bool_lit => {
             let b = primary.as_str() == "true";
-            Expression::Literal(Literal::Bool(b))
+            ExpressionKind::Literal(Literal::Bool(b))
         }
-        Rule::char_lit => {
-            let c = primary.as_str();
-            Expression::Literal(Literal::Char(c.as_bytes()[1] as char))
+        Rule::char_lit => ExpressionKind::Literal(parse_char_literal(primary.as_str())),
+        Rule::string_lit => ExpressionKind::Literal(parse_string_literal(primary.as_str())),
+        Rule::ident => ExpressionKind::Identifier(primary.as_str().to_string()),
+        Rule::path => {
+            let mut inner = primary.clone().into_inner();
+            let mut segment = |what| {
+                inner
+                    .next()
+                    .map(|pair| pair.as_str().to_string())
+                    .ok_or_else(|| SyntaxError::missing("path", what, &primary))
+            };
+            ExpressionKind::Path {
+                target: segment("a type name")?,
+                name: segment("an item name")?,
+            }
         }
-        Rule::ident => Expression::Identifier(primary.as_str().to_string()),
-        Rule::expr | Rule::ternary_expr | Rule::math_expr | Rule::unary => parse_expr(primary),
-        _ => panic!("Unknown primary: {:?}", primary.as_rule()),
-    }
+        Rule::array_lit => {
+            let elements = primary
+                .clone()
+                .into_inner()
+                .map(|pair| parse_expr(pair, file))
+                .collect::<Result<Vec<_>, _>>()?;
+            ExpressionKind::ArrayLiteral(elements)
+        }
+        Rule::struct_lit => {
+            let mut inner = primary.clone().into_inner();
+            let name = inner
+                .next()
+                .ok_or_else(|| SyntaxError::missing("struct literal", "a struct name", &primary))?;
+            let fields = inner
+                .map(|pair| parse_field_init(pair, file))
+                .collect::<Result<Vec<_>, _>>()?;
+            match parse_primary(name.clone(), file)?.kind {
+                ExpressionKind::Path { target, name } => ExpressionKind::VariantLiteral {
+                    target,
+                    variant: name,
+                    fields,
+                },
+                _ => ExpressionKind::StructLiteral {
+                    name: name.as_str().to_string(),
+                    fields,
+                },
+            }
+        }
+        Rule::expr | Rule::ternary_expr | Rule::math_expr | Rule::unary => {
+            return parse_expr(primary, file);
+        }
+        _ => return Err(SyntaxError::unexpected("expression", &primary)),
+    };
+    Ok(Expression::new(kind, span))
+}
+
+/// Parse one `field: value` pair of a struct literal
+fn parse_field_init(pair: Pair<Rule>, file: FileId) -> Result<FieldInit, SyntaxError> {
+    let missing = |what| SyntaxError::missing("field initializer", what, &pair);
+    let mut inner = pair.clone().into_inner();
+    let name = inner.next().ok_or_else(|| missing("a field name"))?;
+    let value = parse_expr(inner.next().ok_or_else(|| missing("a value"))?, file)?;
+    Ok(FieldInit {
+        name: name.as_str().to_string(),
+        span: span_of(&name, file).to(value.span),
+        value,
+    })
 }
 
 /// Parse binary operator
-fn parse_binary_op(op: Pair<Rule>) -> BinaryOp {
-    match op.as_rule() {
+fn parse_binary_op(op: &Pair<Rule>) -> Result<BinaryOp, SyntaxError> {
+    Ok(match op.as_rule() {
         Rule::add_op => BinaryOp::Add,
         Rule::sub_op => BinaryOp::Sub,
         Rule::mul_op => BinaryOp::Mul,
@@ -62,13 +130,13 @@ fn parse_binary_op(op: Pair<Rule>) -> BinaryOp {
         Rule::shr_op => BinaryOp::Shr,
         Rule::and_op => BinaryOp::And,
         Rule::or_op => BinaryOp::Or,
-        _ => panic!("Unexpected operator: {:?}", op.as_rule()),
-    }
+        _ => return Err(SyntaxError::unexpected("binary expression", op)),
+    })
 }
 
 /// Parse unary operator
-fn parse_unary_op(op: Pair<Rule>) -> UnaryOp {
-    match op.as_rule() {
+fn parse_unary_op(op: &Pair<Rule>) -> Result<UnaryOp, SyntaxError> {
+    Ok(match op.as_rule() {
         Rule::prefix_minus => UnaryOp::PrefixOp(Prefix::Neg),
         Rule::prefix_plus => UnaryOp::PrefixOp(Prefix::Pos),
         Rule::prefix_not => UnaryOp::PrefixOp(Prefix::Not),
@@ -76,17 +144,22 @@ fn parse_unary_op(op: Pair<Rule>) -> UnaryOp {
         Rule::prefix_preinc => UnaryOp::PrefixOp(Prefix::PreInc),
         Rule::prefix_predec => UnaryOp::PrefixOp(Prefix::PreDec),
         Rule::prefix_deref => UnaryOp::PrefixOp(Prefix::Deref),
-        _ => panic!("Unexpected unary operator: {:?}", op.as_rule()),
-    }
+        Rule::prefix_addr => UnaryOp::PrefixOp(Prefix::AddrOf),
+        Rule::prefix_addr_mut => UnaryOp::PrefixOp(Prefix::AddrOfMut),
+        _ => return Err(SyntaxError::unexpected("unary expression", op)),
+    })
 }
 
 /// Parse an expression using Pratt parser with ternary operator support
-pub fn parse_expr(pair: Pair<Rule>) -> Expression {
+pub fn parse_expr(pair: Pair<Rule>, file: FileId) -> Result<Expression, SyntaxError> {
     match pair.as_rule() {
         // Handle ternary expression: math_expr ? expr : expr
         Rule::ternary_expr => {
-            let mut inner = pair.into_inner();
-            let condition = parse_math_expr(inner.next().expect("ternary: missing condition"));
+            let span = span_of(&pair, file);
+            let missing = |what| SyntaxError::missing("conditional expression", what, &pair);
+            let mut inner = pair.clone().into_inner();
+            let condition =
+                parse_math_expr(inner.next().ok_or_else(|| missing("a condition"))?, file)?;
 
             // Check if there's a question mark
             if let Some(question) = inner.next() {
@@ -94,75 +167,150 @@ pub fn parse_expr(pair: Pair<Rule>) -> Expression {
                     Rule::question => {
                         // This is a ternary expression
                         let then_expr =
-                            parse_expr(inner.next().expect("ternary: missing then expression"));
-                        let _colon = inner.next().expect("ternary: missing colon");
+                            parse_expr(inner.next().ok_or_else(|| missing("a then branch"))?, file)?;
                         let else_expr =
-                            parse_expr(inner.next().expect("ternary: missing else expression"));
+                            parse_expr(inner.next().ok_or_else(|| missing("an else branch"))?, file)?;
 
-                        Expression::TernaryExpr {
-                            condition: Box::new(condition),
-                            then_expr: Box::new(then_expr),
-                            else_expr: Box::new(else_expr),
-                        }
+                        Ok(Expression::new(
+                            ExpressionKind::TernaryExpr {
+                                condition: Box::new(condition),
+                                then_expr: Box::new(then_expr),
+                                else_expr: Box::new(else_expr),
+                            },
+                            span,
+                        ))
                     }
                     _ => {
                         // No ternary operator, just return the condition
-                        condition
+                        Ok(condition)
                     }
                 }
             } else {
                 // No ternary, just return the condition
-                condition
+                Ok(condition)
             }
         }
         // Handle math expression (with PrattParser)
-        Rule::math_expr => parse_math_expr(pair),
+        Rule::math_expr => parse_math_expr(pair, file),
         // For other rules, parse as math_expr
-        _ => parse_math_expr(pair),
+        _ => parse_math_expr(pair, file),
     }
 }
 
 /// Parse a math expression using global Pratt parser (no ternary at this level)
-fn parse_math_expr(pair: Pair<Rule>) -> Expression {
+fn parse_math_expr(pair: Pair<Rule>, file: FileId) -> Result<Expression, SyntaxError> {
+    let span = span_of(&pair, file);
     let pairs: Vec<_> = pair.into_inner().collect();
 
     // If there are no pairs to parse with Pratt parser, return a simple expression
     if pairs.is_empty() {
-        return Expression::Literal(Literal::Numeric(NumericLiteral::Integer(0))); // fallback
+        let fallback = ExpressionKind::Literal(Literal::Numeric(NumericLiteral::Integer(0)));
+        return Ok(Expression::new(fallback, span));
     }
 
     expr_parser()
-        .map_primary(|primary| parse_primary(primary))
+        .map_primary(|primary| parse_primary(primary, file))
         .map_prefix(|op, rhs| {
-            let unary_op = parse_unary_op(op);
-            Expression::UnaryExpr {
-                op: unary_op,
-                expr: Box::new(rhs),
-            }
+            let rhs = rhs?;
+            let span = span_of(&op, file).to(rhs.span);
+            let unary_op = parse_unary_op(&op)?;
+            Ok(Expression::new(
+                ExpressionKind::UnaryExpr {
+                    op: unary_op,
+                    expr: Box::new(rhs),
+                },
+                span,
+            ))
         })
         .map_infix(|lhs, op, rhs| {
-            let binary_op = parse_binary_op(op);
-            Expression::BinaryExpr {
-                left: Box::new(lhs),
-                op: binary_op,
-                right: Box::new(rhs),
-            }
+            let (lhs, rhs) = (lhs?, rhs?);
+            let span = lhs.span.to(rhs.span);
+            let binary_op = parse_binary_op(&op)?;
+            Ok(Expression::new(
+                ExpressionKind::BinaryExpr {
+                    left: Box::new(lhs),
+                    op: binary_op,
+                    right: Box::new(rhs),
+                },
+                span,
+            ))
         })
         .map_postfix(|lhs, op| {
+            let lhs = lhs?;
+            let span = lhs.span.to(span_of(&op, file));
             match op.as_rule() {
                 Rule::postfix_index => {
-                    let inner_expr_pair = op.into_inner().next().unwrap();
-                    let inner_expr = parse_expr(inner_expr_pair);
-                    Expression::UnaryExpr { op: UnaryOp::PostfixOp(Index { index: Box::new(inner_expr) }), expr: Box::new(lhs) }
+                    let inner_expr_pair = op
+                        .clone()
+                        .into_inner()
+                        .next()
+                        .ok_or_else(|| SyntaxError::missing("index expression", "an index", &op))?;
+                    let inner_expr = parse_expr(inner_expr_pair, file)?;
+                    Ok(Expression::new(
+                        ExpressionKind::UnaryExpr {
+                            op: UnaryOp::PostfixOp(Index { index: Box::new(inner_expr) }),
+                            expr: Box::new(lhs),
+                        },
+                        span,
+                    ))
                 }
-                _ => unreachable!("Unexpected postfix operator: {:?}", op.as_rule())
+                Rule::postfix_call => {
+                    let args = op
+                        .into_inner()
+                        .map(|arg| parse_expr(arg, file))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(Expression::new(
+                        ExpressionKind::Call {
+                            callee: Box::new(lhs),
+                            args,
+                        },
+                        span,
+                    ))
+                }
+                Rule::postfix_method => {
+                    let mut inner = op.clone().into_inner();
+                    let method = inner
+                        .next()
+                        .ok_or_else(|| SyntaxError::missing("method call", "a method name", &op))?
+                        .as_str()
+                        .to_string();
+                    let args = inner
+                        .map(|arg| parse_expr(arg, file))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Ok(Expression::new(
+                        ExpressionKind::MethodCall {
+                            receiver: Box::new(lhs),
+                            method,
+                            args,
+                        },
+                        span,
+                    ))
+                }
+                Rule::postfix_field => {
+                    let field = op
+                        .clone()
+                        .into_inner()
+                        .next()
+                        .ok_or_else(|| SyntaxError::missing("field access", "a field name", &op))?
+                        .as_str()
+                        .to_string();
+                    Ok(Expression::new(
+                        ExpressionKind::FieldAccess {
+                            base: Box::new(lhs),
+                            field,
+                        },
+                        span,
+                    ))
+                }
+                _ => Err(SyntaxError::unexpected("postfix expression", &op)),
             }
         })
         .parse(pairs.into_iter())
 }
 
 /// Parse a unary expression (prefix operators followed by an operand)
-fn parse_unary_expr(pair: Pair<Rule>) -> Expression {
+#[allow(dead_code)]
+fn parse_unary_expr(pair: Pair<Rule>, file: FileId) -> Result<Expression, SyntaxError> {
     let inner: Vec<_> = pair.into_inner().collect();
 
     // Check if there are prefix operators followed by an atom
@@ -187,55 +335,60 @@ fn parse_unary_expr(pair: Pair<Rule>) -> Expression {
         panic!("Expected exactly one operand for unary expression, got {}", remaining.len());
     }
 
-    let mut base_expr = parse_primary(remaining[0].clone());
+    let mut base_expr = parse_primary(remaining[0].clone(), file)?;
 
     // Apply prefix operators from right to left
     for op_pair in prefix_ops.iter().rev() {
-        let unary_op = parse_unary_op(op_pair.clone());
-        base_expr = Expression::UnaryExpr {
-            op: unary_op,
-            expr: Box::new(base_expr),
-        };
+        let unary_op = parse_unary_op(op_pair)?;
+        let span = span_of(op_pair, file).to(base_expr.span);
+        base_expr = Expression::new(
+            ExpressionKind::UnaryExpr {
+                op: unary_op,
+                expr: Box::new(base_expr),
+            },
+            span,
+        );
     }
 
-    base_expr
+    Ok(base_expr)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::build_ast;
+    use amber_ast::IntSuffix;
 
     #[test]
     fn test_expression_precedence() {
         let code = "const a = 1 + 2 * 3;";
         let program = build_ast(code).unwrap();
 
-        if let amber_ast::Statement::Binding(binding) = &program.statements[0] {
-            if let Some(expr) = &binding.value {
-                if let Expression::BinaryExpr { left, op, right } = expr {
-                    assert_eq!(*op, BinaryOp::Add);
-
-                    if let Expression::Literal(Literal::Numeric(num)) = &**left {
-                        assert!(num.is_integer());
-                        assert_eq!(num.to_i64(), 1);
-                    } else {
-                        panic!("Left should be 1");
-                    }
+        if let amber_ast::Statement::Binding(binding) = &program.statements[0]
+            && let Some(expr) = &binding.value
+        {
+            if let ExpressionKind::BinaryExpr { left, op, right } = &expr.kind {
+                assert_eq!(*op, BinaryOp::Add);
 
-                    if let Expression::BinaryExpr {
-                        left: _r_left,
-                        op: r_op,
-                        right: _r_right,
-                    } = &**right
-                    {
-                        assert_eq!(*r_op, BinaryOp::Mul);
-                    } else {
-                        panic!("Right side should be multiplication");
-                    }
+                if let ExpressionKind::Literal(Literal::Numeric(num)) = &left.kind {
+                    assert!(num.is_integer());
+                    assert_eq!(num.to_i64(), 1);
                 } else {
-                    panic!("Top level should be addition");
+                    panic!("Left should be 1");
                 }
+
+                if let ExpressionKind::BinaryExpr {
+                    left: _r_left,
+                    op: r_op,
+                    right: _r_right,
+                } = &right.kind
+                {
+                    assert_eq!(*r_op, BinaryOp::Mul);
+                } else {
+                    panic!("Right side should be multiplication");
+                }
+            } else {
+                panic!("Top level should be addition");
             }
         }
     }
@@ -245,17 +398,165 @@ mod tests {
         let code = "const a = (1 + 2) * 3;";
         let program = build_ast(code).unwrap();
 
-        if let amber_ast::Statement::Binding(binding) = &program.statements[0] {
-            if let Some(expr) = &binding.value {
-                if let Expression::BinaryExpr { op, .. } = expr {
-                    assert_eq!(*op, BinaryOp::Mul);
-                } else {
-                    panic!("Top level should be multiplication");
-                }
+        if let amber_ast::Statement::Binding(binding) = &program.statements[0]
+            && let Some(expr) = &binding.value
+        {
+            if let ExpressionKind::BinaryExpr { op, .. } = &expr.kind {
+                assert_eq!(*op, BinaryOp::Mul);
+            } else {
+                panic!("Top level should be multiplication");
             }
         }
     }
 
+    #[test]
+    fn test_function_call() {
+        let code = "fn main() { HAL_Delay(100); }";
+        let program = build_ast(code).unwrap();
+
+        let amber_ast::Statement::Function(func) = &program.statements[0] else {
+            panic!("Expected function");
+        };
+        let body = func.body.as_ref().unwrap();
+        let amber_ast::Statement::ExprStatement(expr) = &body.statements[0] else {
+            panic!("Expected expression statement");
+        };
+        let ExpressionKind::Call { callee, args } = &expr.kind else {
+            panic!("Expected call, got {:?}", expr.kind);
+        };
+        assert_eq!(callee.kind, ExpressionKind::Identifier("HAL_Delay".to_string()));
+        assert_eq!(args.len(), 1);
+        assert_eq!(&code[expr.span.offset..expr.span.end()], "HAL_Delay(100)");
+    }
+
+    #[test]
+    fn test_call_binds_tighter_than_binary_ops() {
+        let code = "const a = 1 + add(2, 3 * 4) * f();";
+        let program = build_ast(code).unwrap();
+
+        let amber_ast::Statement::Binding(binding) = &program.statements[0] else {
+            panic!("Expected binding");
+        };
+        let ExpressionKind::BinaryExpr { op, right, .. } = &binding.value.as_ref().unwrap().kind
+        else {
+            panic!("Top level should be addition");
+        };
+        assert_eq!(*op, BinaryOp::Add);
+        let ExpressionKind::BinaryExpr { left, right, .. } = &right.kind else {
+            panic!("Right side should be multiplication");
+        };
+        assert!(matches!(&left.kind, ExpressionKind::Call { args, .. } if args.len() == 2));
+        assert!(matches!(&right.kind, ExpressionKind::Call { args, .. } if args.is_empty()));
+    }
+
+    #[test]
+    fn test_method_and_associated_calls() {
+        let code = "const a = p.sum(1, 2);\nconst b = Point::new(3, 4);";
+        let program = build_ast(code).unwrap();
+
+        let amber_ast::Statement::Binding(binding) = &program.statements[0] else {
+            panic!("Expected binding");
+        };
+        let value = binding.value.as_ref().unwrap();
+        let ExpressionKind::MethodCall {
+            receiver,
+            method,
+            args,
+        } = &value.kind
+        else {
+            panic!("Expected method call, got {:?}", value.kind);
+        };
+        assert_eq!(receiver.kind, ExpressionKind::Identifier("p".to_string()));
+        assert_eq!(method, "sum");
+        assert_eq!(args.len(), 2);
+        assert_eq!(&code[value.span.offset..value.span.end()], "p.sum(1, 2)");
+
+        let amber_ast::Statement::Binding(binding) = &program.statements[1] else {
+            panic!("Expected binding");
+        };
+        let ExpressionKind::Call { callee, args } = &binding.value.as_ref().unwrap().kind else {
+            panic!("Expected call");
+        };
+        assert_eq!(
+            callee.kind,
+            ExpressionKind::Path {
+                target: "Point".to_string(),
+                name: "new".to_string(),
+            }
+        );
+        assert_eq!(args.len(), 2);
+    }
+
+    #[test]
+    fn test_field_access_and_struct_literal() {
+        let code = "fn main() {\n    var p = Point { x: 1, y: 2 };\n    p.x = p.y;\n    if p.x > 0 {}\n}";
+        let program = build_ast(code).unwrap();
+
+        let amber_ast::Statement::Function(func) = &program.statements[0] else {
+            panic!("Expected function");
+        };
+        let body = func.body.as_ref().unwrap();
+        let amber_ast::Statement::Binding(binding) = &body.statements[0] else {
+            panic!("Expected binding");
+        };
+        let ExpressionKind::StructLiteral { name, fields } = &binding.value.as_ref().unwrap().kind
+        else {
+            panic!("Expected struct literal");
+        };
+        assert_eq!(name, "Point");
+        let names: Vec<_> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["x", "y"]);
+        assert_eq!(&code[fields[1].span.offset..fields[1].span.end()], "y: 2");
+
+        let amber_ast::Statement::Assignment { target, .. } = &body.statements[1] else {
+            panic!("Expected assignment");
+        };
+        let ExpressionKind::FieldAccess { base, field } = &target.kind else {
+            panic!("Expected field access");
+        };
+        assert_eq!(base.kind, ExpressionKind::Identifier("p".to_string()));
+        assert_eq!(field, "x");
+
+        // A condition followed by an empty block is not a struct literal
+        assert!(matches!(body.statements[2], amber_ast::Statement::IfElse(_)));
+    }
+
+    #[test]
+    fn test_variant_literal() {
+        let code = "const p = Packet::Move { x: 1, y: 2 };";
+        let program = build_ast(code).unwrap();
+        let amber_ast::Statement::Binding(binding) = &program.statements[0] else {
+            panic!("Expected binding");
+        };
+        let ExpressionKind::VariantLiteral {
+            target,
+            variant,
+            fields,
+        } = &binding.value.as_ref().unwrap().kind
+        else {
+            panic!("Expected variant literal");
+        };
+        assert_eq!((target.as_str(), variant.as_str()), ("Packet", "Move"));
+        assert_eq!(fields.len(), 2);
+    }
+
+    #[test]
+    fn test_array_literal() {
+        let code = "const a = [1, 2, 3][0];";
+        let program = build_ast(code).unwrap();
+
+        let amber_ast::Statement::Binding(binding) = &program.statements[0] else {
+            panic!("Expected binding");
+        };
+        let ExpressionKind::UnaryExpr { expr, .. } = &binding.value.as_ref().unwrap().kind else {
+            panic!("Expected index expression");
+        };
+        let ExpressionKind::ArrayLiteral(elements) = &expr.kind else {
+            panic!("Expected array literal, got {:?}", expr.kind);
+        };
+        assert_eq!(elements.len(), 3);
+    }
+
     #[test]
     fn test_bool_literal() {
         let code = "const flag: bool = true;";
@@ -263,7 +564,7 @@ mod tests {
 
         if let amber_ast::Statement::Binding(binding) = &program.statements[0] {
             assert_eq!(binding.name, "flag");
-            if let Some(Expression::Literal(Literal::Bool(b))) = &binding.value {
+            if let Some(ExpressionKind::Literal(Literal::Bool(b))) = binding.value.as_ref().map(|e| &e.kind) {
                 assert!(*b);
             } else {
                 panic!("Expected bool literal");
@@ -279,7 +580,7 @@ mod tests {
         let program = build_ast(code).unwrap();
 
         if let amber_ast::Statement::Binding(binding) = &program.statements[0] {
-            if let Some(Expression::Literal(Literal::Bool(b))) = &binding.value {
+            if let Some(ExpressionKind::Literal(Literal::Bool(b))) = binding.value.as_ref().map(|e| &e.kind) {
                 assert!(!*b);
             } else {
                 panic!("Expected bool literal false");
@@ -295,7 +596,7 @@ mod tests {
         let program = build_ast(code).unwrap();
 
         if let amber_ast::Statement::Binding(binding) = &program.statements[0] {
-            if let Some(Expression::Literal(Literal::Char(c))) = &binding.value {
+            if let Some(ExpressionKind::Literal(Literal::Char(c))) = binding.value.as_ref().map(|e| &e.kind) {
                 assert_eq!(*c, 'a');
             } else {
                 panic!("Expected char literal 'c'")
@@ -304,4 +605,134 @@ mod tests {
             panic!("Expected Binding");
         }
     }
+
+    #[test]
+    fn test_integer_literal_forms() {
+        let code = "const a = [0xFF, 0b1010_0101, 0o17, 1_000_000, 10u8, 0xFFFF_0000u32];";
+        let program = build_ast(code).unwrap();
+
+        let amber_ast::Statement::Binding(binding) = &program.statements[0] else {
+            panic!("Expected binding");
+        };
+        let ExpressionKind::ArrayLiteral(elements) = &binding.value.as_ref().unwrap().kind else {
+            panic!("Expected array literal");
+        };
+        let literals: Vec<_> = elements
+            .iter()
+            .map(|element| match &element.kind {
+                ExpressionKind::Literal(Literal::Numeric(num)) => *num,
+                other => panic!("Expected numeric literal, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            literals,
+            vec![
+                NumericLiteral::Integer(255),
+                NumericLiteral::Integer(165),
+                NumericLiteral::Integer(15),
+                NumericLiteral::Integer(1_000_000),
+                NumericLiteral::Suffixed(10, IntSuffix::U8),
+                NumericLiteral::Suffixed(0xFFFF_0000, IntSuffix::U32),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_integer_literals() {
+        for code in [
+            "const a = 0x;",
+            "const a = 0b102;",
+            "const a = 10u7;",
+            "const a = 18446744073709551616;",
+        ] {
+            assert!(build_ast(code).is_err(), "{} should not parse", code);
+        }
+    }
+
+    #[test]
+    fn test_escaped_char_literals() {
+        let code = r"const a = ['\n', '\0', '\x7F', '\'', '\\', '\t', b'A', b'\xFF'];";
+        let program = build_ast(code).unwrap();
+
+        let amber_ast::Statement::Binding(binding) = &program.statements[0] else {
+            panic!("Expected binding");
+        };
+        let ExpressionKind::ArrayLiteral(elements) = &binding.value.as_ref().unwrap().kind else {
+            panic!("Expected array literal");
+        };
+        let literals: Vec<_> = elements
+            .iter()
+            .map(|element| match &element.kind {
+                ExpressionKind::Literal(lit) => lit.clone(),
+                other => panic!("Expected literal, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            literals,
+            vec![
+                Literal::Char('\n'),
+                Literal::Char('\0'),
+                Literal::Char('\x7F'),
+                Literal::Char('\''),
+                Literal::Char('\\'),
+                Literal::Char('\t'),
+                Literal::Byte(b'A'),
+                Literal::Byte(0xFF),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_char_literals() {
+        for code in [
+            "const a = '';",
+            "const a = 'ab';",
+            r"const a = '\q';",
+            r"const a = '\x7';",
+            "const a = ''';",
+        ] {
+            assert!(build_ast(code).is_err(), "{} should not parse", code);
+        }
+    }
+
+    #[test]
+    fn test_string_literals() {
+        let code = r#"const a = ["say \"hi\"\n", c"%d\x7F", "", "héllo"];"#;
+        let program = build_ast(code).unwrap();
+
+        let amber_ast::Statement::Binding(binding) = &program.statements[0] else {
+            panic!("Expected binding");
+        };
+        let ExpressionKind::ArrayLiteral(elements) = &binding.value.as_ref().unwrap().kind else {
+            panic!("Expected array literal");
+        };
+        let literals: Vec<_> = elements
+            .iter()
+            .map(|element| match &element.kind {
+                ExpressionKind::Literal(lit) => lit.clone(),
+                other => panic!("Expected literal, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            literals,
+            vec![
+                Literal::String("say \"hi\"\n".to_string()),
+                Literal::CString("%d\x7F".to_string()),
+                Literal::String(String::new()),
+                Literal::String("héllo".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_string_literals() {
+        for code in [
+            r#"const a = "open;"#,
+            "const a = \"two\nlines\";",
+            r#"const a = "\q";"#,
+            r#"const a = "\xFF";"#,
+        ] {
+            assert!(build_ast(code).is_err(), "{} should not parse", code);
+        }
+    }
 }
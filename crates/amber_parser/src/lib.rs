@@ -1,22 +1,25 @@
+pub mod decl_parser;
+pub mod error;
 pub mod pratt;
 pub mod utils;
 pub mod expr_parser;
 pub mod stmt_parser;
-pub mod decl_parser;
-pub mod error;
 
+use miette::NamedSource;
 use pest::Parser;
 use pest_derive::Parser;
 
-use amber_ast::Program;
+use amber_ast::{FileId, Program};
 
-pub use error::ParseError;
+pub use error::{ParseError, SyntaxError};
 
 #[derive(Parser)]
 #[grammar = "grammar.pest"]
 pub struct AmberParser;
 
 pub fn parse_source(input: &str) -> Result<(), String> {
+    // The grammar skips invalid statements, so check the source as a whole first
+    build_ast(input).map_err(|e| format!("{:?}", e))?;
     let pairs = AmberParser::parse(Rule::program, input).map_err(|e| format!("{}", e))?;
     for pair in pairs {
         println!("Rule: {:?}", pair.as_rule());
@@ -30,41 +33,82 @@ pub fn parse_source(input: &str) -> Result<(), String> {
     Ok(())
 }
 
-pub fn build_ast(input: &str) -> Result<Program, String> {
-    let mut pairs = AmberParser::parse(Rule::program, input).map_err(|e| format!("{}", e))?;
+pub fn build_ast(input: &str) -> Result<Program, ParseError> {
+    build_ast_with_name(input, "<input>".to_string())
+}
+
+/// Build the AST for `input`, reporting syntax errors against the file `name`
+pub fn build_ast_with_name(input: &str, name: String) -> Result<Program, ParseError> {
+    build_ast_in_file(input, &name, FileId::default())
+}
+
+/// Build the AST for `input`, tagging every span with `file`. Parsing
+/// recovers at statement boundaries, so all syntax errors are reported.
+pub fn build_ast_in_file(input: &str, name: &str, file: FileId) -> Result<Program, ParseError> {
+    let mut pairs = AmberParser::parse(Rule::program, input)
+        .map_err(|err| ParseError::from_pest(err, name, input))?;
 
     let root = pairs.next().unwrap();
 
     let mut statements = Vec::new();
+    let mut errors = Vec::new();
 
     for pair in root.into_inner() {
-        if pair.as_rule() == Rule::statement {
-            statements.push(parse_statement(pair));
+        match pair.as_rule() {
+            Rule::statement => match parse_statement(pair, file, &mut errors) {
+                Ok(statement) => statements.push(statement),
+                Err(err) => errors.push(err),
+            },
+            Rule::invalid_stmt => errors.push(stmt_parser::diagnose_invalid_statement(&pair)),
+            // A stray `}` usually follows a statement that was already skipped,
+            // so it is only worth reporting on its own
+            Rule::stray_rbrace if errors.is_empty() => errors.push(SyntaxError::UnmatchedBrace {
+                span: error::pair_span(&pair),
+            }),
+            _ => {}
         }
     }
-    Ok(Program { statements })
-}
 
-pub fn build_ast_with_name(input: &str, _name: String) -> Result<Program, String> {
-    // For now, we ignore the name parameter
-    // In the future, this could be used for better error reporting with file names
-    build_ast(input)
+    if errors.is_empty() {
+        Ok(Program { statements })
+    } else {
+        Err(ParseError::Syntax {
+            src: NamedSource::new(name, input.to_string()),
+            errors,
+        })
+    }
 }
 
-fn parse_statement(pair: pest::iterators::Pair<Rule>) -> amber_ast::Statement {
-    let inner = pair.into_inner().next().unwrap();
+fn parse_statement(
+    pair: pest::iterators::Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+) -> Result<amber_ast::Statement, SyntaxError> {
+    let inner = pair
+        .clone()
+        .into_inner()
+        .next()
+        .ok_or_else(|| SyntaxError::missing("program", "a statement", &pair))?;
 
     match inner.as_rule() {
-        Rule::declaration => stmt_parser::parse_declaration(inner),
-        Rule::expr_stmt => stmt_parser::parse_expr_stmt(inner),
-        Rule::assignment => stmt_parser::parse_assignment(inner),
-        Rule::return_stmt => stmt_parser::parse_return(inner),
-        Rule::if_stmt => stmt_parser::parse_if_stmt(inner),
-        Rule::while_stmt => stmt_parser::parse_while_stmt(inner),
-        Rule::struct_def => amber_ast::Statement::Struct(decl_parser::parse_struct(inner)),
-        Rule::function_def => amber_ast::Statement::Function(decl_parser::parse_function(inner)),
-        Rule::impl_block => amber_ast::Statement::Impl(decl_parser::parse_impl(inner)),
-        _ => panic!("TODO: Implement other statements: {:?}", inner.as_rule()),
+        Rule::declaration => stmt_parser::parse_declaration(inner, file),
+        Rule::expr_stmt => stmt_parser::parse_expr_stmt(inner, file),
+        Rule::assignment => stmt_parser::parse_assignment(inner, file),
+        Rule::return_stmt => stmt_parser::parse_return(inner, file),
+        Rule::break_stmt | Rule::continue_stmt => Ok(stmt_parser::parse_jump(inner, file)),
+        Rule::if_stmt => stmt_parser::parse_top_level_if(inner, file, errors, parse_statement),
+        Rule::while_stmt => stmt_parser::parse_while_stmt(inner, file, errors),
+        Rule::for_stmt => stmt_parser::parse_for_stmt(inner, file, errors),
+        Rule::match_stmt => stmt_parser::parse_match_stmt(inner, file, errors),
+        Rule::struct_def => decl_parser::parse_struct(inner, file).map(amber_ast::Statement::Struct),
+        Rule::enum_def => decl_parser::parse_enum(inner, file).map(amber_ast::Statement::Enum),
+        Rule::union_def => decl_parser::parse_union(inner, file).map(amber_ast::Statement::Union),
+        Rule::function_def => decl_parser::parse_function(inner, file, errors)
+            .map(amber_ast::Statement::Function),
+        Rule::impl_block => {
+            decl_parser::parse_impl(inner, file, errors).map(amber_ast::Statement::Impl)
+        }
+        _ => Err(SyntaxError::unexpected("program", &inner)),
     }
 }
 
@@ -92,4 +136,110 @@ mod tests {
         let result = parse_source(code);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_build_ast_reports_named_error() {
+        let code = "const a = 10\nconst b = 2;";
+        let err = build_ast_with_name(code, "main.amb".to_string()).unwrap_err();
+        let ParseError::Syntax { src, errors } = &err else {
+            panic!("Expected a syntax error, got {:?}", err);
+        };
+        assert_eq!(src.name(), "main.amb");
+        assert_eq!(err.to_string(), "failed to parse 'main.amb'");
+
+        let [SyntaxError::InvalidSyntax { message, span }] = errors.as_slice() else {
+            panic!("Expected one invalid syntax error, got {:?}", errors);
+        };
+        assert!(message.starts_with("expected"));
+        // The error is reported where the missing `;` should have been
+        assert_eq!(span.offset(), code.find("\nconst b").unwrap() + 1);
+    }
+
+    #[test]
+    fn test_recovers_to_report_every_error() {
+        let code = r#"
+            const a: i32 = ;
+            fn main() {
+                var b: i32 = 1 +;
+                b = 2;
+                const c = (1;
+            }
+            const d: [4]u8;
+        "#;
+        let err = build_ast(code).unwrap_err();
+        let ParseError::Syntax { errors, .. } = &err else {
+            panic!("Expected a syntax error, got {:?}", err);
+        };
+        let lines: Vec<usize> = errors
+            .iter()
+            .map(|err| match err {
+                SyntaxError::InvalidSyntax { span, .. } => {
+                    code[..span.offset()].matches('\n').count()
+                }
+                other => panic!("Unexpected error {:?}", other),
+            })
+            .collect();
+        assert_eq!(lines, vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn test_semantic_parse_errors_do_not_panic() {
+        let code = r#"
+            const big = 99999999999999999999;
+            var buf: [1.5]u8;
+            var ok: [4]u8;
+            var sized: [N * 2]u8;
+        "#;
+        let err = build_ast(code).unwrap_err();
+        let ParseError::Syntax { errors, .. } = &err else {
+            panic!("Expected a syntax error, got {:?}", err);
+        };
+        assert!(matches!(
+            errors.as_slice(),
+            [
+                SyntaxError::InvalidNumber { .. },
+                SyntaxError::InvalidArrayLength { .. },
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_unmatched_brace() {
+        let err = build_ast("fn main() {}\n}").unwrap_err();
+        let ParseError::Syntax { errors, .. } = &err else {
+            panic!("Expected a syntax error, got {:?}", err);
+        };
+        assert!(matches!(
+            errors.as_slice(),
+            [SyntaxError::UnmatchedBrace { .. }]
+        ));
+    }
+
+    #[test]
+    fn test_spans_cover_source() {
+        use amber_ast::{ExpressionKind, Span, Statement};
+
+        let code = "fn main() {\n    const a: i32 = 1 + b[2];\n}";
+        let file = FileId(3);
+        let program = build_ast_in_file(code, "spans.amb", file).unwrap();
+        let Statement::Function(func) = &program.statements[0] else {
+            panic!("Expected function");
+        };
+        assert_eq!(func.span, Span::new(file, 0, code.len()));
+
+        let Statement::Binding(binding) = &func.body.as_ref().unwrap().statements[0] else {
+            panic!("Expected binding");
+        };
+        let text = |span: Span| &code[span.offset..span.end()];
+        assert_eq!(text(binding.span), "const a: i32 = 1 + b[2];");
+
+        let value = binding.value.as_ref().unwrap();
+        assert_eq!(text(value.span), "1 + b[2]");
+        let ExpressionKind::BinaryExpr { left, right, .. } = &value.kind else {
+            panic!("Expected binary expression");
+        };
+        assert_eq!(text(left.span), "1");
+        assert_eq!(text(right.span), "b[2]");
+        assert_eq!(right.span.file, file);
+    }
 }
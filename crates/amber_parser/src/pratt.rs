@@ -42,9 +42,14 @@ lazy_static! {
                 | Op::prefix(Rule::prefix_bitnot)
                 | Op::prefix(Rule::prefix_preinc)
                 | Op::prefix(Rule::prefix_predec)
-                | Op::prefix(Rule::prefix_deref))
+                | Op::prefix(Rule::prefix_deref)
+                | Op::prefix(Rule::prefix_addr_mut)
+                | Op::prefix(Rule::prefix_addr))
             // Unary postfix operators (highest precedence)
-            .op(Op::postfix(Rule::postfix_index))
+            .op(Op::postfix(Rule::postfix_index)
+                | Op::postfix(Rule::postfix_call)
+                | Op::postfix(Rule::postfix_method)
+                | Op::postfix(Rule::postfix_field))
     };
 }
 
@@ -1,12 +1,20 @@
+use pest::Parser;
 use pest::iterators::Pair;
 
-use amber_ast::{Block, IfElse, Modifier, Statement, VariableBinding, WhileLoop};
+use amber_ast::{
+    Block, ElseBranch, ExpressionKind, FieldBinding, FileId, ForIterable, ForLoop, IfElse, Literal,
+    Match, MatchArm, Modifier, Pattern, PatternKind, PayloadPattern, Statement, VariableBinding,
+    WhileLoop,
+};
 
-use crate::Rule;
-use crate::expr_parser::parse_expr;
+use crate::error::{SyntaxError, pair_span};
+use crate::expr_parser::{parse_expr, parse_primary};
+use crate::utils::{parse_int_literal, span_of};
+use crate::{AmberParser, Rule};
 
 /// Parse a declaration (const/var binding)
-pub fn parse_declaration(pair: Pair<Rule>) -> Statement {
+pub fn parse_declaration(pair: Pair<Rule>, file: FileId) -> Result<Statement, SyntaxError> {
+    let span = span_of(&pair, file);
     let inner = pair.into_inner();
 
     let mut modifier = None;
@@ -31,115 +39,430 @@ pub fn parse_declaration(pair: Pair<Rule>) -> Statement {
                 name = part.as_str().to_string();
             }
             Rule::type_def => {
-                ty = Some(crate::utils::parse_type(part));
+                ty = Some(crate::utils::parse_type(part, file)?);
             }
             Rule::expr => {
-                value = Some(parse_expr(part));
+                value = Some(parse_expr(part, file)?);
             }
             _ => {}
         }
     }
 
-    Statement::Binding(VariableBinding {
+    Ok(Statement::Binding(VariableBinding {
         modifier,
         is_mutable,
         name,
         ty,
         value,
-    })
+        span,
+    }))
 }
 
 /// Parse an assignment statement
-pub fn parse_assignment(pair: Pair<Rule>) -> Statement {
-    let mut inner = pair.into_inner();
+pub fn parse_assignment(pair: Pair<Rule>, file: FileId) -> Result<Statement, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut inner = pair.clone().into_inner();
     let target_pair = inner
         .next()
-        .expect("assignment must have a target");
-    let target = parse_expr(target_pair);
-    let expr_pair = inner.next().expect("assignment must have value");
-    Statement::Assignment {
+        .ok_or_else(|| SyntaxError::missing("assignment", "a target", &pair))?;
+    let target = parse_expr(target_pair, file)?;
+    let expr_pair = inner
+        .next()
+        .ok_or_else(|| SyntaxError::missing("assignment", "a value", &pair))?;
+    Ok(Statement::Assignment {
         target,
-        value: parse_expr(expr_pair),
-    }
+        value: parse_expr(expr_pair, file)?,
+        span,
+    })
 }
 
 /// Parse a return statement
-pub fn parse_return(pair: Pair<Rule>) -> Statement {
-    let mut inner = pair.into_inner();
-    let expr = inner.next().map(parse_expr);
-    Statement::Return(expr)
+pub fn parse_return(pair: Pair<Rule>, file: FileId) -> Result<Statement, SyntaxError> {
+    let span = span_of(&pair, file);
+    let value = pair
+        .into_inner()
+        .find(|p| p.as_rule() == Rule::expr)
+        .map(|p| parse_expr(p, file))
+        .transpose()?;
+    Ok(Statement::Return { value, span })
+}
+
+/// Parse a `break` or `continue` statement with its optional label
+pub fn parse_jump(pair: Pair<Rule>, file: FileId) -> Statement {
+    let span = span_of(&pair, file);
+    let label = pair
+        .clone()
+        .into_inner()
+        .find(|p| p.as_rule() == Rule::ident)
+        .map(|p| p.as_str().to_string());
+    if pair.as_rule() == Rule::break_stmt {
+        Statement::Break { label, span }
+    } else {
+        Statement::Continue { label, span }
+    }
+}
+
+/// Name given to a loop by its `loop_label`, if any
+fn loop_label(pair: &Pair<Rule>) -> Option<String> {
+    pair.clone()
+        .into_inner()
+        .find(|p| p.as_rule() == Rule::loop_label)
+        .and_then(|label| label.into_inner().next())
+        .map(|ident| ident.as_str().to_string())
 }
 
 /// Parse an expression statement
-pub fn parse_expr_stmt(pair: Pair<Rule>) -> Statement {
-    let expr_pair = pair.into_inner().next().unwrap();
-    Statement::ExprStatement(parse_expr(expr_pair))
-}
-
-/// Parse an if-else statement
-pub fn parse_if_stmt(pair: Pair<Rule>) -> Statement {
-    let mut inner = pair.into_inner();
-
-    let condition = parse_expr(inner.find(|p|p.as_rule() == Rule::expr).expect("if must have condition"));
-    let then_block = parse_block(inner.find(|p| p.as_rule() == Rule::block).expect("if must have then block"));
-
-    let else_block = if let Some(else_part) = inner.next() {
-        match else_part.as_rule() {
-            Rule::block => Some(parse_block(else_part)),
-            Rule::if_stmt => {
-                // else if case - wrap in block containing if statement
-                let else_if_stmt = parse_if_stmt(else_part);
-                Some(Block {
-                    statements: vec![else_if_stmt],
-                })
-            }
-            _ => None,
-        }
+pub fn parse_expr_stmt(pair: Pair<Rule>, file: FileId) -> Result<Statement, SyntaxError> {
+    let expr_pair = pair
+        .clone()
+        .into_inner()
+        .next()
+        .ok_or_else(|| SyntaxError::missing("expression statement", "an expression", &pair))?;
+    Ok(Statement::ExprStatement(parse_expr(expr_pair, file)?))
+}
+
+/// Parses one statement of a block
+pub type StatementParser =
+    fn(Pair<Rule>, FileId, &mut Vec<SyntaxError>) -> Result<Statement, SyntaxError>;
+
+/// Parse an if-else statement. Errors inside its blocks are collected in `errors`.
+pub fn parse_if_stmt(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+) -> Result<Statement, SyntaxError> {
+    parse_if_else(pair, file, errors, parse_block_statement).map(Statement::IfElse)
+}
+
+/// Parse an if-else statement at top level. The branches of a `comptime if`
+/// are parsed with `parse_statement`, so they can select declarations.
+pub fn parse_top_level_if(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+    parse_statement: StatementParser,
+) -> Result<Statement, SyntaxError> {
+    let is_comptime = pair
+        .clone()
+        .into_inner()
+        .next()
+        .is_some_and(|p| p.as_rule() == Rule::kw_comptime);
+    let parse_statement = if is_comptime {
+        parse_statement
     } else {
-        None
+        parse_block_statement
     };
+    parse_if_else(pair, file, errors, parse_statement).map(Statement::IfElse)
+}
+
+/// Parse one `if` of a chain, with every `else if` that follows it
+fn parse_if_else(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+    parse_statement: StatementParser,
+) -> Result<IfElse, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut inner = pair.clone().into_inner();
+    let modifier = inner
+        .peek()
+        .filter(|p| p.as_rule() == Rule::kw_comptime)
+        .map(|_| Modifier::Comptime);
 
-    Statement::IfElse(IfElse {
+    let condition_pair = inner
+        .find(|p| p.as_rule() == Rule::expr)
+        .ok_or_else(|| SyntaxError::missing("if statement", "a condition", &pair))?;
+    let condition = parse_expr(condition_pair, file)?;
+    let then_pair = inner
+        .find(|p| p.as_rule() == Rule::block)
+        .ok_or_else(|| SyntaxError::missing("if statement", "a block", &pair))?;
+    let then_block = parse_block_with(then_pair, file, errors, parse_statement);
+
+    // Skip the `else` keyword to what follows it
+    let else_branch = match inner.find(|p| p.as_rule() != Rule::kw_else) {
+        Some(else_part) if else_part.as_rule() == Rule::block => Some(ElseBranch::Block(
+            parse_block_with(else_part, file, errors, parse_statement),
+        )),
+        Some(else_part) if else_part.as_rule() == Rule::if_stmt => Some(ElseBranch::If(Box::new(
+            parse_if_else(else_part, file, errors, parse_statement)?,
+        ))),
+        _ => None,
+    };
+
+    Ok(IfElse {
+        modifier,
         condition,
         then_block,
-        else_block,
+        else_branch,
+        span,
     })
 }
 
-/// Parse a while loop statement
-pub fn parse_while_stmt(pair: Pair<Rule>) -> Statement {
-    let mut inner = pair.into_inner();
+/// Parse a while loop statement. Errors inside its body are collected in `errors`.
+pub fn parse_while_stmt(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+) -> Result<Statement, SyntaxError> {
+    let span = span_of(&pair, file);
+    let label = loop_label(&pair);
+    let mut inner = pair.clone().into_inner();
+
+    let condition_pair = inner
+        .find(|p| p.as_rule() == Rule::expr)
+        .ok_or_else(|| SyntaxError::missing("while loop", "a condition", &pair))?;
+    let condition = parse_expr(condition_pair, file)?;
+    let block_pair = inner
+        .find(|p| p.as_rule() == Rule::block)
+        .ok_or_else(|| SyntaxError::missing("while loop", "a body", &pair))?;
+    let block = parse_block(block_pair, file, errors);
+
+    Ok(Statement::WhileLoop(WhileLoop {
+        label,
+        condition,
+        block,
+        span,
+    }))
+}
+
+/// Parse a for loop. Errors inside its body are collected in `errors`.
+pub fn parse_for_stmt(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+) -> Result<Statement, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut variable = None;
+    let mut iterable = None;
+    let mut block = None;
+    let mut is_comptime = false;
+
+    for part in pair.clone().into_inner() {
+        match part.as_rule() {
+            Rule::kw_comptime => is_comptime = true,
+            Rule::ident => variable = Some(part.as_str().to_string()),
+            Rule::range => {
+                let mut bounds = part.clone().into_inner();
+                let mut bound = |what| {
+                    bounds
+                        .next()
+                        .ok_or_else(|| SyntaxError::missing("range", what, &part))
+                        .and_then(|pair| parse_expr(pair, file))
+                };
+                iterable = Some(ForIterable::Range {
+                    start: bound("a start")?,
+                    end: bound("an end")?,
+                });
+            }
+            Rule::expr => iterable = Some(ForIterable::Array(parse_expr(part, file)?)),
+            Rule::block => block = Some(parse_block(part, file, errors)),
+            _ => {}
+        }
+    }
+
+    let missing = |what| SyntaxError::missing("for loop", what, &pair);
+    Ok(Statement::ForLoop(ForLoop {
+        label: loop_label(&pair),
+        variable: variable.ok_or_else(|| missing("a loop variable"))?,
+        iterable: iterable.ok_or_else(|| missing("a range or array"))?,
+        is_comptime,
+        block: block.ok_or_else(|| missing("a body"))?,
+        span,
+    }))
+}
+
+/// Parse a match statement. Errors inside its arms are collected in `errors`.
+pub fn parse_match_stmt(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+) -> Result<Statement, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut inner = pair.clone().into_inner();
 
-    let condition = parse_expr(inner.find(|p| p.as_rule() == Rule::expr).expect("while must have condition"));
-    let block = parse_block(inner.find(|p| p.as_rule() == Rule::block).expect("while must have block"));
+    let scrutinee_pair = inner
+        .find(|p| p.as_rule() == Rule::expr)
+        .ok_or_else(|| SyntaxError::missing("match statement", "a value", &pair))?;
+    let scrutinee = parse_expr(scrutinee_pair, file)?;
+    let arms = inner
+        .filter(|p| p.as_rule() == Rule::match_arm)
+        .map(|p| parse_match_arm(p, file, errors))
+        .collect::<Result<_, _>>()?;
 
-    Statement::WhileLoop(WhileLoop { condition, block })
+    Ok(Statement::Match(Match {
+        scrutinee,
+        arms,
+        span,
+    }))
 }
 
-/// Parse a block containing statements
-pub fn parse_block(pair: Pair<Rule>) -> Block {
-    let statements = pair
+fn parse_match_arm(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+) -> Result<MatchArm, SyntaxError> {
+    let span = span_of(&pair, file);
+    let mut patterns = Vec::new();
+    let mut body = None;
+    for part in pair.clone().into_inner() {
+        match part.as_rule() {
+            Rule::pattern => patterns.push(parse_pattern(part, file)?),
+            Rule::block => body = Some(parse_block(part, file, errors)),
+            _ => {}
+        }
+    }
+    let body = body.ok_or_else(|| SyntaxError::missing("match arm", "a block", &pair))?;
+    Ok(MatchArm {
+        patterns,
+        body,
+        span,
+    })
+}
+
+fn parse_pattern(pair: Pair<Rule>, file: FileId) -> Result<Pattern, SyntaxError> {
+    let span = span_of(&pair, file);
+    let inner = pair
+        .clone()
         .into_inner()
-        .map(parse_block_statement)
-        .collect::<Vec<_>>();
-    Block { statements }
+        .next()
+        .ok_or_else(|| SyntaxError::missing("match arm", "a pattern", &pair))?;
+    let kind = match inner.as_rule() {
+        Rule::wildcard => PatternKind::Wildcard,
+        Rule::negative_int => {
+            let literal = inner.as_str();
+            let value = parse_int_literal(literal).ok_or_else(|| SyntaxError::InvalidNumber {
+                literal: literal.to_string(),
+                span: pair_span(&inner),
+            })?;
+            PatternKind::Literal(Literal::Numeric(value))
+        }
+        Rule::destructure => parse_destructure(inner, file)?,
+        _ => match parse_primary(inner.clone(), file)?.kind {
+            ExpressionKind::Literal(lit) => PatternKind::Literal(lit),
+            ExpressionKind::Path { target, name } => PatternKind::Variant { target, name },
+            _ => return Err(SyntaxError::unexpected("match arm", &inner)),
+        },
+    };
+    Ok(Pattern { kind, span })
+}
+
+/// Parse a union variant pattern that binds its payload
+fn parse_destructure(pair: Pair<Rule>, file: FileId) -> Result<PatternKind, SyntaxError> {
+    let missing = |what| SyntaxError::missing("variant pattern", what, &pair);
+    let mut inner = pair.clone().into_inner();
+    let ExpressionKind::Path { target, name } =
+        parse_primary(inner.next().ok_or_else(|| missing("a variant"))?, file)?.kind
+    else {
+        return Err(SyntaxError::unexpected("variant pattern", &pair));
+    };
+    let fields = inner.next().ok_or_else(|| missing("a payload"))?;
+    let payload = match fields.as_rule() {
+        Rule::tuple_pattern => {
+            PayloadPattern::Tuple(fields.into_inner().map(|p| pattern_binding(&p)).collect())
+        }
+        _ => PayloadPattern::Struct(
+            fields
+                .into_inner()
+                .map(|field| {
+                    let span = span_of(&field, file);
+                    let mut parts = field.into_inner();
+                    let name = parts.next().map(|p| p.as_str().to_string());
+                    let name = name.ok_or_else(|| missing("a field name"))?;
+                    Ok(FieldBinding {
+                        // `field` alone binds the field to its own name
+                        binding: match parts.next() {
+                            Some(binding) => pattern_binding(&binding),
+                            None => Some(name.clone()),
+                        },
+                        field: name,
+                        span,
+                    })
+                })
+                .collect::<Result<_, SyntaxError>>()?,
+        ),
+    };
+    Ok(PatternKind::Destructure {
+        target,
+        name,
+        payload,
+    })
+}
+
+/// Name bound by a payload pattern, or `None` for `_`
+fn pattern_binding(pair: &Pair<Rule>) -> Option<String> {
+    match pair.as_str() {
+        "_" => None,
+        name => Some(name.to_string()),
+    }
+}
+
+/// Parse a block containing statements. Statements that fail to parse are
+/// reported in `errors` and left out of the block.
+pub fn parse_block(pair: Pair<Rule>, file: FileId, errors: &mut Vec<SyntaxError>) -> Block {
+    parse_block_with(pair, file, errors, parse_block_statement)
+}
+
+/// Parse a block whose statements are parsed with `parse_statement`
+fn parse_block_with(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+    parse_statement: StatementParser,
+) -> Block {
+    let span = span_of(&pair, file);
+    let mut statements = Vec::new();
+    for part in pair.into_inner() {
+        if part.as_rule() == Rule::invalid_stmt {
+            errors.push(diagnose_invalid_statement(&part));
+            continue;
+        }
+        match parse_statement(part, file, errors) {
+            Ok(statement) => statements.push(statement),
+            Err(err) => errors.push(err),
+        }
+    }
+    Block { statements, span }
 }
 
 /// Parse a statement inside a block (different from top-level statements)
-fn parse_block_statement(pair: Pair<Rule>) -> Statement {
+fn parse_block_statement(
+    pair: Pair<Rule>,
+    file: FileId,
+    errors: &mut Vec<SyntaxError>,
+) -> Result<Statement, SyntaxError> {
     let rule = pair.as_rule();
     if rule == Rule::statement {
-        let inner = pair.into_inner().next().expect("empty statement");
-        return parse_block_statement(inner);
+        let inner = pair
+            .clone()
+            .into_inner()
+            .next()
+            .ok_or_else(|| SyntaxError::missing("block", "a statement", &pair))?;
+        return parse_block_statement(inner, file, errors);
     }
     match pair.as_rule() {
-        Rule::declaration => parse_declaration(pair),
-        Rule::assignment => parse_assignment(pair),
-        Rule::expr_stmt => parse_expr_stmt(pair),
-        Rule::return_stmt => parse_return(pair),
-        Rule::if_stmt => parse_if_stmt(pair),
-        Rule::while_stmt => parse_while_stmt(pair),
-        _ => panic!("unexpected statement '{:?}' inside block", pair.as_rule()),
+        Rule::declaration => parse_declaration(pair, file),
+        Rule::assignment => parse_assignment(pair, file),
+        Rule::expr_stmt => parse_expr_stmt(pair, file),
+        Rule::return_stmt => parse_return(pair, file),
+        Rule::break_stmt | Rule::continue_stmt => Ok(parse_jump(pair, file)),
+        Rule::if_stmt => parse_if_stmt(pair, file, errors),
+        Rule::while_stmt => parse_while_stmt(pair, file, errors),
+        Rule::for_stmt => parse_for_stmt(pair, file, errors),
+        Rule::match_stmt => parse_match_stmt(pair, file, errors),
+        _ => Err(SyntaxError::unexpected("block", &pair)),
+    }
+}
+
+/// Explain why text skipped by error recovery is not a statement, by parsing
+/// it again on its own to get pest's description of the failure
+pub fn diagnose_invalid_statement(pair: &Pair<Rule>) -> SyntaxError {
+    let text = pair.as_str();
+    match AmberParser::parse(Rule::single_statement, text) {
+        Err(err) => SyntaxError::from_pest(&err, text, pair.as_span().start()),
+        Ok(_) => SyntaxError::InvalidSyntax {
+            message: "expected a statement".to_string(),
+            span: pair_span(pair),
+        },
     }
 }
 
@@ -147,7 +470,7 @@ fn parse_block_statement(pair: Pair<Rule>) -> Statement {
 mod tests {
     use super::*;
     use crate::build_ast;
-    use amber_ast::Expression;
+    use amber_ast::{ExpressionKind, NumericLiteral};
 
     #[test]
     fn test_basic_declaration() {
@@ -181,7 +504,7 @@ mod tests {
             assert!(!binding.is_mutable);
             assert_eq!(binding.name, "baud");
 
-            if let Some(Expression::Literal(val)) = &binding.value {
+            if let Some(ExpressionKind::Literal(val)) = binding.value.as_ref().map(|e| &e.kind) {
                 assert_eq!(val.to_string(), "9600");
             } else {
                 panic!("Expected integer value");
@@ -191,6 +514,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_return_parsing() {
+        let code = r#"
+            fn value() -> bool {
+                return true;
+            }
+
+            fn nothing() {
+                return;
+            }
+        "#;
+        let program = build_ast(code).unwrap();
+        let bodies: Vec<_> = program
+            .statements
+            .iter()
+            .map(|stmt| match stmt {
+                Statement::Function(func) => func.body.clone().unwrap().statements,
+                _ => panic!("Expected function"),
+            })
+            .collect();
+
+        match &bodies[0][0] {
+            Statement::Return { value: Some(expr), .. } => {
+                assert_eq!(expr.kind, ExpressionKind::Literal(amber_ast::Literal::Bool(true)));
+            }
+            other => panic!("Expected return with value, got {:?}", other),
+        }
+        assert!(matches!(bodies[1][0], Statement::Return { value: None, .. }));
+    }
+
     #[test]
     fn test_while_loop_parsing() {
         let code = r#"
@@ -203,4 +556,281 @@ mod tests {
         let result = crate::build_ast(code);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_for_loop_parsing() {
+        let code = r#"
+            fn test(values: [4]u8) {
+                for i in 0..N * 2 {}
+                for value in values {}
+                comptime for index in start..len(values) {}
+            }
+        "#;
+        let program = build_ast(code).unwrap();
+        let Statement::Function(func) = &program.statements[0] else {
+            panic!("Expected function");
+        };
+        let loops: Vec<_> = func
+            .body
+            .as_ref()
+            .unwrap()
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::ForLoop(for_loop) => for_loop,
+                other => panic!("Expected for loop, got {:?}", other),
+            })
+            .collect();
+
+        assert_eq!(loops[0].variable, "i");
+        assert!(!loops[0].is_comptime);
+        let ForIterable::Range { start, end } = &loops[0].iterable else {
+            panic!("Expected range");
+        };
+        assert_eq!(
+            start.kind,
+            ExpressionKind::Literal(Literal::Numeric(NumericLiteral::Integer(0)))
+        );
+        assert!(matches!(end.kind, ExpressionKind::BinaryExpr { .. }));
+
+        let ForIterable::Array(array) = &loops[1].iterable else {
+            panic!("Expected array");
+        };
+        assert_eq!(array.kind, ExpressionKind::Identifier("values".to_string()));
+
+        assert!(loops[2].is_comptime);
+        assert!(matches!(
+            &loops[2].iterable,
+            ForIterable::Range { end, .. } if matches!(end.kind, ExpressionKind::Call { .. })
+        ));
+
+        // `in` and `for` are reserved, but may start other names
+        assert!(build_ast("const in = 1;").is_err());
+        assert!(build_ast("const index = 1; const format = 2;").is_ok());
+    }
+
+    #[test]
+    fn test_else_if_chain() {
+        let code = r#"
+            fn test(x: i32) {
+                if x < 0 {
+                    return;
+                } else if x == 0 {
+                } else if x == 1 {
+                } else {
+                    return;
+                }
+            }
+        "#;
+        let program = build_ast(code).unwrap();
+        let Statement::Function(func) = &program.statements[0] else {
+            panic!("Expected function");
+        };
+        let Statement::IfElse(if_else) = &func.body.as_ref().unwrap().statements[0] else {
+            panic!("Expected if statement");
+        };
+
+        // Each `else if` links to the next instead of nesting inside a block
+        let mut conditions = vec![&if_else.condition];
+        let mut branch = &if_else.else_branch;
+        while let Some(ElseBranch::If(next)) = branch {
+            conditions.push(&next.condition);
+            branch = &next.else_branch;
+        }
+        assert_eq!(conditions.len(), 3);
+        assert!(matches!(
+            conditions[2].kind,
+            ExpressionKind::BinaryExpr { .. }
+        ));
+        let Some(ElseBranch::Block(last)) = branch else {
+            panic!("Expected final else block, got {:?}", branch);
+        };
+        assert!(matches!(last.statements[..], [Statement::Return { .. }]));
+    }
+
+    #[test]
+    fn test_comptime_if() {
+        let code = r#"
+            comptime if HAS_FPU {
+                fn scale(x: f32) -> f32 { return x; }
+            } else if HAS_DSP {
+            }
+            fn main() {
+                comptime if DEBUG {
+                    const level: u8 = 2;
+                }
+                if true {}
+            }
+        "#;
+        let program = build_ast(code).unwrap();
+        let Statement::IfElse(top) = &program.statements[0] else {
+            panic!("Expected if statement");
+        };
+        assert_eq!(top.modifier, Some(Modifier::Comptime));
+        assert!(matches!(
+            top.then_block.statements[..],
+            [Statement::Function(_)]
+        ));
+        let Some(ElseBranch::If(next)) = &top.else_branch else {
+            panic!("Expected else if");
+        };
+        assert_eq!(next.modifier, None);
+
+        let Statement::Function(func) = &program.statements[1] else {
+            panic!("Expected function");
+        };
+        let modifiers: Vec<_> = func
+            .body
+            .as_ref()
+            .unwrap()
+            .statements
+            .iter()
+            .map(|statement| match statement {
+                Statement::IfElse(if_else) => if_else.modifier.clone(),
+                other => panic!("Expected if statement, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(modifiers, [Some(Modifier::Comptime), None]);
+    }
+
+    #[test]
+    fn test_break_continue_and_labels() {
+        let code = r#"
+            fn test(flag: bool) {
+                outer: while flag {
+                    for i in 0..4 {
+                        continue;
+                        break outer;
+                    }
+                    continue outer;
+                    break;
+                }
+            }
+        "#;
+        let program = build_ast(code).unwrap();
+        let Statement::Function(func) = &program.statements[0] else {
+            panic!("Expected function");
+        };
+        let Statement::WhileLoop(outer) = &func.body.as_ref().unwrap().statements[0] else {
+            panic!("Expected while loop");
+        };
+        assert_eq!(outer.label.as_deref(), Some("outer"));
+        let Statement::ForLoop(inner) = &outer.block.statements[0] else {
+            panic!("Expected for loop");
+        };
+        assert_eq!(inner.label, None);
+        assert!(matches!(
+            &inner.block.statements[..],
+            [
+                Statement::Continue { label: None, .. },
+                Statement::Break { label: Some(label), .. },
+            ] if label == "outer"
+        ));
+        assert!(matches!(
+            &outer.block.statements[1..],
+            [
+                Statement::Continue { label: Some(_), .. },
+                Statement::Break { label: None, .. },
+            ]
+        ));
+
+        assert!(build_ast("const break = 1;").is_err());
+        assert!(build_ast("const breaker = 1; const continued = 2;").is_ok());
+    }
+
+    #[test]
+    fn test_match_parsing() {
+        let code = r#"
+            fn test(state: State, code: i32) {
+                match state {
+                    State::Idle | State::Done => {}
+                    _ => {
+                        return;
+                    }
+                }
+                match code {
+                    -1 => {}
+                    0 | 1 => {}
+                    _ => {}
+                }
+            }
+        "#;
+        let program = build_ast(code).unwrap();
+        let Statement::Function(func) = &program.statements[0] else {
+            panic!("Expected function");
+        };
+        let body = func.body.as_ref().unwrap();
+
+        let Statement::Match(first) = &body.statements[0] else {
+            panic!("Expected match statement");
+        };
+        assert_eq!(first.arms.len(), 2);
+        assert_eq!(
+            first.arms[0].patterns[1].kind,
+            PatternKind::Variant {
+                target: "State".to_string(),
+                name: "Done".to_string(),
+            }
+        );
+        assert_eq!(first.arms[1].patterns[0].kind, PatternKind::Wildcard);
+        assert_eq!(first.arms[1].body.statements.len(), 1);
+
+        let Statement::Match(second) = &body.statements[1] else {
+            panic!("Expected match statement");
+        };
+        assert_eq!(
+            second.arms[0].patterns[0].kind,
+            PatternKind::Literal(Literal::Numeric(NumericLiteral::Integer(-1)))
+        );
+        assert_eq!(second.arms[1].patterns.len(), 2);
+    }
+
+    #[test]
+    fn test_destructure_patterns() {
+        let code = r#"
+            fn test(packet: Packet) {
+                match packet {
+                    Packet::Data(len, _) => {}
+                    Packet::Move { x, y: _, z: depth } => {}
+                    Packet::Ping => {}
+                }
+            }
+        "#;
+        let program = build_ast(code).unwrap();
+        let Statement::Function(func) = &program.statements[0] else {
+            panic!("Expected function");
+        };
+        let Statement::Match(match_stmt) = &func.body.as_ref().unwrap().statements[0] else {
+            panic!("Expected match statement");
+        };
+        let patterns: Vec<_> = match_stmt
+            .arms
+            .iter()
+            .map(|arm| &arm.patterns[0].kind)
+            .collect();
+        assert_eq!(
+            *patterns[0],
+            PatternKind::Destructure {
+                target: "Packet".to_string(),
+                name: "Data".to_string(),
+                payload: PayloadPattern::Tuple(vec![Some("len".to_string()), None]),
+            }
+        );
+        let PatternKind::Destructure {
+            payload: PayloadPattern::Struct(fields),
+            ..
+        } = patterns[1]
+        else {
+            panic!("Expected struct pattern");
+        };
+        let bindings: Vec<_> = fields
+            .iter()
+            .map(|f| (f.field.as_str(), f.binding.as_deref()))
+            .collect();
+        assert_eq!(
+            bindings,
+            [("x", Some("x")), ("y", None), ("z", Some("depth"))]
+        );
+        assert!(matches!(patterns[2], PatternKind::Variant { .. }));
+    }
 }
@@ -1,32 +1,133 @@
 use pest::iterators::Pair;
 
-use amber_ast::Type;
+use amber_ast::{ExpressionKind, FileId, IntSuffix, Literal, NumericLiteral, Span, Type};
 
 use crate::Rule;
+use crate::error::{SyntaxError, pair_span};
+use crate::expr_parser::parse_expr;
+
+/// Source span covered by a grammar pair
+pub fn span_of(pair: &Pair<Rule>, file: FileId) -> Span {
+    let span = pair.as_span();
+    Span::new(file, span.start(), span.end() - span.start())
+}
+
+/// Parse an integer literal such as `42`, `-0x80`, `0b1010_0101` or `10u8`.
+/// `None` if its magnitude does not fit any C integer type.
+pub fn parse_int_literal(text: &str) -> Option<NumericLiteral> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let (digits, suffix) = match text.find(['u', 'i']) {
+        Some(at) => (&text[..at], Some(IntSuffix::parse(&text[at..])?)),
+        None => (text, None),
+    };
+    let (digits, radix) = match digits.get(..2) {
+        Some("0x") => (&digits[2..], 16),
+        Some("0b") => (&digits[2..], 2),
+        Some("0o") => (&digits[2..], 8),
+        _ => (digits, 10),
+    };
+    // No C integer type holds more than u64
+    let value = i128::from_str_radix(&digits.replace('_', ""), radix)
+        .ok()
+        .filter(|value| *value <= i128::from(u64::MAX))?;
+    let value = if negative { -value } else { value };
+    Some(match suffix {
+        Some(suffix) => NumericLiteral::Suffixed(value, suffix),
+        None => NumericLiteral::Integer(value),
+    })
+}
+
+/// Parse a character or byte literal such as `'a'`, `'\n'`, `'\x7F'` or
+/// `b'A'`. The grammar only lets through a single character or escape.
+pub fn parse_char_literal(text: &str) -> Literal {
+    let (byte, quoted) = match text.strip_prefix('b') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let c = unescape(&quoted[1..quoted.len() - 1])
+        .chars()
+        .next()
+        .unwrap_or_default();
+    if byte {
+        Literal::Byte(c as u8)
+    } else {
+        Literal::Char(c)
+    }
+}
+
+/// Parse a string literal such as `"hello\n"` or the C string `c"%d\n"`
+pub fn parse_string_literal(text: &str) -> Literal {
+    match text.strip_prefix('c') {
+        Some(quoted) => Literal::CString(unescape(&quoted[1..quoted.len() - 1])),
+        None => Literal::String(unescape(&text[1..text.len() - 1])),
+    }
+}
+
+/// Replace the escape sequences in the body of a character or string literal
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next() {
+            Some('n') => '\n',
+            Some('r') => '\r',
+            Some('t') => '\t',
+            Some('0') => '\0',
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                u8::from_str_radix(&digits, 16).map_or('\0', char::from)
+            }
+            Some(other) => other,
+            None => '\\',
+        });
+    }
+    unescaped
+}
 
 /// Parse a type from a grammar pair
-pub fn parse_type(pair: Pair<Rule>) -> Type {
+pub fn parse_type(pair: Pair<Rule>, file: FileId) -> Result<Type, SyntaxError> {
     match pair.as_rule() {
         Rule::type_def => {
             let inner = pair
+                .clone()
                 .into_inner()
                 .next()
-                .expect("type_def must contain inner");
-            parse_type(inner)
+                .ok_or_else(|| SyntaxError::missing("type", "a type name", &pair))?;
+            parse_type(inner, file)
         }
         Rule::ptr_type => {
-            let mutable = pair.as_str().contains("mut");
-            let inner_pair = pair
-                .into_inner()
-                .find(|p| p.as_rule()!= Rule::kw_mut)
-                .expect("ptr_type must contain inner");
-            let inner_type = parse_type(inner_pair);
-            Type::Pointer {
+            // Only a `mut` directly after this `*` makes it mutable; nested
+            // pointer types carry their own `mut`
+            let mut inner = pair.clone().into_inner().peekable();
+            let mutable = inner.next_if(|p| p.as_rule() == Rule::kw_mut).is_some();
+            let inner_pair = inner
+                .next()
+                .ok_or_else(|| SyntaxError::missing("pointer type", "a pointee type", &pair))?;
+            let inner_type = parse_type(inner_pair, file)?;
+            Ok(Type::Pointer {
                 is_mut: mutable,
                 inner: Box::new(inner_type),
-            }
+            })
         }
-        Rule::builtin_type => match pair.as_str() {
+        Rule::array_type => {
+            let mut inner = pair.clone().into_inner();
+            let len_pair = inner
+                .next()
+                .ok_or_else(|| SyntaxError::missing("array type", "a length", &pair))?;
+            let inner_pair = inner
+                .next()
+                .ok_or_else(|| SyntaxError::missing("array type", "an element type", &pair))?;
+            let inner = Box::new(parse_type(inner_pair, file)?);
+            parse_array_len(len_pair, file, inner)
+        }
+        Rule::builtin_type => Ok(match pair.as_str() {
             "u8" => Type::U8,
             "u16" => Type::U16,
             "u32" => Type::U32,
@@ -35,13 +136,35 @@ pub fn parse_type(pair: Pair<Rule>) -> Type {
             "i16" => Type::I16,
             "i32" => Type::I32,
             "i64" => Type::I64,
+            "f32" => Type::F32,
+            "f64" => Type::F64,
             "bool" => Type::Bool,
             "char" => Type::Char,
             "void" => Type::Void,
+            "str" => Type::Str,
             other => Type::Named(other.to_string()),
-        },
-        Rule::ident => Type::Named(pair.as_str().to_string()),
-        _ => panic!("Unexpected type rule: {:?}", pair.as_rule()),
+        }),
+        Rule::ident => Ok(Type::Named(pair.as_str().to_string())),
+        _ => Err(SyntaxError::unexpected("type", &pair)),
+    }
+}
+
+/// Build an array type from its length expression. Integer literals give the
+/// length directly; other expressions, e.g. `[N]u8`, are left for the
+/// comptime pass to evaluate.
+fn parse_array_len(pair: Pair<Rule>, file: FileId, inner: Box<Type>) -> Result<Type, SyntaxError> {
+    let span = pair_span(&pair);
+    let len = parse_expr(pair, file)?;
+    match len.kind {
+        ExpressionKind::Literal(Literal::Numeric(num)) if let Some(len) = num.as_integer() => {
+            let len = usize::try_from(len).map_err(|_| SyntaxError::InvalidArrayLength { span })?;
+            Ok(Type::Array { inner, len })
+        }
+        ExpressionKind::Literal(_) => Err(SyntaxError::InvalidArrayLength { span }),
+        _ => Ok(Type::ComptimeArray {
+            inner,
+            len: Box::new(len),
+        }),
     }
 }
 
@@ -55,7 +178,7 @@ mod tests {
         let result = AmberParser::parse(Rule::ptr_type, "*mut u32");
         assert!(result.is_ok());
         let pair = result.unwrap().next().unwrap();
-        let ty = parse_type(pair);
+        let ty = parse_type(pair, FileId::default()).unwrap();
         assert_eq!(
             ty,
             Type::Pointer {
@@ -64,6 +187,25 @@ mod tests {
             }
         )
     }
+    #[test]
+    fn test_parse_nested_pointer_mutability() {
+        let parse = |src| {
+            let pair = AmberParser::parse(Rule::type_def, src)
+                .unwrap()
+                .next()
+                .unwrap();
+            parse_type(pair, FileId::default()).unwrap()
+        };
+        let ptr = |inner, is_mut| Type::Pointer {
+            inner: Box::new(inner),
+            is_mut,
+        };
+        assert_eq!(parse("**mut u8"), ptr(ptr(Type::U8, true), false));
+        assert_eq!(parse("*mut *u8"), ptr(ptr(Type::U8, false), true));
+        // `mut` must be a whole word
+        assert_eq!(parse("*mutex_t"), ptr(Type::Named("mutex_t".into()), false));
+    }
+
     #[test]
     fn test_parse_builtin_types() {
         use crate::AmberParser;
@@ -73,7 +215,43 @@ mod tests {
         assert!(result.is_ok());
 
         let pair = result.unwrap().next().unwrap();
-        let ty = parse_type(pair);
+        let ty = parse_type(pair, FileId::default()).unwrap();
         assert_eq!(ty, Type::U32);
     }
+
+    #[test]
+    fn test_parse_array_types() {
+        let pair = AmberParser::parse(Rule::type_def, "[4]*u8")
+            .unwrap()
+            .next()
+            .unwrap();
+        assert_eq!(
+            parse_type(pair, FileId::default()),
+            Ok(Type::Array {
+                inner: Box::new(Type::Pointer {
+                    inner: Box::new(Type::U8),
+                    is_mut: false,
+                }),
+                len: 4,
+            })
+        );
+
+        let pair = AmberParser::parse(Rule::type_def, "[N]u8")
+            .unwrap()
+            .next()
+            .unwrap();
+        assert!(matches!(
+            parse_type(pair, FileId::default()),
+            Ok(Type::ComptimeArray { .. })
+        ));
+
+        let pair = AmberParser::parse(Rule::type_def, "[true]u8")
+            .unwrap()
+            .next()
+            .unwrap();
+        assert!(matches!(
+            parse_type(pair, FileId::default()),
+            Err(SyntaxError::InvalidArrayLength { .. })
+        ));
+    }
 }
@@ -0,0 +1,93 @@
+use amber_ast::Span;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum VmError {
+    #[error("unknown identifier '{name}' in comptime expression")]
+    UndefinedIdentifier { name: String },
+    #[error("'{name}' is not a comptime binding and cannot be used at compile time")]
+    NotComptime { name: String },
+    #[error("comptime binding '{name}' requires an initializer")]
+    MissingInitializer { name: String },
+    #[error("operator '{op}' cannot be applied to {left} and {right}")]
+    InvalidBinaryOperands {
+        op: &'static str,
+        left: &'static str,
+        right: &'static str,
+    },
+    #[error("operator '{op}' cannot be applied to {operand}")]
+    InvalidUnaryOperand {
+        op: &'static str,
+        operand: &'static str,
+    },
+    #[error("condition must be bool, found {found}")]
+    NonBoolCondition { found: &'static str },
+    #[error("division by zero in comptime expression")]
+    DivisionByZero,
+    #[error("{ty} arithmetic overflows in comptime expression")]
+    IntegerOverflow { ty: &'static str },
+    #[error("{value} is out of range for {ty}")]
+    IntegerOutOfRange { value: i128, ty: &'static str },
+    #[error("shift amount {amount} is out of range for a {bits}-bit integer")]
+    ShiftOutOfRange { amount: i128, bits: u32 },
+    #[error("index {index} is out of bounds for an array of length {len}")]
+    IndexOutOfBounds { index: i128, len: usize },
+    #[error("'{target}' has no field '{field}'")]
+    UnknownField { target: String, field: String },
+    #[error("array length must be a non-negative integer, found {found}")]
+    InvalidArrayLength { found: String },
+    #[error("discriminant of '{variant}' must be an integer, found {found}")]
+    InvalidDiscriminant {
+        variant: String,
+        found: &'static str,
+    },
+    #[error("range bounds of a comptime loop must be integers, found {found}")]
+    InvalidRangeBound { found: &'static str },
+    #[error("comptime loop would unroll {count} iterations, more than the limit of {limit}")]
+    UnrollLimit { count: i128, limit: i64 },
+    #[error("'{keyword}' cannot leave a comptime loop, which is unrolled")]
+    UnrolledLoopExit { keyword: &'static str },
+    #[error("unknown function '{name}' in comptime expression")]
+    UndefinedFunction { name: String },
+    #[error("extern function '{name}' cannot be called at compile time")]
+    ExternCall { name: String },
+    #[error("'{function}' takes {expected} arguments but {found} were given")]
+    ArgumentCount {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("comptime call to '{function}' did not return a value")]
+    NoReturnValue { function: String },
+    #[error("'{name}' is used before it is assigned")]
+    Uninitialized { name: String },
+    #[error("comptime call cannot assign to global '{name}'")]
+    GlobalAssignment { name: String },
+    #[error("comptime evaluation exceeded the limit of {limit} steps")]
+    StepLimit { limit: u64 },
+    #[error("comptime call to '{function}' exceeded the depth limit of {limit} nested calls")]
+    CallDepthLimit { function: String, limit: usize },
+    #[error("{what} cannot be evaluated at compile time")]
+    NotEvaluable { what: &'static str },
+    #[error("mismatched types: expected {expected}, found {found}")]
+    TypeMismatch {
+        expected: String,
+        found: &'static str,
+    },
+}
+
+/// An error raised while folding a program, with the span of the code whose
+/// evaluation raised it
+#[derive(Debug, Error, PartialEq)]
+#[error("{error}")]
+pub struct FoldError {
+    pub error: VmError,
+    pub span: Span,
+}
+
+impl VmError {
+    /// Attribute the error to the code at `span`
+    pub fn at(self, span: Span) -> FoldError {
+        FoldError { error: self, span }
+    }
+}
@@ -0,0 +1,777 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use amber_ast::{
+    BinaryOp, Expression, ExpressionKind, FieldInit, Function, Literal, NumericLiteral, Postfix,
+    Prefix, StructDef, Type, UnaryOp,
+};
+
+use crate::error::VmError;
+use crate::value::{Access, IntType, Overflow, Value};
+
+/// What a name in scope refers to during comptime evaluation
+#[derive(Debug, Clone)]
+pub(crate) enum Slot {
+    Comptime(Value),
+    Runtime,
+    /// A local of a comptime call declared without an initializer
+    Uninitialized,
+}
+
+/// Tree-walking interpreter for comptime expressions and function calls
+pub struct Evaluator {
+    pub(crate) scopes: Vec<HashMap<String, Slot>>,
+    /// Functions that may be called at compile time, by name
+    pub(crate) functions: HashMap<String, Rc<Function>>,
+    /// Struct definitions, by name, giving the types of literal fields
+    pub(crate) structs: HashMap<String, Rc<StructDef>>,
+    /// Statements executed by the outermost comptime call so far
+    pub(crate) steps: u64,
+    /// Comptime calls currently in progress
+    pub(crate) depth: usize,
+    /// What integer arithmetic does when a result leaves its type's range
+    pub(crate) overflow: Overflow,
+    /// Bindings of the program read since the folder last took this list
+    pub(crate) reads: Vec<String>,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        Self {
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            structs: HashMap::new(),
+            steps: 0,
+            depth: 0,
+            overflow: Overflow::default(),
+            reads: Vec::new(),
+        }
+    }
+
+    pub fn with_overflow(overflow: Overflow) -> Self {
+        Self {
+            overflow,
+            ..Self::new()
+        }
+    }
+
+    pub fn push_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            self.scopes.pop();
+        }
+    }
+
+    /// Bind a comptime-known value in the innermost scope
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.current_scope()
+            .insert(name.to_string(), Slot::Comptime(value));
+    }
+
+    /// Record a runtime name so it shadows any outer comptime binding
+    pub fn declare_runtime(&mut self, name: &str) {
+        self.current_scope().insert(name.to_string(), Slot::Runtime);
+    }
+
+    /// Make `func` callable from comptime expressions
+    pub fn define_function(&mut self, func: &Function) {
+        self.functions
+            .insert(func.name.clone(), Rc::new(func.clone()));
+    }
+
+    /// Make the struct `def` available to comptime struct literals
+    pub fn define_struct(&mut self, def: &StructDef) {
+        self.structs.insert(def.name.clone(), Rc::new(def.clone()));
+    }
+
+    pub fn lookup(&self, name: &str) -> Result<&Value, VmError> {
+        for scope in self.scopes.iter().rev() {
+            match scope.get(name) {
+                Some(Slot::Comptime(value)) => return Ok(value),
+                Some(Slot::Runtime) => {
+                    return Err(VmError::NotComptime {
+                        name: name.to_string(),
+                    });
+                }
+                Some(Slot::Uninitialized) => {
+                    return Err(VmError::Uninitialized {
+                        name: name.to_string(),
+                    });
+                }
+                None => {}
+            }
+        }
+        Err(VmError::UndefinedIdentifier {
+            name: name.to_string(),
+        })
+    }
+
+    /// Remember that the binding `name` was read, unless it is a local of
+    /// the running comptime call
+    fn note_read(&mut self, name: &str) {
+        let is_local = self.depth > 0
+            && self.scopes[1..]
+                .iter()
+                .any(|scope| scope.contains_key(name));
+        if !is_local && !self.reads.iter().any(|read| read == name) {
+            self.reads.push(name.to_string());
+        }
+    }
+
+    pub fn eval_expr(&mut self, expr: &Expression) -> Result<Value, VmError> {
+        self.eval_hinted(expr, None)
+    }
+
+    /// Evaluate `expr` as a value of type `ty`: untyped integer literals in
+    /// it take that type, and the result is converted to it
+    pub fn eval_expr_as(&mut self, expr: &Expression, ty: &Type) -> Result<Value, VmError> {
+        self.eval_hinted(expr, IntType::from_type(ty))?
+            .coerce_to(ty, self.overflow)
+    }
+
+    /// Evaluate `expr`, giving integer literals the type `hint` where the
+    /// operators between them and the result preserve it
+    fn eval_hinted(&mut self, expr: &Expression, hint: Option<IntType>) -> Result<Value, VmError> {
+        match &expr.kind {
+            ExpressionKind::Literal(lit) => match (Value::from_literal(lit), hint) {
+                (Value::Int(i), Some(ty)) => Ok(Value::Fixed(ty.convert(i, self.overflow)?, ty)),
+                (value, _) => Ok(value),
+            },
+            ExpressionKind::Identifier(name) => {
+                let value = self.lookup(name)?.clone();
+                self.note_read(name);
+                Ok(value)
+            }
+            ExpressionKind::Path { .. } => Err(VmError::NotEvaluable {
+                what: "associated item",
+            }),
+            ExpressionKind::UnaryExpr { op, expr } => match op {
+                UnaryOp::PrefixOp(Prefix::Not) => {
+                    let value = self.eval_expr(expr)?;
+                    self.eval_prefix(&Prefix::Not, value)
+                }
+                UnaryOp::PrefixOp(prefix) => {
+                    // `-128` is one literal, which fits an i8 though `128` does not
+                    if *prefix == Prefix::Neg
+                        && let ExpressionKind::Literal(Literal::Numeric(NumericLiteral::Integer(i))) =
+                            &expr.kind
+                        && let Some(ty) = hint
+                    {
+                        return Ok(Value::Fixed(ty.convert(-i, self.overflow)?, ty));
+                    }
+                    let value = self.eval_hinted(expr, hint)?;
+                    self.eval_prefix(prefix, value)
+                }
+                UnaryOp::PostfixOp(Postfix::Index { index }) => {
+                    let base = self.eval_expr(expr)?;
+                    let index = self.eval_expr(index)?;
+                    let Some(i) = index.as_int() else {
+                        return Err(VmError::InvalidBinaryOperands {
+                            op: "[]",
+                            left: base.type_name(),
+                            right: index.type_name(),
+                        });
+                    };
+                    base.get(&Access::Index(i)).cloned()
+                }
+            },
+            ExpressionKind::BinaryExpr { left, op, right } => {
+                self.eval_binary(left, op, right, hint)
+            }
+            ExpressionKind::TernaryExpr {
+                condition,
+                then_expr,
+                else_expr,
+            } => {
+                if expect_bool(self.eval_expr(condition)?)? {
+                    self.eval_hinted(then_expr, hint)
+                } else {
+                    self.eval_hinted(else_expr, hint)
+                }
+            }
+            ExpressionKind::Call { callee, args } => match &callee.kind {
+                ExpressionKind::Identifier(name) => {
+                    self.call(name, args)?
+                        .ok_or_else(|| VmError::NoReturnValue {
+                            function: name.clone(),
+                        })
+                }
+                _ => Err(VmError::NotEvaluable {
+                    what: "associated function call",
+                }),
+            },
+            ExpressionKind::MethodCall { .. } => Err(VmError::NotEvaluable {
+                what: "method call",
+            }),
+            ExpressionKind::ArrayLiteral(elements) => elements
+                .iter()
+                .map(|element| self.eval_expr(element))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            ExpressionKind::StructLiteral { name, fields } => self.eval_struct(name, fields),
+            ExpressionKind::FieldAccess { base, field } => self
+                .eval_expr(base)?
+                .get(&Access::Field(field.clone()))
+                .cloned(),
+            ExpressionKind::VariantLiteral { .. } => Err(VmError::NotEvaluable {
+                what: "union value",
+            }),
+        }
+    }
+
+    /// Evaluate a struct literal, giving each field the type it is declared with
+    fn eval_struct(&mut self, name: &str, fields: &[FieldInit]) -> Result<Value, VmError> {
+        let def = self.structs.get(name).cloned();
+        let fields = fields
+            .iter()
+            .map(|init| {
+                let declared = def
+                    .as_ref()
+                    .and_then(|def| def.fields.iter().find(|field| field.name == init.name));
+                let value = match declared {
+                    Some(field) => self.eval_expr_as(&init.value, &field.ty)?,
+                    None => self.eval_expr(&init.value)?,
+                };
+                Ok((init.name.clone(), value))
+            })
+            .collect::<Result<_, VmError>>()?;
+        Ok(Value::Struct {
+            name: name.to_string(),
+            fields,
+        })
+    }
+
+    /// All-zero value of `ty`, for aggregates declared without an initializer
+    pub(crate) fn zero_value(&self, ty: &Type) -> Option<Value> {
+        if let Some(int_ty) = IntType::from_type(ty) {
+            return Some(Value::Fixed(0, int_ty));
+        }
+        match ty {
+            Type::F32 => Some(Value::Float(0.0)),
+            Type::F64 => Some(Value::Double(0.0)),
+            Type::Bool => Some(Value::Bool(false)),
+            Type::Char => Some(Value::Char('\0')),
+            Type::Array { inner, len } => {
+                let zero = self.zero_value(inner)?;
+                Some(Value::Array(vec![zero; *len]))
+            }
+            Type::Named(name) => {
+                let def = self.structs.get(name)?;
+                let fields = def
+                    .fields
+                    .iter()
+                    .map(|field| Some((field.name.clone(), self.zero_value(&field.ty)?)))
+                    .collect::<Option<_>>()?;
+                Some(Value::Struct {
+                    name: name.clone(),
+                    fields,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn eval_binary(
+        &mut self,
+        left: &Expression,
+        op: &BinaryOp,
+        right: &Expression,
+        hint: Option<IntType>,
+    ) -> Result<Value, VmError> {
+        // Comparisons and logic produce bool, so their operands are typed by
+        // themselves; the amount of a shift does not affect its result type
+        let (left_hint, right_hint) = match op {
+            BinaryOp::Add
+            | BinaryOp::Sub
+            | BinaryOp::Mul
+            | BinaryOp::Div
+            | BinaryOp::Mod
+            | BinaryOp::BitAnd
+            | BinaryOp::BitOr
+            | BinaryOp::BitXor => (hint, hint),
+            BinaryOp::Shl | BinaryOp::Shr => (hint, None),
+            _ => (None, None),
+        };
+        let lhs = self.eval_hinted(left, left_hint)?;
+
+        // Logical operators short-circuit like their C counterparts
+        match op {
+            BinaryOp::And => {
+                return if expect_bool(lhs)? {
+                    Ok(Value::Bool(expect_bool(self.eval_expr(right)?)?))
+                } else {
+                    Ok(Value::Bool(false))
+                };
+            }
+            BinaryOp::Or => {
+                return if expect_bool(lhs)? {
+                    Ok(Value::Bool(true))
+                } else {
+                    Ok(Value::Bool(expect_bool(self.eval_expr(right)?)?))
+                };
+            }
+            _ => {}
+        }
+
+        let rhs = self.eval_hinted(right, right_hint)?;
+        self.eval_binary_values(op, lhs, rhs)
+    }
+
+    fn eval_prefix(&self, op: &Prefix, value: Value) -> Result<Value, VmError> {
+        match (op, value) {
+            (Prefix::Neg, Value::Int(i)) => i
+                .checked_neg()
+                .map(Value::Int)
+                .ok_or(VmError::IntegerOverflow { ty: "integer" }),
+            (Prefix::Neg, Value::Fixed(i, ty)) => self.fit(-i, ty),
+            (Prefix::Neg, Value::Float(f)) => Ok(Value::Float(-f)),
+            (Prefix::Neg, Value::Double(d)) => Ok(Value::Double(-d)),
+            (
+                Prefix::Pos,
+                value @ (Value::Int(_) | Value::Fixed(..) | Value::Float(_) | Value::Double(_)),
+            ) => Ok(value),
+            (Prefix::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (Prefix::BitNot, Value::Int(i)) => Ok(Value::Int(!i)),
+            // Complementing never leaves the range of a signed type, and for
+            // an unsigned one it is defined as wrapping
+            (Prefix::BitNot, Value::Fixed(i, ty)) => Ok(Value::Fixed(ty.wrap(!i), ty)),
+            (Prefix::PreInc | Prefix::PreDec, _) => Err(VmError::NotEvaluable {
+                what: "increment and decrement",
+            }),
+            (Prefix::Deref, _) => Err(VmError::NotEvaluable {
+                what: "pointer dereference",
+            }),
+            (Prefix::AddrOf | Prefix::AddrOfMut, _) => Err(VmError::NotEvaluable {
+                what: "address-of",
+            }),
+            (op, value) => Err(VmError::InvalidUnaryOperand {
+                op: op.symbol(),
+                operand: value.type_name(),
+            }),
+        }
+    }
+
+    fn eval_binary_values(&self, op: &BinaryOp, lhs: Value, rhs: Value) -> Result<Value, VmError> {
+        match (lhs, rhs) {
+            (Value::Int(a), Value::Int(b)) => eval_int(op, a, b),
+            (Value::Fixed(a, ty), Value::Int(b)) => self.eval_fixed(op, a, ty, b, None),
+            (Value::Fixed(a, ty), Value::Fixed(b, b_ty)) => {
+                self.eval_fixed(op, a, ty, b, Some(b_ty))
+            }
+            // The amount of a shift says nothing about the type of the result
+            (Value::Int(a), Value::Fixed(b, _)) if is_shift(op) => eval_int(op, a, b),
+            (Value::Int(a), Value::Fixed(b, ty)) => {
+                let a = if is_comparison(op) {
+                    a
+                } else {
+                    ty.convert(a, self.overflow)?
+                };
+                self.eval_fixed(op, a, ty, b, Some(ty))
+            }
+            (Value::Float(a), Value::Float(b)) => eval_float(op, a as f64, b as f64, false),
+            (Value::Float(a), Value::Double(b)) => eval_float(op, a as f64, b, true),
+            (Value::Double(a), Value::Float(b)) => eval_float(op, a, b as f64, true),
+            (Value::Double(a), Value::Double(b)) => eval_float(op, a, b, true),
+            // An untyped integer takes the type of the float it meets, as the
+            // checker gives it
+            (Value::Float(a), Value::Int(b)) => eval_float(op, a as f64, b as f32 as f64, false),
+            (Value::Int(a), Value::Float(b)) => eval_float(op, a as f32 as f64, b as f64, false),
+            (Value::Double(a), Value::Int(b)) => eval_float(op, a, b as f64, true),
+            (Value::Int(a), Value::Double(b)) => eval_float(op, a as f64, b, true),
+            (Value::Bool(a), Value::Bool(b)) => eval_bool(op, a, b),
+            (Value::Char(a), Value::Char(b)) => {
+                compare(op, &a, &b).ok_or(VmError::InvalidBinaryOperands {
+                    op: op.symbol(),
+                    left: "char",
+                    right: "char",
+                })
+            }
+            (lhs, rhs) => Err(VmError::InvalidBinaryOperands {
+                op: op.symbol(),
+                left: lhs.type_name(),
+                right: rhs.type_name(),
+            }),
+        }
+    }
+
+    /// Apply `op` to `a` of type `ty` and `b`, of type `b_ty` or untyped.
+    /// The result is computed exactly and then fitted to `ty`, so every
+    /// operation wraps or fails on its own; the generated C casts results
+    /// narrower than `int` back to their type to do the same.
+    fn eval_fixed(
+        &self,
+        op: &BinaryOp,
+        a: i128,
+        ty: IntType,
+        b: i128,
+        b_ty: Option<IntType>,
+    ) -> Result<Value, VmError> {
+        // Comparisons and shifts accept operands of different types
+        if let Some(result) = compare(op, &a, &b) {
+            return Ok(result);
+        }
+        if is_shift(op) {
+            if !(0..i128::from(ty.bits)).contains(&b) {
+                return Err(VmError::ShiftOutOfRange {
+                    amount: b,
+                    bits: ty.bits,
+                });
+            }
+            // Bits shifted out of an unsigned type are discarded, as C
+            // defines; for a signed one they overflow like any other result
+            return match op {
+                BinaryOp::Shl if ty.signed => self.fit(a << b, ty),
+                BinaryOp::Shl => Ok(Value::Fixed(ty.wrap(a << b), ty)),
+                _ => Ok(Value::Fixed(a >> b, ty)),
+            };
+        }
+
+        let b = match b_ty {
+            Some(b_ty) if b_ty != ty => {
+                return Err(VmError::InvalidBinaryOperands {
+                    op: op.symbol(),
+                    left: ty.name(),
+                    right: b_ty.name(),
+                });
+            }
+            Some(_) => b,
+            None => ty.convert(b, self.overflow)?,
+        };
+        let result = match op {
+            BinaryOp::Add => a + b,
+            BinaryOp::Sub => a - b,
+            // Operands are at most 64 bits wide, so only a product can leave
+            // the range of i128; wrapping it there keeps the low bits exact
+            BinaryOp::Mul => match a.checked_mul(b) {
+                Some(result) => result,
+                None if self.overflow == Overflow::Wrap => a.wrapping_mul(b),
+                None => return Err(VmError::IntegerOverflow { ty: ty.name() }),
+            },
+            BinaryOp::Div | BinaryOp::Mod if b == 0 => return Err(VmError::DivisionByZero),
+            BinaryOp::Div => a / b,
+            BinaryOp::Mod => a % b,
+            BinaryOp::BitAnd => a & b,
+            BinaryOp::BitOr => a | b,
+            BinaryOp::BitXor => a ^ b,
+            _ => {
+                return Err(VmError::InvalidBinaryOperands {
+                    op: op.symbol(),
+                    left: ty.name(),
+                    right: ty.name(),
+                });
+            }
+        };
+        self.fit(result, ty)
+    }
+
+    /// Fit the exact result of arithmetic on `ty` into its range
+    fn fit(&self, result: i128, ty: IntType) -> Result<Value, VmError> {
+        if ty.contains(result) {
+            Ok(Value::Fixed(result, ty))
+        } else if self.overflow == Overflow::Wrap {
+            Ok(Value::Fixed(ty.wrap(result), ty))
+        } else {
+            Err(VmError::IntegerOverflow { ty: ty.name() })
+        }
+    }
+
+    pub(crate) fn current_scope(&mut self) -> &mut HashMap<String, Slot> {
+        self.scopes
+            .last_mut()
+            .expect("evaluator always has a global scope")
+    }
+}
+
+pub(crate) fn expect_bool(value: Value) -> Result<bool, VmError> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        other => Err(VmError::NonBoolCondition {
+            found: other.type_name(),
+        }),
+    }
+}
+
+/// Arithmetic on integers of no particular type, which must stay exact
+fn eval_int(op: &BinaryOp, a: i128, b: i128) -> Result<Value, VmError> {
+    if let Some(result) = compare(op, &a, &b) {
+        return Ok(result);
+    }
+    let overflow = VmError::IntegerOverflow { ty: "integer" };
+    let result = match op {
+        BinaryOp::Add => a.checked_add(b).ok_or(overflow)?,
+        BinaryOp::Sub => a.checked_sub(b).ok_or(overflow)?,
+        BinaryOp::Mul => a.checked_mul(b).ok_or(overflow)?,
+        BinaryOp::Div | BinaryOp::Mod if b == 0 => return Err(VmError::DivisionByZero),
+        BinaryOp::Div => a.checked_div(b).ok_or(overflow)?,
+        BinaryOp::Mod => a.checked_rem(b).ok_or(overflow)?,
+        BinaryOp::BitAnd => a & b,
+        BinaryOp::BitOr => a | b,
+        BinaryOp::BitXor => a ^ b,
+        // No C integer type is wider than 64 bits
+        BinaryOp::Shl | BinaryOp::Shr if !(0..64).contains(&b) => {
+            return Err(VmError::ShiftOutOfRange {
+                amount: b,
+                bits: 64,
+            });
+        }
+        BinaryOp::Shl => a.checked_mul(1 << b).ok_or(overflow)?,
+        BinaryOp::Shr => a >> b,
+        _ => {
+            return Err(VmError::InvalidBinaryOperands {
+                op: op.symbol(),
+                left: "integer",
+                right: "integer",
+            });
+        }
+    };
+    Ok(Value::Int(result))
+}
+
+fn eval_float(op: &BinaryOp, a: f64, b: f64, is_double: bool) -> Result<Value, VmError> {
+    if let Some(result) = compare(op, &a, &b) {
+        return Ok(result);
+    }
+    let result = match op {
+        BinaryOp::Add => a + b,
+        BinaryOp::Sub => a - b,
+        BinaryOp::Mul => a * b,
+        BinaryOp::Div => a / b,
+        _ => {
+            let name = if is_double { "f64" } else { "f32" };
+            return Err(VmError::InvalidBinaryOperands {
+                op: op.symbol(),
+                left: name,
+                right: name,
+            });
+        }
+    };
+    if is_double {
+        Ok(Value::Double(result))
+    } else {
+        Ok(Value::Float(result as f32))
+    }
+}
+
+fn eval_bool(op: &BinaryOp, a: bool, b: bool) -> Result<Value, VmError> {
+    let result = match op {
+        BinaryOp::Eq => a == b,
+        BinaryOp::Ne => a != b,
+        BinaryOp::BitAnd => a & b,
+        BinaryOp::BitOr => a | b,
+        BinaryOp::BitXor => a ^ b,
+        _ => {
+            return Err(VmError::InvalidBinaryOperands {
+                op: op.symbol(),
+                left: "bool",
+                right: "bool",
+            });
+        }
+    };
+    Ok(Value::Bool(result))
+}
+
+fn is_shift(op: &BinaryOp) -> bool {
+    matches!(op, BinaryOp::Shl | BinaryOp::Shr)
+}
+
+fn is_comparison(op: &BinaryOp) -> bool {
+    matches!(
+        op,
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+    )
+}
+
+/// Evaluate a comparison operator, returning `None` for non-comparison operators
+fn compare<T: PartialOrd>(op: &BinaryOp, a: &T, b: &T) -> Option<Value> {
+    let result = match op {
+        BinaryOp::Eq => a == b,
+        BinaryOp::Ne => a != b,
+        BinaryOp::Lt => a < b,
+        BinaryOp::Le => a <= b,
+        BinaryOp::Gt => a > b,
+        BinaryOp::Ge => a >= b,
+        _ => return None,
+    };
+    Some(Value::Bool(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amber_ast::{Span, Statement};
+    use amber_parser::build_ast;
+
+    fn eval(source: &str) -> Result<Value, VmError> {
+        let program = build_ast(&format!("const v = {};", source)).unwrap();
+        match &program.statements[0] {
+            Statement::Binding(binding) => {
+                Evaluator::new().eval_expr(binding.value.as_ref().unwrap())
+            }
+            _ => panic!("Expected binding"),
+        }
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(eval("16000000 / 9600"), Ok(Value::Int(1666)));
+        assert_eq!(eval("(1 + 2) * 3 - -4"), Ok(Value::Int(13)));
+        assert_eq!(eval("1 << 4 | 3"), Ok(Value::Int(19)));
+        assert_eq!(eval("1.5 * 2.0"), Ok(Value::Double(3.0)));
+    }
+
+    /// Evaluate `source` as the initializer of a binding of type `ty`
+    fn eval_as(ty: &str, source: &str, overflow: Overflow) -> Result<Value, VmError> {
+        let program = build_ast(&format!("const v: {} = {};", ty, source)).unwrap();
+        match &program.statements[0] {
+            Statement::Binding(binding) => Evaluator::with_overflow(overflow).eval_expr_as(
+                binding.value.as_ref().unwrap(),
+                binding.ty.as_ref().unwrap(),
+            ),
+            _ => panic!("Expected binding"),
+        }
+    }
+
+    fn fixed(value: i128, ty: Type) -> Value {
+        Value::Fixed(value, IntType::from_type(&ty).unwrap())
+    }
+
+    #[test]
+    fn test_fixed_width_overflow() {
+        use Overflow::{Error, Wrap};
+
+        assert_eq!(eval_as("u8", "250 + 10", Wrap), Ok(fixed(4, Type::U8)));
+        assert_eq!(
+            eval_as("u8", "250 + 10", Error),
+            Err(VmError::IntegerOverflow { ty: "u8" })
+        );
+        assert_eq!(eval_as("i8", "127 + 1", Wrap), Ok(fixed(-128, Type::I8)));
+        // Each operation wraps on its own, like `((uint8_t)(200 * 2)) / 2` in C
+        assert_eq!(eval_as("u8", "200 * 2 / 2", Wrap), Ok(fixed(72, Type::U8)));
+        assert_eq!(
+            eval_as("u32", "0 - 1", Wrap),
+            Ok(fixed(4294967295, Type::U32))
+        );
+        assert_eq!(eval_as("u8", "~0", Error), Ok(fixed(255, Type::U8)));
+        assert_eq!(
+            eval_as("u8", "300", Error),
+            Err(VmError::IntegerOutOfRange {
+                value: 300,
+                ty: "u8"
+            })
+        );
+        // u64 values above i64::MAX are exact, and products wrap modulo 2^64
+        assert_eq!(
+            eval_as("u64", "9223372036854775807 + 1", Error),
+            Ok(fixed(9223372036854775808, Type::U64))
+        );
+        assert_eq!(
+            eval_as("u64", "18446744073709551615 * 18446744073709551615", Wrap),
+            Ok(fixed(1, Type::U64))
+        );
+        assert_eq!(
+            eval_as("u64", "18446744073709551615 * 2", Error),
+            Err(VmError::IntegerOverflow { ty: "u64" })
+        );
+    }
+
+    #[test]
+    fn test_fixed_width_division_and_shifts() {
+        use Overflow::{Error, Wrap};
+
+        assert_eq!(
+            eval_as("u32", "(0 - 1) / 2", Wrap),
+            Ok(fixed(2147483647, Type::U32))
+        );
+        assert_eq!(eval_as("i32", "-7 / 2", Error), Ok(fixed(-3, Type::I32)));
+        assert_eq!(eval_as("i32", "-7 % 2", Error), Ok(fixed(-1, Type::I32)));
+        assert_eq!(eval_as("u8", "1 / 0", Wrap), Err(VmError::DivisionByZero));
+        assert_eq!(eval_as("u8", "3 << 7", Error), Ok(fixed(128, Type::U8)));
+        assert_eq!(
+            eval_as("i32", "1 << 31", Error),
+            Err(VmError::IntegerOverflow { ty: "i32" })
+        );
+        assert_eq!(
+            eval_as("i32", "1 << 31", Wrap),
+            Ok(fixed(-2147483648, Type::I32))
+        );
+        assert_eq!(eval_as("i16", "-16 >> 2", Error), Ok(fixed(-4, Type::I16)));
+        assert_eq!(
+            eval_as("u8", "1 << 8", Wrap),
+            Err(VmError::ShiftOutOfRange { amount: 8, bits: 8 })
+        );
+        assert_eq!(
+            eval_as("u32", "1 << -1", Wrap),
+            Err(VmError::ShiftOutOfRange {
+                amount: -1,
+                bits: 32
+            })
+        );
+        // Comparisons see the values, whatever their types
+        assert_eq!(eval_as("bool", "1 + 1 == 2", Error), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn test_logic_and_ternary() {
+        assert_eq!(eval("1 < 2 && !false"), Ok(Value::Bool(true)));
+        assert_eq!(eval("3 > 4 ? 10 : 20"), Ok(Value::Int(20)));
+        // The right-hand side is never evaluated, so no division error
+        assert_eq!(eval("false && 1 / 0 == 0"), Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let mut evaluator = Evaluator::new();
+        evaluator.define("a", Value::Int(7));
+        evaluator.push_scope();
+        evaluator.declare_runtime("b");
+
+        let ident = |name: &str| {
+            Expression::new(
+                ExpressionKind::Identifier(name.to_string()),
+                Span::default(),
+            )
+        };
+        let (a, b, c) = (ident("a"), ident("b"), ident("c"));
+        assert_eq!(evaluator.eval_expr(&a), Ok(Value::Int(7)));
+        assert!(matches!(
+            evaluator.eval_expr(&b),
+            Err(VmError::NotComptime { .. })
+        ));
+        assert!(matches!(
+            evaluator.eval_expr(&c),
+            Err(VmError::UndefinedIdentifier { .. })
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(eval("1 / 0"), Err(VmError::DivisionByZero));
+        assert_eq!(
+            eval("1 << 64"),
+            Err(VmError::ShiftOutOfRange {
+                amount: 64,
+                bits: 64
+            })
+        );
+        assert!(matches!(
+            eval("1 + true"),
+            Err(VmError::InvalidBinaryOperands { .. })
+        ));
+        assert!(matches!(eval("*1"), Err(VmError::NotEvaluable { .. })));
+        assert_eq!(
+            eval("f(1)"),
+            Err(VmError::UndefinedFunction {
+                name: "f".to_string()
+            })
+        );
+    }
+}
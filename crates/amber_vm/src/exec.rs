@@ -0,0 +1,605 @@
+use std::collections::HashMap;
+
+use amber_ast::{
+    Block, ElseBranch, Expression, ExpressionKind, ForIterable, ForLoop, IfElse, Modifier, Param,
+    Postfix, Statement, UnaryOp, WhileLoop,
+};
+
+use crate::error::VmError;
+use crate::eval::{Evaluator, Slot, expect_bool};
+use crate::value::{Access, Value};
+
+/// Most statements and loop iterations one outermost comptime call may execute
+pub const MAX_STEPS: u64 = 100_000;
+
+/// Most comptime calls that may be in progress at once, counting recursion
+pub const MAX_CALL_DEPTH: usize = 64;
+
+/// How control leaves a statement
+enum Flow {
+    Next,
+    Return(Option<Value>),
+    Break(Option<String>),
+    Continue(Option<String>),
+}
+
+impl Evaluator {
+    /// Call the function `name` at compile time, returning its result or
+    /// `None` if it returns nothing. The body only sees its parameters, its
+    /// own locals and comptime bindings of the global scope.
+    pub(crate) fn call(
+        &mut self,
+        name: &str,
+        args: &[Expression],
+    ) -> Result<Option<Value>, VmError> {
+        let func = self
+            .functions
+            .get(name)
+            .cloned()
+            .ok_or_else(|| VmError::UndefinedFunction {
+                name: name.to_string(),
+            })?;
+        let body = match &func.body {
+            Some(body) if !func.is_extern => body,
+            _ => {
+                return Err(VmError::ExternCall {
+                    name: name.to_string(),
+                });
+            }
+        };
+        if func.params.len() != args.len() {
+            return Err(VmError::ArgumentCount {
+                function: name.to_string(),
+                expected: func.params.len(),
+                found: args.len(),
+            });
+        }
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(VmError::CallDepthLimit {
+                function: name.to_string(),
+                limit: MAX_CALL_DEPTH,
+            });
+        }
+
+        let mut frame = HashMap::new();
+        for (param, arg) in func.params.iter().zip(args) {
+            let Param::Typed { name, ty } = param else {
+                return Err(VmError::NotEvaluable {
+                    what: "method call",
+                });
+            };
+            let value = self.eval_expr_as(arg, ty)?;
+            frame.insert(name.clone(), Slot::Comptime(value));
+        }
+
+        if self.depth == 0 {
+            self.steps = 0;
+        }
+        // Swap the caller's locals out so the body cannot see them
+        let caller = self.scopes.split_off(1);
+        self.scopes.push(frame);
+        self.depth += 1;
+        let flow = self.exec_block(body);
+        self.depth -= 1;
+        self.scopes.truncate(1);
+        self.scopes.extend(caller);
+
+        match flow? {
+            Flow::Return(Some(value)) => match &func.return_type {
+                Some(ty) => value.coerce_to(ty, self.overflow).map(Some),
+                None => Ok(Some(value)),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    fn exec_block(&mut self, block: &Block) -> Result<Flow, VmError> {
+        self.push_scope();
+        let flow = self.exec_statements(&block.statements);
+        self.pop_scope();
+        flow
+    }
+
+    fn exec_statements(&mut self, statements: &[Statement]) -> Result<Flow, VmError> {
+        for statement in statements {
+            match self.exec_statement(statement)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn exec_statement(&mut self, statement: &Statement) -> Result<Flow, VmError> {
+        self.step()?;
+        match statement {
+            Statement::Binding(binding) => {
+                match &binding.value {
+                    Some(expr) => {
+                        let value = match &binding.ty {
+                            Some(ty) => self.eval_expr_as(expr, ty)?,
+                            None => self.eval_expr(expr)?,
+                        };
+                        self.define(&binding.name, value);
+                    }
+                    // Aggregates start out zeroed so that their elements can
+                    // be filled in one at a time
+                    None => match binding.ty.as_ref().and_then(|ty| self.zero_value(ty)) {
+                        Some(zero @ (Value::Array(_) | Value::Struct { .. })) => {
+                            self.define(&binding.name, zero)
+                        }
+                        _ => {
+                            self.current_scope()
+                                .insert(binding.name.clone(), Slot::Uninitialized);
+                        }
+                    },
+                }
+                Ok(Flow::Next)
+            }
+            Statement::Assignment { target, value, .. } => {
+                let mut path = Vec::new();
+                let name = self.place(target, &mut path)?;
+                // The assigned value keeps the integer type of what it replaces
+                let current = self.lookup(name).and_then(|root| {
+                    path.iter()
+                        .try_fold(root, |value, access| value.get(access))
+                });
+                let ty = match current {
+                    Ok(Value::Fixed(_, ty)) => Some(ty.to_type()),
+                    _ => None,
+                };
+                let value = match &ty {
+                    Some(ty) => self.eval_expr_as(value, ty)?,
+                    None => self.eval_expr(value)?,
+                };
+                self.assign(name, &path, value)?;
+                Ok(Flow::Next)
+            }
+            Statement::ExprStatement(expr) => {
+                // A call in statement position may return nothing
+                if let ExpressionKind::Call { callee, args } = &expr.kind
+                    && let ExpressionKind::Identifier(name) = &callee.kind
+                {
+                    self.call(name, args)?;
+                } else {
+                    self.eval_expr(expr)?;
+                }
+                Ok(Flow::Next)
+            }
+            Statement::IfElse(if_else) => self.exec_if_else(if_else),
+            Statement::WhileLoop(while_loop) => self.exec_while(while_loop),
+            Statement::ForLoop(for_loop) => self.exec_for(for_loop),
+            Statement::Block(block) => self.exec_block(block),
+            Statement::Return { value, .. } => Ok(Flow::Return(
+                value
+                    .as_ref()
+                    .map(|expr| self.eval_expr(expr))
+                    .transpose()?,
+            )),
+            Statement::Break { label, .. } => Ok(Flow::Break(label.clone())),
+            Statement::Continue { label, .. } => Ok(Flow::Continue(label.clone())),
+            Statement::Match(_) => Err(VmError::NotEvaluable {
+                what: "match statement",
+            }),
+            Statement::Struct(_)
+            | Statement::Enum(_)
+            | Statement::Union(_)
+            | Statement::Function(_)
+            | Statement::Impl(_) => Err(VmError::NotEvaluable {
+                what: "nested declaration",
+            }),
+        }
+    }
+
+    fn exec_if_else(&mut self, if_else: &IfElse) -> Result<Flow, VmError> {
+        // Like the comptime pass, a `comptime if` runs its branch in the
+        // enclosing scope
+        let is_comptime = if_else.modifier == Some(Modifier::Comptime);
+        let mut branch = Some(if_else);
+        while let Some(current) = branch {
+            let condition = self.eval_expr(&current.condition)?;
+            if expect_bool(condition)? {
+                return self.exec_branch(&current.then_block, is_comptime);
+            }
+            branch = match &current.else_branch {
+                Some(ElseBranch::If(next)) => Some(next),
+                Some(ElseBranch::Block(block)) => {
+                    return self.exec_branch(block, is_comptime);
+                }
+                None => None,
+            };
+        }
+        Ok(Flow::Next)
+    }
+
+    fn exec_branch(&mut self, block: &Block, is_comptime: bool) -> Result<Flow, VmError> {
+        if is_comptime {
+            self.exec_statements(&block.statements)
+        } else {
+            self.exec_block(block)
+        }
+    }
+
+    fn exec_while(&mut self, while_loop: &WhileLoop) -> Result<Flow, VmError> {
+        loop {
+            self.step()?;
+            let condition = self.eval_expr(&while_loop.condition)?;
+            if !expect_bool(condition)? {
+                return Ok(Flow::Next);
+            }
+            match self.exec_block(&while_loop.block)? {
+                Flow::Break(label) if targets(&label, &while_loop.label) => {
+                    return Ok(Flow::Next);
+                }
+                Flow::Next => {}
+                Flow::Continue(label) if targets(&label, &while_loop.label) => {}
+                flow => return Ok(flow),
+            }
+        }
+    }
+
+    fn exec_for(&mut self, for_loop: &ForLoop) -> Result<Flow, VmError> {
+        let ForIterable::Range { start, end } = &for_loop.iterable else {
+            return Err(VmError::NotEvaluable { what: "array loop" });
+        };
+        let (start, end) = (self.eval_expr(start)?, self.eval_expr(end)?);
+        let (Some(first), Some(last)) = (start.as_int(), end.as_int()) else {
+            let found = if start.as_int().is_some() {
+                &end
+            } else {
+                &start
+            };
+            return Err(VmError::InvalidRangeBound {
+                found: found.type_name(),
+            });
+        };
+        // The loop variable takes the integer type of its bounds
+        let ty = match (&start, &end) {
+            (Value::Fixed(_, ty), _) | (_, Value::Fixed(_, ty)) => Some(*ty),
+            _ => None,
+        };
+
+        for i in first..last {
+            self.step()?;
+            self.push_scope();
+            let value = match ty {
+                Some(ty) => Value::Fixed(i, ty),
+                None => Value::Int(i),
+            };
+            self.define(&for_loop.variable, value);
+            let flow = self.exec_block(&for_loop.block);
+            self.pop_scope();
+            match flow? {
+                Flow::Break(label) if targets(&label, &for_loop.label) => break,
+                Flow::Next => {}
+                Flow::Continue(label) if targets(&label, &for_loop.label) => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Local at the root of the assignment target `target`, with the
+    /// element and field accesses that lead from it to the assigned place
+    fn place<'a>(
+        &mut self,
+        target: &'a Expression,
+        path: &mut Vec<Access>,
+    ) -> Result<&'a str, VmError> {
+        match &target.kind {
+            ExpressionKind::Identifier(name) => Ok(name),
+            ExpressionKind::FieldAccess { base, field } => {
+                let root = self.place(base, path)?;
+                path.push(Access::Field(field.clone()));
+                Ok(root)
+            }
+            ExpressionKind::UnaryExpr {
+                op: UnaryOp::PostfixOp(Postfix::Index { index }),
+                expr,
+            } => {
+                let root = self.place(expr, path)?;
+                let index = self.eval_expr(index)?;
+                let i = index.as_int().ok_or(VmError::InvalidBinaryOperands {
+                    op: "[]",
+                    left: "array",
+                    right: index.type_name(),
+                })?;
+                path.push(Access::Index(i));
+                Ok(root)
+            }
+            _ => Err(VmError::NotEvaluable {
+                what: "assignment through a pointer",
+            }),
+        }
+    }
+
+    /// Store `value` in the local `name` of the running call, or in the
+    /// element of it that `path` leads to. Globals are either comptime
+    /// constants or runtime state, so neither may be assigned.
+    fn assign(&mut self, name: &str, path: &[Access], value: Value) -> Result<(), VmError> {
+        for scope in self.scopes[1..].iter_mut().rev() {
+            match scope.get_mut(name) {
+                Some(Slot::Runtime) => {
+                    return Err(VmError::NotComptime {
+                        name: name.to_string(),
+                    });
+                }
+                Some(Slot::Comptime(root)) => {
+                    let target = path
+                        .iter()
+                        .try_fold(root, |value, access| value.get_mut(access))?;
+                    *target = value;
+                    return Ok(());
+                }
+                Some(slot) if path.is_empty() => {
+                    *slot = Slot::Comptime(value);
+                    return Ok(());
+                }
+                Some(_) => {
+                    return Err(VmError::Uninitialized {
+                        name: name.to_string(),
+                    });
+                }
+                None => {}
+            }
+        }
+        if self.scopes[0].contains_key(name) {
+            Err(VmError::GlobalAssignment {
+                name: name.to_string(),
+            })
+        } else {
+            Err(VmError::UndefinedIdentifier {
+                name: name.to_string(),
+            })
+        }
+    }
+
+    fn step(&mut self) -> Result<(), VmError> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Err(VmError::StepLimit { limit: MAX_STEPS });
+        }
+        Ok(())
+    }
+}
+
+/// Whether a jump with `label` targets the loop named `loop_label`
+fn targets(label: &Option<String>, loop_label: &Option<String>) -> bool {
+    label.is_none() || label == loop_label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Overflow;
+    use crate::{fold_program, fold_program_with};
+    use amber_ast::Program;
+    use amber_parser::build_ast;
+
+    fn fold(code: &str) -> Result<Program, VmError> {
+        fold_program(&build_ast(code).unwrap()).map_err(|err| err.error)
+    }
+
+    /// Folded value of the top-level binding `name`
+    fn folded(program: &Program, name: &str) -> Option<i128> {
+        program
+            .statements
+            .iter()
+            .find_map(|statement| match statement {
+                Statement::Binding(binding) if binding.name == name => {
+                    match binding.value.as_ref().map(|expr| &expr.kind) {
+                        Some(ExpressionKind::Literal(lit)) => lit.as_integer(),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn test_comptime_calls() {
+        // Functions may be called before their definition
+        let code = r#"
+            comptime const POLY: u32 = 7;
+            comptime const CRC = crc8(49);
+            comptime const FACT = factorial(10);
+            comptime const SEARCH = find_pair(5);
+
+            fn crc8(byte: u32) -> u32 {
+                var crc: u32 = byte;
+                for i in 0..8 {
+                    if (crc & 128) != 0 {
+                        crc = ((crc << 1) ^ POLY) & 255;
+                    } else {
+                        crc = (crc << 1) & 255;
+                    }
+                }
+                return crc;
+            }
+
+            fn factorial(n: u64) -> u64 {
+                if n <= 1 {
+                    return 1;
+                }
+                return n * factorial(n - 1);
+            }
+
+            fn find_pair(target: u32) -> u32 {
+                var found: u32;
+                found = 0;
+                outer: for a in 0..10 {
+                    var b: u32 = 0;
+                    while true {
+                        b = b + 1;
+                        if b > a {
+                            continue outer;
+                        }
+                        if a + b == target {
+                            found = a * 10 + b;
+                            break outer;
+                        }
+                    }
+                }
+                return found;
+            }
+
+            fn main(count: u32) {
+                comptime const LOCAL = factorial(3);
+            }
+        "#;
+        let program = fold(code).unwrap();
+        assert_eq!(folded(&program, "CRC"), Some(151));
+        assert_eq!(folded(&program, "FACT"), Some(3628800));
+        assert_eq!(folded(&program, "SEARCH"), Some(32));
+    }
+
+    #[test]
+    fn test_comptime_call_errors() {
+        let err = |code: &str| fold(code).unwrap_err();
+
+        assert_eq!(
+            err("extern fn HAL_GetTick() -> u32; comptime const T = HAL_GetTick();"),
+            VmError::ExternCall {
+                name: "HAL_GetTick".to_string()
+            }
+        );
+        // Runtime globals and the caller's locals are out of reach
+        assert_eq!(
+            err("var ticks: u32 = 0; fn now() -> u32 { return ticks; } comptime const T = now();"),
+            VmError::NotComptime {
+                name: "ticks".to_string()
+            }
+        );
+        assert_eq!(
+            err("fn f() -> u32 { return n; } fn g(n: u32) { comptime const T = f(); }"),
+            VmError::UndefinedIdentifier {
+                name: "n".to_string()
+            }
+        );
+        assert_eq!(
+            err("comptime const N = 1; fn f() -> u32 { N = 2; return N; } comptime const T = f();"),
+            VmError::GlobalAssignment {
+                name: "N".to_string()
+            }
+        );
+        assert_eq!(
+            err("fn f() -> u32 { var x: u32; return x; } comptime const T = f();"),
+            VmError::Uninitialized {
+                name: "x".to_string()
+            }
+        );
+        assert_eq!(
+            err("fn f() {} comptime const T = f();"),
+            VmError::NoReturnValue {
+                function: "f".to_string()
+            }
+        );
+        assert_eq!(
+            err("fn f(a: u32) -> u32 { return a; } comptime const T = f(1, 2);"),
+            VmError::ArgumentCount {
+                function: "f".to_string(),
+                expected: 1,
+                found: 2,
+            }
+        );
+
+        // Runaway evaluation is cut off by the budget
+        assert_eq!(
+            err("fn spin() -> u32 { while true {} return 0; } comptime const T = spin();"),
+            VmError::StepLimit { limit: MAX_STEPS }
+        );
+        assert_eq!(
+            err("fn down(n: u32) -> u32 { return down(n + 1); } comptime const T = down(0);"),
+            VmError::CallDepthLimit {
+                function: "down".to_string(),
+                limit: MAX_CALL_DEPTH,
+            }
+        );
+    }
+
+    #[test]
+    fn test_comptime_aggregates() {
+        let code = r#"
+            struct Config { baud: u32, taps: [3]u8 }
+            fn squares() -> Config {
+                var config: Config;
+                for i in 0..3 {
+                    config.taps[i] = i * i;
+                }
+                config.baud = 9600;
+                return config;
+            }
+            comptime const CONFIG: Config = squares();
+            comptime const LAST = CONFIG.taps[2] + 1;
+            comptime const TAPS: [3]u8 = squares().taps;
+        "#;
+        let program = fold(code).unwrap();
+        assert_eq!(folded(&program, "LAST"), Some(5));
+        let Statement::Binding(binding) = &program.statements[2] else {
+            panic!("Expected binding");
+        };
+        let Some(ExpressionKind::StructLiteral { name, fields }) =
+            binding.value.as_ref().map(|expr| &expr.kind)
+        else {
+            panic!("Expected a struct literal, got {:?}", binding.value);
+        };
+        assert_eq!(name, "Config");
+        assert_eq!(fields[0].name, "baud");
+        assert!(matches!(
+            &fields[1].value.kind,
+            ExpressionKind::ArrayLiteral(taps) if taps.len() == 3
+        ));
+
+        let err = |code: &str| fold(code).unwrap_err();
+        assert_eq!(
+            err("comptime const T: [2]u8 = [1, 2]; comptime const X = T[2];"),
+            VmError::IndexOutOfBounds { index: 2, len: 2 }
+        );
+        assert_eq!(
+            err("struct P { x: u8 } comptime const V = P { x: 1 }.y;"),
+            VmError::UnknownField {
+                target: "P".to_string(),
+                field: "y".to_string(),
+            }
+        );
+        assert_eq!(
+            err("struct P { x: u8 } comptime const V = P { x: 256 };"),
+            VmError::IntegerOutOfRange {
+                value: 256,
+                ty: "u8"
+            }
+        );
+    }
+
+    #[test]
+    fn test_fixed_width_calls() {
+        let code = r#"
+            fn checksum(len: u8) -> u8 {
+                var sum: u8 = 0;
+                for i in 0..len {
+                    sum = sum + i * 7;
+                }
+                return sum;
+            }
+            comptime const SUM = checksum(20);
+        "#;
+        let program = build_ast(code).unwrap();
+        // Locals, parameters and the loop variable all behave as u8
+        let wrapped = fold_program_with(&program, Overflow::Wrap).unwrap();
+        assert_eq!(folded(&wrapped, "SUM"), Some(50));
+        assert_eq!(
+            fold_program(&program).unwrap_err().error,
+            VmError::IntegerOverflow { ty: "u8" }
+        );
+
+        assert_eq!(
+            fold("fn mix(a: u8, b: u16) -> u16 { return a + b; } comptime const M = mix(1, 2);")
+                .unwrap_err(),
+            VmError::InvalidBinaryOperands {
+                op: "+",
+                left: "u8",
+                right: "u16",
+            }
+        );
+    }
+}
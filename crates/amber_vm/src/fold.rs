@@ -0,0 +1,1309 @@
+use amber_ast::{
+    Block, ElseBranch, EnumDef, EnumVariant, Expression, ExpressionKind, ForIterable, ForLoop,
+    Function, IfElse, Literal, Match, MatchArm, Modifier, NumericLiteral, Param, Pattern,
+    PatternKind, PayloadPattern, Program, Span, Statement, StructDef, StructField, Type, UnionDef,
+    UnionVariant, VariableBinding, VariantPayload, WhileLoop,
+};
+
+use crate::error::{FoldError, VmError};
+use crate::eval::Evaluator;
+use crate::substitute::{map_children, substitute_block};
+use crate::trace::{TraceEntry, dependency_chain};
+use crate::value::{Overflow, Value};
+
+/// Most iterations a `comptime for` may unroll into
+pub const MAX_UNROLL: i64 = 64;
+
+/// Evaluate every `comptime` binding in the program and replace its
+/// initializer with the resulting literal. Array lengths and enum
+/// discriminants written as expressions are evaluated as well, and may call
+/// the program's functions.
+pub fn fold_program(program: &Program) -> Result<Program, FoldError> {
+    fold_program_with(program, Overflow::default())
+}
+
+/// Fold `program` like `fold_program`, with `overflow` deciding what integer
+/// arithmetic in comptime code does when a result leaves the range of its
+/// type. Folds of code not marked `comptime` never wrap; such arithmetic is
+/// left to the generated C.
+pub fn fold_program_with(program: &Program, overflow: Overflow) -> Result<Program, FoldError> {
+    fold_program_traced(program, overflow).map(|(program, _)| program)
+}
+
+/// Fold `program` like `fold_program_with`, also returning what was computed
+/// for each binding that was folded, in the order they were folded
+pub fn fold_program_traced(
+    program: &Program,
+    overflow: Overflow,
+) -> Result<(Program, Vec<TraceEntry>), FoldError> {
+    let mut folder = ComptimeFolder::new(program, overflow);
+    let statements = folder.fold_statements(&program.statements)?;
+    Ok((Program { statements }, folder.trace))
+}
+
+/// Resolve only what must be known before `program` can be type checked:
+/// `comptime if` and `comptime for` are expanded, and array lengths and enum
+/// discriminants are evaluated. Every other expression is kept as written,
+/// so the result can be checked before `fold_program_with` replaces parts of
+/// it with values.
+pub fn resolve_program(program: &Program, overflow: Overflow) -> Result<Program, FoldError> {
+    let mut folder = ComptimeFolder::new(program, overflow);
+    folder.resolve_only = true;
+    let statements = folder.fold_statements(&program.statements)?;
+    Ok(Program { statements })
+}
+
+#[derive(Default)]
+struct ComptimeFolder {
+    evaluator: Evaluator,
+    /// Evaluate bindings for later comptime code without replacing their
+    /// initializers, as `resolve_program` does
+    resolve_only: bool,
+    /// Function whose body is being folded, as named in the trace
+    function: Option<String>,
+    trace: Vec<TraceEntry>,
+}
+
+impl ComptimeFolder {
+    fn new(program: &Program, overflow: Overflow) -> Self {
+        let mut folder = ComptimeFolder {
+            evaluator: Evaluator::with_overflow(overflow),
+            ..ComptimeFolder::default()
+        };
+        // Functions and structs may be used at compile time before their definition
+        for statement in &program.statements {
+            match statement {
+                Statement::Function(func) => folder.evaluator.define_function(func),
+                Statement::Struct(def) => folder.evaluator.define_struct(def),
+                _ => {}
+            }
+        }
+        folder
+    }
+
+    fn fold_statements(&mut self, statements: &[Statement]) -> Result<Vec<Statement>, FoldError> {
+        let mut folded = Vec::with_capacity(statements.len());
+        for statement in statements {
+            match statement {
+                Statement::ForLoop(for_loop) if for_loop.is_comptime => {
+                    folded.extend(self.unroll(for_loop)?)
+                }
+                // The selected branch is spliced into the enclosing scope, so
+                // it can provide declarations to the code that follows it
+                Statement::IfElse(if_else) if if_else.modifier == Some(Modifier::Comptime) => {
+                    if let Some(block) = self.select_branch(if_else)? {
+                        folded.extend(self.fold_statements(&block.statements)?);
+                    }
+                }
+                _ => folded.push(self.fold_statement(statement)?),
+            }
+        }
+        Ok(folded)
+    }
+
+    fn fold_statement(&mut self, statement: &Statement) -> Result<Statement, FoldError> {
+        match statement {
+            Statement::Binding(binding) => self.fold_binding(binding).map(Statement::Binding),
+            Statement::Function(func) => {
+                self.function = Some(func.name.clone());
+                let func = self.fold_function(func);
+                self.function = None;
+                let func = func?;
+                self.evaluator.define_function(&func);
+                Ok(Statement::Function(func))
+            }
+            Statement::Impl(block) => {
+                let mut block = block.clone();
+                for method in &mut block.methods {
+                    self.function = Some(format!("{}::{}", block.target, method.name));
+                    let folded = self.fold_function(method);
+                    self.function = None;
+                    *method = folded?;
+                }
+                Ok(Statement::Impl(block))
+            }
+            Statement::IfElse(if_else) => self.fold_if_else(if_else).map(Statement::IfElse),
+            Statement::WhileLoop(while_loop) => Ok(Statement::WhileLoop(WhileLoop {
+                condition: self.fold_expr(&while_loop.condition),
+                block: self.fold_block(&while_loop.block)?,
+                ..while_loop.clone()
+            })),
+            Statement::ForLoop(for_loop) => {
+                let iterable = match &for_loop.iterable {
+                    ForIterable::Range { start, end } => ForIterable::Range {
+                        start: self.fold_expr(start),
+                        end: self.fold_expr(end),
+                    },
+                    ForIterable::Array(array) => ForIterable::Array(self.fold_expr(array)),
+                };
+                self.evaluator.push_scope();
+                self.evaluator.declare_runtime(&for_loop.variable);
+                let block = self.fold_block(&for_loop.block);
+                self.evaluator.pop_scope();
+                Ok(Statement::ForLoop(ForLoop {
+                    iterable,
+                    block: block?,
+                    ..for_loop.clone()
+                }))
+            }
+            Statement::Block(block) => self.fold_block(block).map(Statement::Block),
+            Statement::Match(match_stmt) => {
+                let arms = match_stmt
+                    .arms
+                    .iter()
+                    .map(|arm| {
+                        // Payload bindings are runtime values inside the arm
+                        self.evaluator.push_scope();
+                        for name in arm.patterns.iter().flat_map(pattern_bindings) {
+                            self.evaluator.declare_runtime(name);
+                        }
+                        let body = self.fold_block(&arm.body);
+                        self.evaluator.pop_scope();
+                        Ok(MatchArm {
+                            body: body?,
+                            ..arm.clone()
+                        })
+                    })
+                    .collect::<Result<_, FoldError>>()?;
+                Ok(Statement::Match(Match {
+                    scrutinee: self.fold_expr(&match_stmt.scrutinee),
+                    arms,
+                    ..match_stmt.clone()
+                }))
+            }
+            Statement::Enum(def) => self.fold_enum(def).map(Statement::Enum),
+            Statement::Union(def) => self.fold_union(def).map(Statement::Union),
+            Statement::Struct(def) => {
+                let def = StructDef {
+                    fields: self.fold_fields(&def.fields)?,
+                    ..def.clone()
+                };
+                self.evaluator.define_struct(&def);
+                Ok(Statement::Struct(def))
+            }
+            Statement::Assignment {
+                target,
+                value,
+                span,
+            } => Ok(Statement::Assignment {
+                target: target.clone(),
+                value: self.fold_expr(value),
+                span: *span,
+            }),
+            Statement::Return { value, span } => Ok(Statement::Return {
+                value: value.as_ref().map(|value| self.fold_expr(value)),
+                span: *span,
+            }),
+            // A call kept for its side effects stays a call
+            Statement::ExprStatement(_) | Statement::Break { .. } | Statement::Continue { .. } => {
+                Ok(statement.clone())
+            }
+        }
+    }
+
+    fn fold_if_else(&mut self, if_else: &IfElse) -> Result<IfElse, FoldError> {
+        let condition = self.fold_expr(&if_else.condition);
+        let then_block = self.fold_block(&if_else.then_block)?;
+        let else_branch = match &if_else.else_branch {
+            // `else comptime if` resolves to the selected block, if any
+            Some(ElseBranch::If(next)) if next.modifier == Some(Modifier::Comptime) => {
+                match self.select_branch(next)? {
+                    Some(block) => Some(ElseBranch::Block(self.fold_block(block)?)),
+                    None => None,
+                }
+            }
+            Some(ElseBranch::If(next)) => Some(ElseBranch::If(Box::new(self.fold_if_else(next)?))),
+            Some(ElseBranch::Block(block)) => Some(ElseBranch::Block(self.fold_block(block)?)),
+            None => None,
+        };
+        Ok(IfElse {
+            condition,
+            then_block,
+            else_branch,
+            ..if_else.clone()
+        })
+    }
+
+    /// Evaluate the conditions of a `comptime if` chain in order and return
+    /// the block of the first one that holds, or the final `else` block.
+    /// Branches that are not selected are never folded.
+    fn select_branch<'a>(&mut self, if_else: &'a IfElse) -> Result<Option<&'a Block>, FoldError> {
+        let condition = &if_else.condition;
+        match self.eval_as(condition, None)? {
+            Value::Bool(true) => Ok(Some(&if_else.then_block)),
+            Value::Bool(false) => match &if_else.else_branch {
+                Some(ElseBranch::If(next)) => self.select_branch(next),
+                Some(ElseBranch::Block(block)) => Ok(Some(block)),
+                None => Ok(None),
+            },
+            other => Err(VmError::NonBoolCondition {
+                found: other.type_name(),
+            }
+            .at(condition.span)),
+        }
+    }
+
+    fn fold_binding(&mut self, binding: &VariableBinding) -> Result<VariableBinding, FoldError> {
+        let binding = &VariableBinding {
+            ty: binding
+                .ty
+                .as_ref()
+                .map(|ty| self.fold_type(ty))
+                .transpose()?,
+            ..binding.clone()
+        };
+        match binding.modifier {
+            Some(Modifier::Comptime) => {}
+            // `runtime` keeps the initializer exactly as written
+            Some(Modifier::Runtime) => {
+                self.evaluator.declare_runtime(&binding.name);
+                return Ok(binding.clone());
+            }
+            None => return Ok(self.fold_implicit_binding(binding)),
+        }
+
+        let expr = binding.value.as_ref().ok_or_else(|| {
+            VmError::MissingInitializer {
+                name: binding.name.clone(),
+            }
+            .at(binding.span)
+        })?;
+        self.evaluator.reads.clear();
+        let value = self.eval_as(expr, binding.ty.as_ref())?;
+        self.evaluator.define(&binding.name, value.clone());
+        if self.resolve_only {
+            return Ok(binding.clone());
+        }
+        self.record(binding, expr, &value);
+
+        Ok(VariableBinding {
+            value: Some(initializer(binding, &value, expr.span)),
+            ..binding.clone()
+        })
+    }
+
+    /// Fold the initializer of a binding without a modifier as far as it is
+    /// comptime-known. An immutable binding whose computed initializer folds
+    /// completely is frozen: it becomes a comptime binding, so later comptime
+    /// code may use it and code generation reports it.
+    fn fold_implicit_binding(&mut self, binding: &VariableBinding) -> VariableBinding {
+        let Some(expr) = &binding.value else {
+            self.evaluator.declare_runtime(&binding.name);
+            return binding.clone();
+        };
+        let computed = !binding.is_mutable
+            && !matches!(
+                expr.kind,
+                ExpressionKind::Literal(_)
+                    | ExpressionKind::Identifier(_)
+                    | ExpressionKind::Path { .. }
+            );
+        self.evaluator.reads.clear();
+        if computed && let Some(value) = self.eval_implicit(expr, binding.ty.as_ref()) {
+            self.evaluator.define(&binding.name, value.clone());
+            // An array or struct literal written in the source is already as
+            // simple as it gets
+            if self.resolve_only || is_literal_value(expr) {
+                return binding.clone();
+            }
+            self.record(binding, expr, &value);
+            return VariableBinding {
+                modifier: Some(Modifier::Comptime),
+                value: Some(initializer(binding, &value, expr.span)),
+                ..binding.clone()
+            };
+        }
+
+        let folded = if computed {
+            map_children(expr, &mut |child| self.fold_expr(child))
+        } else {
+            self.fold_expr_as(expr, binding.ty.as_ref())
+        };
+        let value = match &folded.kind {
+            ExpressionKind::Literal(literal) if !binding.is_mutable => {
+                let value = Value::from_literal(literal);
+                match &binding.ty {
+                    // A literal out of range is left for analysis to report
+                    Some(ty) => value.coerce_to(ty, Overflow::Error).ok(),
+                    None => Some(value),
+                }
+            }
+            _ => None,
+        };
+        match value {
+            Some(value) => self.evaluator.define(&binding.name, value),
+            None => self.evaluator.declare_runtime(&binding.name),
+        }
+        VariableBinding {
+            value: Some(folded),
+            ..binding.clone()
+        }
+    }
+
+    /// Replace the largest comptime-known parts of `expr` with their values.
+    /// Names and literals are kept as written, and anything that cannot be
+    /// evaluated, such as a side effect, a runtime name or an invalid value
+    /// like `1 / 0`, is left in place for the generated C to compute.
+    /// Arrays and structs are only ever folded whole into a binding.
+    fn fold_expr(&mut self, expr: &Expression) -> Expression {
+        self.fold_expr_as(expr, None)
+    }
+
+    /// Like `fold_expr`, but the whole of `expr` is evaluated as a value of
+    /// type `ty` when one is given
+    fn fold_expr_as(&mut self, expr: &Expression, ty: Option<&Type>) -> Expression {
+        if self.resolve_only
+            || matches!(
+                expr.kind,
+                ExpressionKind::Literal(_)
+                    | ExpressionKind::Identifier(_)
+                    | ExpressionKind::Path { .. }
+            )
+        {
+            return expr.clone();
+        }
+        match self
+            .eval_implicit(expr, ty)
+            .and_then(|value| value.to_literal())
+        {
+            Some(literal) => Expression::new(ExpressionKind::Literal(literal), expr.span),
+            None => map_children(expr, &mut |child| self.fold_expr(child)),
+        }
+    }
+
+    /// Evaluate `expr` for a fold the source did not ask for. Arithmetic
+    /// that leaves its type's range fails here even when comptime code
+    /// wraps, so any failure keeps the expression as written.
+    fn eval_implicit(&mut self, expr: &Expression, ty: Option<&Type>) -> Option<Value> {
+        let overflow = std::mem::replace(&mut self.evaluator.overflow, Overflow::Error);
+        let value = self.eval_as(expr, ty).ok();
+        self.evaluator.overflow = overflow;
+        value
+    }
+
+    /// Add the binding folded into `value` to the trace, with the bindings
+    /// read while evaluating its initializer `expr`
+    fn record(&mut self, binding: &VariableBinding, expr: &Expression, value: &Value) {
+        let reads = std::mem::take(&mut self.evaluator.reads);
+        let depends_on = dependency_chain(&self.trace, self.function.as_deref(), reads);
+        self.trace.push(TraceEntry {
+            name: binding.name.clone(),
+            scope: self.function.clone(),
+            span: expr.span,
+            value: value.clone(),
+            ty: binding.ty.clone().or_else(|| value.ty()),
+            depends_on,
+        });
+    }
+
+    fn eval_as(&mut self, expr: &Expression, ty: Option<&Type>) -> Result<Value, FoldError> {
+        match ty {
+            Some(ty) => self.evaluator.eval_expr_as(expr, ty),
+            None => self.evaluator.eval_expr(expr),
+        }
+        .map_err(|err| err.at(expr.span))
+    }
+
+    fn fold_function(&mut self, func: &Function) -> Result<Function, FoldError> {
+        let params = func
+            .params
+            .iter()
+            .map(|param| match param {
+                Param::SelfParam { is_mut } => Ok(Param::SelfParam { is_mut: *is_mut }),
+                Param::Typed { name, ty } => Ok(Param::Typed {
+                    name: name.clone(),
+                    ty: self.fold_type(ty)?,
+                }),
+            })
+            .collect::<Result<_, FoldError>>()?;
+        let return_type = func
+            .return_type
+            .as_ref()
+            .map(|ty| self.fold_type(ty))
+            .transpose()?;
+        let func = &Function {
+            params,
+            return_type,
+            ..func.clone()
+        };
+        let Some(body) = &func.body else {
+            return Ok(func.clone());
+        };
+
+        self.evaluator.push_scope();
+        for param in &func.params {
+            match param {
+                Param::SelfParam { .. } => self.evaluator.declare_runtime("self"),
+                Param::Typed { name, .. } => self.evaluator.declare_runtime(name),
+            }
+        }
+        let body = self.fold_block(body);
+        self.evaluator.pop_scope();
+
+        Ok(Function {
+            body: Some(body?),
+            ..func.clone()
+        })
+    }
+
+    fn fold_enum(&mut self, def: &EnumDef) -> Result<EnumDef, FoldError> {
+        let variants = def
+            .variants
+            .iter()
+            .map(|variant| {
+                let Some(expr) = &variant.value else {
+                    return Ok(variant.clone());
+                };
+                let value = self.eval_as(expr, None)?;
+                if value.as_int().is_none() {
+                    return Err(VmError::InvalidDiscriminant {
+                        variant: format!("{}::{}", def.name, variant.name),
+                        found: value.type_name(),
+                    }
+                    .at(expr.span));
+                }
+                Ok(EnumVariant {
+                    value: Some(value.to_expr(expr.span)),
+                    ..variant.clone()
+                })
+            })
+            .collect::<Result<_, FoldError>>()?;
+        Ok(EnumDef {
+            variants,
+            ..def.clone()
+        })
+    }
+
+    fn fold_union(&mut self, def: &UnionDef) -> Result<UnionDef, FoldError> {
+        let variants = def
+            .variants
+            .iter()
+            .map(|variant| {
+                let payload = match &variant.payload {
+                    VariantPayload::Unit => VariantPayload::Unit,
+                    VariantPayload::Tuple(types) => VariantPayload::Tuple(
+                        types
+                            .iter()
+                            .map(|ty| self.fold_type(ty))
+                            .collect::<Result<_, _>>()?,
+                    ),
+                    VariantPayload::Struct(fields) => {
+                        VariantPayload::Struct(self.fold_fields(fields)?)
+                    }
+                };
+                Ok(UnionVariant {
+                    payload,
+                    ..variant.clone()
+                })
+            })
+            .collect::<Result<_, FoldError>>()?;
+        Ok(UnionDef {
+            variants,
+            ..def.clone()
+        })
+    }
+
+    fn fold_fields(&mut self, fields: &[StructField]) -> Result<Vec<StructField>, FoldError> {
+        fields
+            .iter()
+            .map(|field| {
+                Ok(StructField {
+                    ty: self.fold_type(&field.ty)?,
+                    ..field.clone()
+                })
+            })
+            .collect()
+    }
+
+    /// Replace every `ComptimeArray` in `ty` with an `Array` of evaluated length
+    fn fold_type(&mut self, ty: &Type) -> Result<Type, FoldError> {
+        match ty {
+            Type::Pointer { inner, is_mut } => Ok(Type::Pointer {
+                inner: Box::new(self.fold_type(inner)?),
+                is_mut: *is_mut,
+            }),
+            Type::Array { inner, len } => Ok(Type::Array {
+                inner: Box::new(self.fold_type(inner)?),
+                len: *len,
+            }),
+            Type::ComptimeArray { inner, len } => {
+                let value = self.eval_as(len, None)?;
+                let size = value
+                    .as_int()
+                    .and_then(|len| usize::try_from(len).ok())
+                    .ok_or_else(|| {
+                        VmError::InvalidArrayLength {
+                            found: value.to_string(),
+                        }
+                        .at(len.span)
+                    })?;
+                Ok(Type::Array {
+                    inner: Box::new(self.fold_type(inner)?),
+                    len: size,
+                })
+            }
+            _ => Ok(ty.clone()),
+        }
+    }
+
+    /// Expand `comptime for i in start..end` into one block per iteration,
+    /// with `i` replaced by that iteration's value
+    fn unroll(&mut self, for_loop: &ForLoop) -> Result<Vec<Statement>, FoldError> {
+        let ForIterable::Range { start, end } = &for_loop.iterable else {
+            return Err(VmError::NotEvaluable { what: "array loop" }.at(for_loop.span));
+        };
+        // Once unrolled there is no loop left to jump out of
+        if let Some(jump) = loop_exit(&for_loop.block, for_loop.label.as_deref(), false) {
+            return Err(VmError::UnrolledLoopExit {
+                keyword: match jump {
+                    Statement::Break { .. } => "break",
+                    _ => "continue",
+                },
+            }
+            .at(jump.span()));
+        }
+        let start = self.range_bound(start)?;
+        let end = self.range_bound(end)?;
+        let count = end.saturating_sub(start).max(0);
+        if count > i128::from(MAX_UNROLL) {
+            return Err(VmError::UnrollLimit {
+                count,
+                limit: MAX_UNROLL,
+            }
+            .at(for_loop.span));
+        }
+
+        (start..end)
+            .map(|i| {
+                let value = Literal::Numeric(NumericLiteral::Integer(i));
+                let block = substitute_block(&for_loop.block, &for_loop.variable, &value);
+                self.fold_block(&block).map(Statement::Block)
+            })
+            .collect()
+    }
+
+    fn range_bound(&mut self, expr: &Expression) -> Result<i128, FoldError> {
+        let value = self.eval_as(expr, None)?;
+        value.as_int().ok_or_else(|| {
+            VmError::InvalidRangeBound {
+                found: value.type_name(),
+            }
+            .at(expr.span)
+        })
+    }
+
+    fn fold_block(&mut self, block: &Block) -> Result<Block, FoldError> {
+        self.evaluator.push_scope();
+        let statements = self.fold_statements(&block.statements);
+        self.evaluator.pop_scope();
+        Ok(Block {
+            statements: statements?,
+            span: block.span,
+        })
+    }
+}
+
+/// Initializer of `binding` once it is folded into `value`. Without a
+/// declared type, an integer keeps the type it was computed with.
+fn initializer(binding: &VariableBinding, value: &Value, span: Span) -> Expression {
+    match binding.ty {
+        Some(_) => value.to_expr(span),
+        None => value.to_typed_expr(span),
+    }
+}
+
+/// Whether `expr` is a literal, or an array or struct literal built only
+/// from literals
+fn is_literal_value(expr: &Expression) -> bool {
+    match &expr.kind {
+        ExpressionKind::Literal(_) => true,
+        ExpressionKind::ArrayLiteral(elements) => elements.iter().all(is_literal_value),
+        ExpressionKind::StructLiteral { fields, .. } => {
+            fields.iter().all(|field| is_literal_value(&field.value))
+        }
+        _ => false,
+    }
+}
+
+/// First `break` or `continue` in `block` that targets the enclosing loop,
+/// named `label` if it has one. Unlabeled jumps inside nested loops target
+/// those loops instead.
+fn loop_exit<'a>(block: &'a Block, label: Option<&str>, nested: bool) -> Option<&'a Statement> {
+    block
+        .statements
+        .iter()
+        .find_map(|statement| match statement {
+            Statement::Break { label: target, .. } | Statement::Continue { label: target, .. } => {
+                let exits = match target {
+                    Some(target) => label == Some(target.as_str()),
+                    None => !nested,
+                };
+                exits.then_some(statement)
+            }
+            Statement::WhileLoop(inner) => loop_exit(&inner.block, label, true),
+            Statement::ForLoop(inner) => loop_exit(&inner.block, label, true),
+            Statement::IfElse(if_else) => if_else_exit(if_else, label, nested),
+            Statement::Match(match_stmt) => match_stmt
+                .arms
+                .iter()
+                .find_map(|arm| loop_exit(&arm.body, label, nested)),
+            Statement::Block(inner) => loop_exit(inner, label, nested),
+            _ => None,
+        })
+}
+
+fn if_else_exit<'a>(
+    if_else: &'a IfElse,
+    label: Option<&str>,
+    nested: bool,
+) -> Option<&'a Statement> {
+    loop_exit(&if_else.then_block, label, nested).or_else(|| match &if_else.else_branch {
+        Some(ElseBranch::If(next)) => if_else_exit(next, label, nested),
+        Some(ElseBranch::Block(block)) => loop_exit(block, label, nested),
+        None => None,
+    })
+}
+
+/// Names a pattern binds from a union payload
+pub(crate) fn pattern_bindings(pattern: &Pattern) -> Vec<&str> {
+    match &pattern.kind {
+        PatternKind::Destructure {
+            payload: PayloadPattern::Tuple(names),
+            ..
+        } => names.iter().flatten().map(String::as_str).collect(),
+        PatternKind::Destructure {
+            payload: PayloadPattern::Struct(fields),
+            ..
+        } => fields
+            .iter()
+            .filter_map(|field| field.binding.as_deref())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amber_ast::{Literal, NumericLiteral, Postfix, UnaryOp};
+    use amber_parser::build_ast;
+
+    fn folded_value(program: &Program, index: usize) -> Option<ExpressionKind> {
+        match &program.statements[index] {
+            Statement::Binding(binding) => binding.value.as_ref().map(|expr| expr.kind.clone()),
+            other => panic!("Expected binding, got {:?}", other),
+        }
+    }
+
+    fn int(i: i128) -> Option<ExpressionKind> {
+        Some(ExpressionKind::Literal(Literal::Numeric(
+            NumericLiteral::Integer(i),
+        )))
+    }
+
+    #[test]
+    fn test_fold_comptime_bindings() {
+        let code = r#"
+            comptime const CLOCK: u32 = 16000000;
+            comptime const BAUD: u32 = CLOCK / 9600;
+            runtime const runtime_value: u32 = CLOCK / 2;
+        "#;
+        let program = fold_program(&build_ast(code).unwrap()).unwrap();
+
+        assert_eq!(folded_value(&program, 0), int(16000000));
+        assert_eq!(folded_value(&program, 1), int(1666));
+        // `runtime` bindings are left untouched
+        assert!(matches!(
+            folded_value(&program, 2),
+            Some(ExpressionKind::BinaryExpr { .. })
+        ));
+    }
+
+    #[test]
+    fn test_implicit_folding() {
+        let code = r#"
+            const CLOCK: u32 = 16000000;
+            const BAUD: u32 = CLOCK / 9600;
+            var counter: u32 = 2 * 8;
+            runtime const DIVISOR: u32 = 4;
+            fn main(x: u32, p: *u32) {
+                const scaled = x + BAUD * 2;
+                const doubled = 2 * 21;
+                const read = *p + (1 + 1);
+                var total = counter + doubled;
+                total = doubled * 2;
+                if doubled > 40 && x > 0 {}
+                comptime const FROM_DOUBLED = doubled + 1;
+            }
+        "#;
+        let program = fold_program(&build_ast(code).unwrap()).unwrap();
+        let modifier = |statement: &Statement| match statement {
+            Statement::Binding(binding) => binding.modifier.clone(),
+            other => panic!("Expected binding, got {:?}", other),
+        };
+
+        // A literal is kept, while a computed immutable binding is frozen
+        assert_eq!(modifier(&program.statements[0]), None);
+        assert_eq!(folded_value(&program, 1), int(1666));
+        assert_eq!(modifier(&program.statements[1]), Some(Modifier::Comptime));
+        // A mutable binding only has its initializer folded
+        assert_eq!(folded_value(&program, 2), int(16));
+        assert_eq!(modifier(&program.statements[2]), None);
+
+        let Statement::Function(func) = &program.statements[4] else {
+            panic!("Expected function");
+        };
+        let body = &func.body.as_ref().unwrap().statements;
+        let render = |statement: &Statement| match statement {
+            Statement::Binding(binding) => binding.value.clone().unwrap(),
+            Statement::Assignment { value, .. } => value.clone(),
+            Statement::IfElse(if_else) => if_else.condition.clone(),
+            other => panic!("Unexpected statement {:?}", other),
+        };
+        let binary = |expr: Expression| match expr.kind {
+            ExpressionKind::BinaryExpr { left, right, .. } => (left.kind, right.kind),
+            other => panic!("Expected binary expression, got {:?}", other),
+        };
+        let literal = |i| ExpressionKind::Literal(Literal::Numeric(NumericLiteral::Integer(i)));
+
+        // Only the comptime-known part of a runtime expression is folded
+        let (left, right) = binary(render(&body[0]));
+        assert_eq!(left, ExpressionKind::Identifier("x".to_string()));
+        assert_eq!(right, literal(3332));
+        assert_eq!(render(&body[1]).kind, literal(42));
+        // Side effects and runtime state are never evaluated
+        let (left, right) = binary(render(&body[2]));
+        assert!(matches!(left, ExpressionKind::UnaryExpr { .. }));
+        assert_eq!(right, literal(2));
+        assert!(matches!(
+            binary(render(&body[3])).0,
+            ExpressionKind::Identifier(_)
+        ));
+        assert_eq!(render(&body[4]).kind, literal(84));
+        let (left, _) = binary(render(&body[5]));
+        assert_eq!(left, ExpressionKind::Literal(Literal::Bool(true)));
+        // Frozen bindings can feed explicit comptime code
+        assert_eq!(render(&body[6]).kind, literal(43));
+
+        let code = "runtime const N: u32 = 4; comptime const M = N * 2;";
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(
+            err,
+            VmError::NotComptime {
+                name: "N".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_fold_inside_function_scope() {
+        let code = r#"
+            comptime const BASE = 10;
+            fn main() {
+                comptime const SCALED = BASE * 4;
+            }
+        "#;
+        let program = fold_program(&build_ast(code).unwrap()).unwrap();
+
+        let Statement::Function(func) = &program.statements[1] else {
+            panic!("Expected function");
+        };
+        let Statement::Binding(binding) = &func.body.as_ref().unwrap().statements[0] else {
+            panic!("Expected binding");
+        };
+        assert_eq!(
+            binding.value.as_ref().map(|expr| expr.kind.clone()),
+            int(40)
+        );
+    }
+
+    #[test]
+    fn test_runtime_names_are_rejected() {
+        let code = r#"
+            fn main(count: u32) {
+                comptime const DOUBLE = count * 2;
+            }
+        "#;
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(
+            err,
+            VmError::NotComptime {
+                name: "count".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_implicit_folding_leaves_invalid_values_to_runtime() {
+        let code = r#"
+            const A: u8 = 200;
+            const B: u8 = 100;
+            const D: u32 = 10 / 0;
+            const SH: u32 = 1 << 40;
+            fn avg(a: u8, b: u8) -> u8 {
+                return (a + b) / 2;
+            }
+            fn main() -> u8 {
+                var x: u8 = 0;
+                x = A + B;
+                return avg(200, 100);
+            }
+        "#;
+        let program = build_ast(code).unwrap();
+        // Arithmetic only wraps in code marked `comptime`
+        for overflow in [Overflow::Error, Overflow::Wrap] {
+            let folded = fold_program_with(&program, overflow).unwrap();
+            assert!(matches!(
+                folded_value(&folded, 2),
+                Some(ExpressionKind::BinaryExpr { .. })
+            ));
+            assert!(matches!(
+                folded_value(&folded, 3),
+                Some(ExpressionKind::BinaryExpr { .. })
+            ));
+            let Statement::Function(main) = &folded.statements[5] else {
+                panic!("Expected function");
+            };
+            let body = &main.body.as_ref().unwrap().statements;
+            assert!(matches!(
+                &body[1],
+                Statement::Assignment { value, .. }
+                    if matches!(value.kind, ExpressionKind::BinaryExpr { .. })
+            ));
+            assert!(matches!(
+                &body[2],
+                Statement::Return { value: Some(value), .. }
+                    if matches!(value.kind, ExpressionKind::Call { .. })
+            ));
+        }
+
+        // The same values are errors in comptime code
+        let fold = |code: &str| fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(
+            fold("comptime const D: u32 = 10 / 0;"),
+            VmError::DivisionByZero
+        );
+        assert_eq!(
+            fold("comptime const O: u8 = 200 + 100;"),
+            VmError::IntegerOverflow { ty: "u8" }
+        );
+    }
+
+    #[test]
+    fn test_typed_values_keep_their_type() {
+        let fold = |code: &str| fold_program(&build_ast(code).unwrap()).unwrap_err();
+        let mismatch = |expected: &str, found| VmError::TypeMismatch {
+            expected: expected.to_string(),
+            found,
+        };
+
+        assert_eq!(
+            fold("comptime const A: u8 = 200u16;").error,
+            mismatch("u8", "u16")
+        );
+        assert_eq!(
+            fold("comptime const G: f32 = 7u64;").error,
+            mismatch("f32", "u64")
+        );
+        // The error points at the initializer
+        let err = fold("comptime const X: u16 = 5;\ncomptime const Y: u8 = X;");
+        assert_eq!(err.error, mismatch("u8", "u16"));
+        assert_eq!((err.span.offset, err.span.len), (50, 1));
+
+        // Untyped integers still take the declared type
+        let code = "comptime const B: u8 = 200; comptime const F: f64 = 7;";
+        assert!(fold_program(&build_ast(code).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_fold_array_lengths() {
+        let code = r#"
+            comptime const N = 4;
+            var buf: [N * 2]u8;
+            struct Frame { data: [N]*u8 }
+            fn fill(dst: *mut [N + 1]u8) {}
+        "#;
+        let program = fold_program(&build_ast(code).unwrap()).unwrap();
+
+        let Statement::Binding(binding) = &program.statements[1] else {
+            panic!("Expected binding");
+        };
+        assert_eq!(binding.ty.as_ref().unwrap().to_string(), "[8]u8");
+        let Statement::Struct(def) = &program.statements[2] else {
+            panic!("Expected struct");
+        };
+        assert_eq!(def.fields[0].ty.to_string(), "[4]*u8");
+        let Statement::Function(func) = &program.statements[3] else {
+            panic!("Expected function");
+        };
+        let Param::Typed { ty, .. } = &func.params[0] else {
+            panic!("Expected typed param");
+        };
+        assert_eq!(ty.to_string(), "*mut [5]u8");
+
+        let code = "comptime const N = -1; var buf: [N]u8;";
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(
+            err,
+            VmError::InvalidArrayLength {
+                found: "-1".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_fold_enum_discriminants() {
+        let code = r#"
+            comptime const BASE = 4;
+            enum Flags: u8 {
+                A = 1 << 2,
+                B = BASE * 2,
+                C,
+            }
+        "#;
+        let program = fold_program(&build_ast(code).unwrap()).unwrap();
+        let Statement::Enum(def) = &program.statements[1] else {
+            panic!("Expected enum");
+        };
+        let values: Vec<_> = def
+            .variants
+            .iter()
+            .map(|variant| variant.value.as_ref().map(|expr| expr.kind.clone()))
+            .collect();
+        assert_eq!(values, [int(4), int(8), None]);
+
+        let code = "enum Flags { A = true }";
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(
+            err,
+            VmError::InvalidDiscriminant {
+                variant: "Flags::A".to_string(),
+                found: "bool",
+            }
+        );
+    }
+
+    #[test]
+    fn test_fold_union_payloads() {
+        let code = r#"
+            comptime const N = 2;
+            union Message { Raw([N]u8), Pair { values: [N * 2]u8 } }
+        "#;
+        let program = fold_program(&build_ast(code).unwrap()).unwrap();
+        let Statement::Union(def) = &program.statements[1] else {
+            panic!("Expected union");
+        };
+        let array = |len| Type::Array {
+            inner: Box::new(Type::U8),
+            len,
+        };
+        assert_eq!(
+            def.variants[0].payload,
+            VariantPayload::Tuple(vec![array(2)])
+        );
+        let VariantPayload::Struct(fields) = &def.variants[1].payload else {
+            panic!("Expected struct payload");
+        };
+        assert_eq!(fields[0].ty, array(4));
+
+        // A payload binding shadows the comptime binding inside its arm
+        let code = r#"
+            comptime const N = 2;
+            fn f(message: Message) {
+                match message {
+                    Message::Raw(N) => {
+                        comptime const M = N;
+                    }
+                }
+            }
+        "#;
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(
+            err,
+            VmError::NotComptime {
+                name: "N".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_unroll_comptime_for() {
+        let code = r#"
+            comptime const N = 3;
+            fn fill(values: *mut [N]u8, count: u32) {
+                comptime for i in 0..N {
+                    comptime const BIT = 1 << i;
+                    values[i] = BIT;
+                    const i = count;
+                    values[i] = 0;
+                }
+                for j in 0..count {
+                    const k = j;
+                }
+            }
+        "#;
+        let program = fold_program(&build_ast(code).unwrap()).unwrap();
+        let Statement::Function(func) = &program.statements[1] else {
+            panic!("Expected function");
+        };
+        let body = &func.body.as_ref().unwrap().statements;
+        assert_eq!(body.len(), 4);
+        for (i, statement) in body[..3].iter().enumerate() {
+            let Statement::Block(block) = statement else {
+                panic!("Expected unrolled block, got {:?}", statement);
+            };
+            let Statement::Binding(binding) = &block.statements[0] else {
+                panic!("Expected binding");
+            };
+            assert_eq!(
+                binding.value.as_ref().map(|expr| expr.kind.clone()),
+                int(1 << i)
+            );
+            let index = |statement: &Statement| match statement {
+                Statement::Assignment { target, .. } => match &target.kind {
+                    ExpressionKind::UnaryExpr {
+                        op: UnaryOp::PostfixOp(Postfix::Index { index }),
+                        ..
+                    } => Some(index.kind.clone()),
+                    _ => None,
+                },
+                _ => None,
+            };
+            assert_eq!(index(&block.statements[1]), int(i as i128));
+            // The shadowing binding hides the loop variable
+            assert_eq!(
+                index(&block.statements[3]),
+                Some(ExpressionKind::Identifier("i".to_string()))
+            );
+        }
+        assert!(matches!(&body[3], Statement::ForLoop(for_loop) if !for_loop.is_comptime));
+
+        let code = "fn f(n: u32) { comptime for i in 0..n {} }";
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(
+            err,
+            VmError::NotComptime {
+                name: "n".to_string(),
+            }
+        );
+
+        let code = "fn f() { comptime for i in 0..1000 {} }";
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(
+            err,
+            VmError::UnrollLimit {
+                count: 1000,
+                limit: MAX_UNROLL,
+            }
+        );
+
+        let code = "fn f() { comptime for i in 0..true {} }";
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(err, VmError::InvalidRangeBound { found: "bool" });
+
+        // Jumps may target loops inside or around the unrolled one
+        let code = r#"
+            fn f(flag: bool) {
+                outer: while flag {
+                    comptime for i in 0..2 {
+                        while flag {
+                            break;
+                        }
+                        continue outer;
+                    }
+                }
+            }
+        "#;
+        assert!(fold_program(&build_ast(code).unwrap()).is_ok());
+        let code = "fn f(flag: bool) { comptime for i in 0..2 { if flag { break; } } }";
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(err, VmError::UnrolledLoopExit { keyword: "break" });
+        let code = "fn f(flag: bool) { l: comptime for i in 0..2 { while flag { continue l; } } }";
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(
+            err,
+            VmError::UnrolledLoopExit {
+                keyword: "continue"
+            }
+        );
+    }
+
+    #[test]
+    fn test_comptime_if_selects_branch() {
+        let code = r#"
+            comptime const HAS_FPU = false;
+            comptime const HAS_DSP = true;
+            comptime if HAS_FPU {
+                comptime const SCALE = MISSING * 2;
+                fn scale() {}
+            } else if HAS_DSP {
+                comptime const SCALE = 4;
+                fn scale_dsp() {}
+            } else {
+                fn scale_soft() {}
+            }
+            fn main(flag: bool) {
+                comptime if SCALE > 2 {
+                    const level: u8 = SCALE;
+                }
+                comptime if HAS_FPU {
+                    const level: u8 = 0;
+                }
+                if flag {
+                } else comptime if HAS_FPU {
+                    const fpu: u8 = 1;
+                } else {
+                    const soft: u8 = 1;
+                }
+            }
+        "#;
+        let program = fold_program(&build_ast(code).unwrap()).unwrap();
+
+        // The selected branch replaces the whole chain at top level, and its
+        // comptime bindings stay visible to later declarations
+        assert_eq!(program.statements.len(), 5);
+        assert_eq!(folded_value(&program, 2), int(4));
+        assert!(
+            matches!(&program.statements[3], Statement::Function(func) if func.name == "scale_dsp")
+        );
+
+        let Statement::Function(func) = &program.statements[4] else {
+            panic!("Expected function");
+        };
+        let body = &func.body.as_ref().unwrap().statements;
+        assert_eq!(body.len(), 2);
+        assert!(matches!(&body[0], Statement::Binding(binding) if binding.name == "level"));
+        let Statement::IfElse(if_else) = &body[1] else {
+            panic!("Expected if statement");
+        };
+        let Some(ElseBranch::Block(block)) = &if_else.else_branch else {
+            panic!("Expected resolved else block");
+        };
+        assert!(
+            matches!(&block.statements[..], [Statement::Binding(binding)] if binding.name == "soft")
+        );
+
+        let code = "comptime const N = 1; comptime if N {}";
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(err, VmError::NonBoolCondition { found: "integer" });
+
+        let code = "fn f(flag: bool) { comptime if flag {} }";
+        let err = fold_program(&build_ast(code).unwrap()).unwrap_err().error;
+        assert_eq!(
+            err,
+            VmError::NotComptime {
+                name: "flag".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_float_binding_coercion() {
+        let code = "comptime const RATIO: f32 = 3 / 2;";
+        let program = fold_program(&build_ast(code).unwrap()).unwrap();
+        assert_eq!(
+            folded_value(&program, 0),
+            Some(ExpressionKind::Literal(Literal::Numeric(
+                NumericLiteral::Float(1.0)
+            )))
+        );
+    }
+
+    #[test]
+    fn test_mixed_float_and_integer_operands() {
+        let code = r#"
+            comptime const SCALED: f32 = 1.5 * 2;
+            comptime const HALF: f64 = 3 / 2.0;
+            comptime const ABOVE: bool = 2.5 > 2;
+        "#;
+        let program = fold_program(&build_ast(code).unwrap()).unwrap();
+        assert_eq!(
+            folded_value(&program, 0),
+            Some(ExpressionKind::Literal(Literal::Numeric(
+                NumericLiteral::Float(3.0)
+            )))
+        );
+        assert_eq!(
+            folded_value(&program, 1),
+            Some(ExpressionKind::Literal(Literal::Numeric(
+                NumericLiteral::Double(1.5)
+            )))
+        );
+        assert_eq!(
+            folded_value(&program, 2),
+            Some(ExpressionKind::Literal(Literal::Bool(true)))
+        );
+    }
+
+    #[test]
+    fn test_trace_records_dependency_chain() {
+        let code = r#"
+            comptime const CLOCK_HZ: u32 = 16000000;
+            comptime const BAUD_CLOCK: u32 = CLOCK_HZ / 16;
+
+            fn divisor(clock: u32, baud: u32) -> u32 {
+                return clock / baud - 1;
+            }
+
+            fn main() {
+                comptime const BAUD: u32 = 9600;
+                comptime const UBRR: u32 = divisor(BAUD_CLOCK, BAUD);
+                var count: u32 = 0;
+            }
+        "#;
+        let (_, trace) = fold_program_traced(&build_ast(code).unwrap(), Overflow::Error).unwrap();
+
+        let names: Vec<&str> = trace.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["CLOCK_HZ", "BAUD_CLOCK", "BAUD", "UBRR"]);
+
+        let clock = &trace[1];
+        assert_eq!(clock.scope, None);
+        assert_eq!(clock.ty, Some(Type::U32));
+        assert_eq!(clock.depends_on, ["CLOCK_HZ"]);
+
+        // The parameters read inside `divisor` are not dependencies
+        let ubrr = &trace[3];
+        assert_eq!(ubrr.scope.as_deref(), Some("main"));
+        assert_eq!(ubrr.value.as_int(), Some(103));
+        assert_eq!(ubrr.depends_on, ["BAUD_CLOCK", "BAUD", "CLOCK_HZ"]);
+        assert_eq!(
+            &code[ubrr.span.offset..ubrr.span.end()],
+            "divisor(BAUD_CLOCK, BAUD)"
+        );
+    }
+
+    #[test]
+    fn test_resolve_keeps_initializers() {
+        let code = r#"
+            comptime const N: u32 = 2 + 2;
+            const doubled: u32 = N * 2;
+            var buf: [N * 2]u8;
+            comptime if doubled > 4 {
+                const big: bool = true;
+            } else {
+                const big: bool = false;
+            }
+        "#;
+        let program = resolve_program(&build_ast(code).unwrap(), Overflow::Error).unwrap();
+
+        // Bindings keep what was written, even those folding would freeze
+        assert!(matches!(
+            folded_value(&program, 0),
+            Some(ExpressionKind::BinaryExpr { .. })
+        ));
+        assert!(matches!(
+            folded_value(&program, 1),
+            Some(ExpressionKind::BinaryExpr { .. })
+        ));
+        let Statement::Binding(binding) = &program.statements[2] else {
+            panic!("Expected binding");
+        };
+        assert_eq!(binding.ty.as_ref().unwrap().to_string(), "[8]u8");
+        // The selected branch is spliced in as it is when folding
+        assert_eq!(program.statements.len(), 4);
+        assert_eq!(
+            folded_value(&program, 3),
+            Some(ExpressionKind::Literal(Literal::Bool(true)))
+        );
+    }
+}
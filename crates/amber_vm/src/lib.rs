@@ -1,14 +1,14 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+mod error;
+mod eval;
+mod exec;
+mod fold;
+mod substitute;
+mod trace;
+mod value;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use error::{FoldError, VmError};
+pub use eval::Evaluator;
+pub use exec::{MAX_CALL_DEPTH, MAX_STEPS};
+pub use fold::{MAX_UNROLL, fold_program, fold_program_traced, fold_program_with, resolve_program};
+pub use trace::TraceEntry;
+pub use value::{IntType, Overflow, Value};
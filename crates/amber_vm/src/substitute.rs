@@ -0,0 +1,232 @@
+use amber_ast::{
+    Block, ElseBranch, Expression, ExpressionKind, FieldInit, ForIterable, ForLoop, IfElse,
+    Literal, Match, MatchArm, Postfix, Statement, Type, UnaryOp, VariableBinding, WhileLoop,
+};
+
+use crate::fold::pattern_bindings;
+
+/// Copy of `block` with every use of the variable `name` replaced by `value`.
+/// Names that shadow the variable hide it from the statements they cover.
+pub(crate) fn substitute_block(block: &Block, name: &str, value: &Literal) -> Block {
+    let mut statements = Vec::with_capacity(block.statements.len());
+    let mut shadowed = false;
+    for statement in &block.statements {
+        if shadowed {
+            statements.push(statement.clone());
+            continue;
+        }
+        statements.push(substitute_statement(statement, name, value));
+        // Later statements see the new binding instead
+        if let Statement::Binding(binding) = statement {
+            shadowed = binding.name == name;
+        }
+    }
+    Block {
+        statements,
+        span: block.span,
+    }
+}
+
+fn substitute_statement(statement: &Statement, name: &str, value: &Literal) -> Statement {
+    let expr = |expr: &Expression| substitute_expr(expr, name, value);
+    let block = |block: &Block| substitute_block(block, name, value);
+    match statement {
+        Statement::Binding(binding) => Statement::Binding(VariableBinding {
+            ty: binding
+                .ty
+                .as_ref()
+                .map(|ty| substitute_type(ty, name, value)),
+            value: binding.value.as_ref().map(expr),
+            ..binding.clone()
+        }),
+        Statement::IfElse(if_else) => Statement::IfElse(substitute_if_else(if_else, name, value)),
+        Statement::WhileLoop(while_loop) => Statement::WhileLoop(WhileLoop {
+            condition: expr(&while_loop.condition),
+            block: block(&while_loop.block),
+            ..while_loop.clone()
+        }),
+        Statement::ForLoop(for_loop) => Statement::ForLoop(ForLoop {
+            iterable: match &for_loop.iterable {
+                ForIterable::Range { start, end } => ForIterable::Range {
+                    start: expr(start),
+                    end: expr(end),
+                },
+                ForIterable::Array(array) => ForIterable::Array(expr(array)),
+            },
+            block: if for_loop.variable == name {
+                for_loop.block.clone()
+            } else {
+                block(&for_loop.block)
+            },
+            ..for_loop.clone()
+        }),
+        Statement::Match(match_stmt) => Statement::Match(Match {
+            scrutinee: expr(&match_stmt.scrutinee),
+            arms: match_stmt
+                .arms
+                .iter()
+                .map(|arm| {
+                    let binds_name = arm
+                        .patterns
+                        .iter()
+                        .any(|pattern| pattern_bindings(pattern).contains(&name));
+                    MatchArm {
+                        body: if binds_name {
+                            arm.body.clone()
+                        } else {
+                            block(&arm.body)
+                        },
+                        ..arm.clone()
+                    }
+                })
+                .collect(),
+            ..match_stmt.clone()
+        }),
+        Statement::Block(inner) => Statement::Block(block(inner)),
+        Statement::ExprStatement(value) => Statement::ExprStatement(expr(value)),
+        Statement::Assignment {
+            target,
+            value,
+            span,
+        } => Statement::Assignment {
+            target: expr(target),
+            value: expr(value),
+            span: *span,
+        },
+        Statement::Return { value, span } => Statement::Return {
+            value: value.as_ref().map(expr),
+            span: *span,
+        },
+        Statement::Break { .. } | Statement::Continue { .. } => statement.clone(),
+        // Declarations cannot refer to a local variable
+        Statement::Struct(_)
+        | Statement::Enum(_)
+        | Statement::Union(_)
+        | Statement::Function(_)
+        | Statement::Impl(_) => statement.clone(),
+    }
+}
+
+fn substitute_if_else(if_else: &IfElse, name: &str, value: &Literal) -> IfElse {
+    IfElse {
+        modifier: if_else.modifier.clone(),
+        condition: substitute_expr(&if_else.condition, name, value),
+        then_block: substitute_block(&if_else.then_block, name, value),
+        else_branch: if_else.else_branch.as_ref().map(|branch| match branch {
+            ElseBranch::If(next) => ElseBranch::If(Box::new(substitute_if_else(next, name, value))),
+            ElseBranch::Block(block) => ElseBranch::Block(substitute_block(block, name, value)),
+        }),
+        span: if_else.span,
+    }
+}
+
+fn substitute_expr(expr: &Expression, name: &str, value: &Literal) -> Expression {
+    match &expr.kind {
+        ExpressionKind::Identifier(ident) if ident == name => {
+            Expression::new(ExpressionKind::Literal(value.clone()), expr.span)
+        }
+        _ => map_children(expr, &mut |child| substitute_expr(child, name, value)),
+    }
+}
+
+/// Rebuild `expr` with `f` applied to each of its direct subexpressions
+pub(crate) fn map_children(
+    expr: &Expression,
+    f: &mut dyn FnMut(&Expression) -> Expression,
+) -> Expression {
+    let mut fields = |fields: &[FieldInit]| {
+        fields
+            .iter()
+            .map(|field| FieldInit {
+                value: f(&field.value),
+                ..field.clone()
+            })
+            .collect()
+    };
+    let kind = match &expr.kind {
+        ExpressionKind::Literal(_)
+        | ExpressionKind::Identifier(_)
+        | ExpressionKind::Path { .. } => expr.kind.clone(),
+        ExpressionKind::StructLiteral {
+            name,
+            fields: inits,
+        } => ExpressionKind::StructLiteral {
+            name: name.clone(),
+            fields: fields(inits),
+        },
+        ExpressionKind::VariantLiteral {
+            target,
+            variant,
+            fields: inits,
+        } => ExpressionKind::VariantLiteral {
+            target: target.clone(),
+            variant: variant.clone(),
+            fields: fields(inits),
+        },
+        ExpressionKind::UnaryExpr { op, expr } => ExpressionKind::UnaryExpr {
+            op: match op {
+                UnaryOp::PostfixOp(Postfix::Index { index }) => {
+                    UnaryOp::PostfixOp(Postfix::Index {
+                        index: Box::new(f(index)),
+                    })
+                }
+                UnaryOp::PrefixOp(_) => op.clone(),
+            },
+            expr: Box::new(f(expr)),
+        },
+        ExpressionKind::BinaryExpr { left, op, right } => ExpressionKind::BinaryExpr {
+            left: Box::new(f(left)),
+            op: op.clone(),
+            right: Box::new(f(right)),
+        },
+        ExpressionKind::TernaryExpr {
+            condition,
+            then_expr,
+            else_expr,
+        } => ExpressionKind::TernaryExpr {
+            condition: Box::new(f(condition)),
+            then_expr: Box::new(f(then_expr)),
+            else_expr: Box::new(f(else_expr)),
+        },
+        ExpressionKind::Call { callee, args } => ExpressionKind::Call {
+            callee: Box::new(f(callee)),
+            args: args.iter().map(&mut *f).collect(),
+        },
+        ExpressionKind::MethodCall {
+            receiver,
+            method,
+            args,
+        } => ExpressionKind::MethodCall {
+            receiver: Box::new(f(receiver)),
+            method: method.clone(),
+            args: args.iter().map(&mut *f).collect(),
+        },
+        ExpressionKind::FieldAccess { base, field } => ExpressionKind::FieldAccess {
+            base: Box::new(f(base)),
+            field: field.clone(),
+        },
+        ExpressionKind::ArrayLiteral(elements) => {
+            ExpressionKind::ArrayLiteral(elements.iter().map(&mut *f).collect())
+        }
+    };
+    Expression::new(kind, expr.span)
+}
+
+/// Substitute inside the length of `[N]T` array types
+fn substitute_type(ty: &Type, name: &str, value: &Literal) -> Type {
+    match ty {
+        Type::Pointer { inner, is_mut } => Type::Pointer {
+            inner: Box::new(substitute_type(inner, name, value)),
+            is_mut: *is_mut,
+        },
+        Type::Array { inner, len } => Type::Array {
+            inner: Box::new(substitute_type(inner, name, value)),
+            len: *len,
+        },
+        Type::ComptimeArray { inner, len } => Type::ComptimeArray {
+            inner: Box::new(substitute_type(inner, name, value)),
+            len: Box::new(substitute_expr(len, name, value)),
+        },
+        _ => ty.clone(),
+    }
+}
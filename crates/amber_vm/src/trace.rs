@@ -0,0 +1,47 @@
+use amber_ast::{Span, Type};
+
+use crate::value::Value;
+
+/// What the comptime pass computed for one binding it folded
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    pub name: String,
+    /// Function the binding belongs to, e.g. `main` or `Uart::init`, or
+    /// `None` at the top level
+    pub scope: Option<String>,
+    /// Initializer as written in the source
+    pub span: Span,
+    pub value: Value,
+    /// Declared type of the binding, or else the type of its value. `None`
+    /// for an integer whose type is left to analysis.
+    pub ty: Option<Type>,
+    /// Comptime bindings the value was computed from, directly first and
+    /// then through other bindings
+    pub depends_on: Vec<String>,
+}
+
+/// Dependencies of a binding that read `reads`, following each read binding
+/// that is itself in `trace`
+pub(crate) fn dependency_chain(
+    trace: &[TraceEntry],
+    scope: Option<&str>,
+    reads: Vec<String>,
+) -> Vec<String> {
+    let mut chain = reads;
+    let mut next = 0;
+    while next < chain.len() {
+        // The latest binding of that name in the same function or at the top level
+        let entry = trace.iter().rev().find(|entry| {
+            entry.name == chain[next] && (entry.scope.as_deref() == scope || entry.scope.is_none())
+        });
+        if let Some(entry) = entry {
+            for name in &entry.depends_on {
+                if !chain.contains(name) {
+                    chain.push(name.clone());
+                }
+            }
+        }
+        next += 1;
+    }
+    chain
+}
@@ -0,0 +1,380 @@
+use std::fmt;
+
+use amber_ast::{
+    Expression, ExpressionKind, FieldInit, IntSuffix, Literal, NumericLiteral, Span, Type,
+};
+
+use crate::error::VmError;
+
+/// A value produced by compile-time evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// An integer whose type is not known yet, such as a bare literal
+    Int(i128),
+    /// An integer of a fixed-width type, always within that type's range
+    Fixed(i128, IntType),
+    Float(f32),
+    Double(f64),
+    Bool(bool),
+    Char(char),
+    Str(String),
+    /// Null-terminated string, as passed to C
+    CString(String),
+    Array(Vec<Value>),
+    /// An instance of the struct `name`, with its fields in the order written
+    Struct {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+}
+
+/// One step into an aggregate value, e.g. `[i]` or `.field`
+#[derive(Debug, Clone)]
+pub(crate) enum Access {
+    Index(i128),
+    Field(String),
+}
+
+/// A fixed-width integer type, as used by the generated C
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntType {
+    pub(crate) bits: u32,
+    pub(crate) signed: bool,
+}
+
+/// What comptime arithmetic does with a result outside its type's range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Overflow {
+    /// Report the overflow as an error
+    #[default]
+    Error,
+    /// Wrap each result around to its type in two's complement
+    Wrap,
+}
+
+impl IntType {
+    pub fn from_type(ty: &Type) -> Option<Self> {
+        let (bits, signed) = match ty {
+            Type::U8 => (8, false),
+            Type::U16 => (16, false),
+            Type::U32 => (32, false),
+            Type::U64 => (64, false),
+            Type::I8 => (8, true),
+            Type::I16 => (16, true),
+            Type::I32 => (32, true),
+            Type::I64 => (64, true),
+            _ => return None,
+        };
+        Some(Self { bits, signed })
+    }
+
+    pub fn to_type(&self) -> Type {
+        match (self.bits, self.signed) {
+            (8, false) => Type::U8,
+            (16, false) => Type::U16,
+            (32, false) => Type::U32,
+            (64, false) => Type::U64,
+            (8, true) => Type::I8,
+            (16, true) => Type::I16,
+            (32, true) => Type::I32,
+            _ => Type::I64,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match (self.bits, self.signed) {
+            (8, false) => "u8",
+            (16, false) => "u16",
+            (32, false) => "u32",
+            (64, false) => "u64",
+            (8, true) => "i8",
+            (16, true) => "i16",
+            (32, true) => "i32",
+            _ => "i64",
+        }
+    }
+
+    pub fn min(&self) -> i128 {
+        if self.signed {
+            -(1 << (self.bits - 1))
+        } else {
+            0
+        }
+    }
+
+    pub fn max(&self) -> i128 {
+        if self.signed {
+            (1 << (self.bits - 1)) - 1
+        } else {
+            (1 << self.bits) - 1
+        }
+    }
+
+    pub fn contains(&self, value: i128) -> bool {
+        (self.min()..=self.max()).contains(&value)
+    }
+
+    /// Reduce `value` modulo 2^bits into this type's range
+    pub fn wrap(&self, value: i128) -> i128 {
+        let mask = (1i128 << self.bits) - 1;
+        let low = value & mask;
+        if self.signed && low > self.max() {
+            low - (1 << self.bits)
+        } else {
+            low
+        }
+    }
+
+    /// Convert `value` to this type, wrapping or failing when it is out of range
+    pub fn convert(&self, value: i128, overflow: Overflow) -> Result<i128, VmError> {
+        if self.contains(value) {
+            return Ok(value);
+        }
+        match overflow {
+            Overflow::Error => Err(VmError::IntegerOutOfRange {
+                value,
+                ty: self.name(),
+            }),
+            Overflow::Wrap => Ok(self.wrap(value)),
+        }
+    }
+}
+
+impl Value {
+    pub fn from_literal(lit: &Literal) -> Self {
+        match lit {
+            Literal::Numeric(NumericLiteral::Integer(i)) => Value::Int(*i),
+            // A literal out of range is left for analysis to report
+            Literal::Numeric(NumericLiteral::Suffixed(i, suffix)) => {
+                match IntType::from_type(&suffix.ty()) {
+                    Some(ty) if ty.contains(*i) => Value::Fixed(*i, ty),
+                    _ => Value::Int(*i),
+                }
+            }
+            Literal::Numeric(NumericLiteral::Float(f)) => Value::Float(*f),
+            Literal::Numeric(NumericLiteral::Double(d)) => Value::Double(*d),
+            Literal::Bool(b) => Value::Bool(*b),
+            Literal::Char(c) => Value::Char(*c),
+            Literal::Byte(b) => {
+                let ty = IntType {
+                    bits: 8,
+                    signed: false,
+                };
+                Value::Fixed(i128::from(*b), ty)
+            }
+            Literal::String(s) => Value::Str(s.clone()),
+            Literal::CString(s) => Value::CString(s.clone()),
+        }
+    }
+
+    /// The literal for a scalar value, or `None` for an array or struct
+    pub fn to_literal(&self) -> Option<Literal> {
+        Some(match self {
+            Value::Int(i) | Value::Fixed(i, _) => Literal::Numeric(NumericLiteral::Integer(*i)),
+            Value::Float(f) => Literal::Numeric(NumericLiteral::Float(*f)),
+            Value::Double(d) => Literal::Numeric(NumericLiteral::Double(*d)),
+            Value::Bool(b) => Literal::Bool(*b),
+            Value::Char(c) => Literal::Char(*c),
+            Value::Str(s) => Literal::String(s.clone()),
+            Value::CString(s) => Literal::CString(s.clone()),
+            Value::Array(_) | Value::Struct { .. } => return None,
+        })
+    }
+
+    /// Expression that produces the value, with every node at `span`
+    pub fn to_expr(&self, span: Span) -> Expression {
+        self.build_expr(span, false)
+    }
+
+    /// Like `to_expr`, but fixed-width integers keep their type as a literal
+    /// suffix, for an initializer that nothing else gives a type to
+    pub fn to_typed_expr(&self, span: Span) -> Expression {
+        self.build_expr(span, true)
+    }
+
+    fn build_expr(&self, span: Span, typed: bool) -> Expression {
+        let kind = match self {
+            Value::Array(elements) => ExpressionKind::ArrayLiteral(
+                elements
+                    .iter()
+                    .map(|element| element.build_expr(span, typed))
+                    .collect(),
+            ),
+            Value::Struct { name, fields } => ExpressionKind::StructLiteral {
+                name: name.clone(),
+                fields: fields
+                    .iter()
+                    .map(|(name, value)| FieldInit {
+                        name: name.clone(),
+                        value: value.build_expr(span, typed),
+                        span,
+                    })
+                    .collect(),
+            },
+            Value::Fixed(i, ty) if typed => {
+                let suffix = IntSuffix::parse(ty.name()).expect("every IntType has a suffix");
+                ExpressionKind::Literal(Literal::Numeric(NumericLiteral::Suffixed(*i, suffix)))
+            }
+            scalar => ExpressionKind::Literal(scalar.to_literal().expect("scalar value")),
+        };
+        Expression::new(kind, span)
+    }
+
+    /// The integer held by the value, typed or not
+    pub fn as_int(&self) -> Option<i128> {
+        match self {
+            Value::Int(i) | Value::Fixed(i, _) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Type of the value, or `None` for an integer with no type yet
+    pub fn ty(&self) -> Option<Type> {
+        match self {
+            Value::Int(_) => None,
+            Value::Fixed(_, ty) => Some(ty.to_type()),
+            Value::Float(_) => Some(Type::F32),
+            Value::Double(_) => Some(Type::F64),
+            Value::Bool(_) => Some(Type::Bool),
+            Value::Char(_) => Some(Type::Char),
+            Value::Str(_) => Some(Type::Str),
+            Value::CString(_) => Some(Type::Pointer {
+                inner: Box::new(Type::U8),
+                is_mut: false,
+            }),
+            Value::Array(elements) => Some(Type::Array {
+                inner: Box::new(elements.first()?.ty()?),
+                len: elements.len(),
+            }),
+            Value::Struct { name, .. } => Some(Type::Named(name.clone())),
+        }
+    }
+
+    /// Name of the value's kind, used in diagnostics
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "integer",
+            Value::Fixed(_, ty) => ty.name(),
+            Value::Float(_) => "f32",
+            Value::Double(_) => "f64",
+            Value::Bool(_) => "bool",
+            Value::Char(_) => "char",
+            Value::Str(_) => "str",
+            Value::CString(_) => "*u8",
+            Value::Array(_) => "array",
+            Value::Struct { .. } => "struct",
+        }
+    }
+
+    /// The element or field of an aggregate selected by `access`
+    pub(crate) fn get(&self, access: &Access) -> Result<&Value, VmError> {
+        match (self, access) {
+            (Value::Array(elements), Access::Index(index)) => usize::try_from(*index)
+                .ok()
+                .and_then(|i| elements.get(i))
+                .ok_or(VmError::IndexOutOfBounds {
+                    index: *index,
+                    len: elements.len(),
+                }),
+            (Value::Struct { name, fields }, Access::Field(field)) => fields
+                .iter()
+                .find(|(other, _)| other == field)
+                .map(|(_, value)| value)
+                .ok_or_else(|| VmError::UnknownField {
+                    target: name.clone(),
+                    field: field.clone(),
+                }),
+            (value, access) => Err(value.not_accessible(access)),
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, access: &Access) -> Result<&mut Value, VmError> {
+        match (self, access) {
+            (Value::Array(elements), Access::Index(index)) => {
+                let len = elements.len();
+                usize::try_from(*index)
+                    .ok()
+                    .and_then(|i| elements.get_mut(i))
+                    .ok_or(VmError::IndexOutOfBounds { index: *index, len })
+            }
+            (Value::Struct { name, fields }, Access::Field(field)) => {
+                let target = name.clone();
+                fields
+                    .iter_mut()
+                    .find(|(other, _)| other == field)
+                    .map(|(_, value)| value)
+                    .ok_or_else(|| VmError::UnknownField {
+                        target,
+                        field: field.clone(),
+                    })
+            }
+            (value, access) => Err(value.not_accessible(access)),
+        }
+    }
+
+    fn not_accessible(&self, access: &Access) -> VmError {
+        match access {
+            Access::Index(_) => VmError::InvalidUnaryOperand {
+                op: "[]",
+                operand: self.type_name(),
+            },
+            Access::Field(field) => VmError::UnknownField {
+                target: self.type_name().to_string(),
+                field: field.clone(),
+            },
+        }
+    }
+
+    /// Convert a value to the representation of a declared binding type.
+    /// Untyped integers take any integer or float type, wrapping or failing
+    /// according to `overflow` outside the range of an integer type, but an
+    /// integer that has a type must already have the declared one. Floats
+    /// take either float type, as float literals do; any other combination
+    /// is returned unchanged.
+    pub fn coerce_to(self, ty: &Type, overflow: Overflow) -> Result<Self, VmError> {
+        let mismatch = |found: IntType| VmError::TypeMismatch {
+            expected: ty.to_string(),
+            found: found.name(),
+        };
+        if let Some(int_ty) = IntType::from_type(ty) {
+            match self {
+                Value::Int(i) => return Ok(Value::Fixed(int_ty.convert(i, overflow)?, int_ty)),
+                Value::Fixed(_, found) if found != int_ty => return Err(mismatch(found)),
+                _ => {}
+            }
+        }
+        Ok(match (self, ty) {
+            (Value::Array(elements), Type::Array { inner, .. }) => Value::Array(
+                elements
+                    .into_iter()
+                    .map(|element| element.coerce_to(inner, overflow))
+                    .collect::<Result<_, _>>()?,
+            ),
+            (Value::Int(i), Type::F32) => Value::Float(i as f32),
+            (Value::Int(i), Type::F64) => Value::Double(i as f64),
+            (Value::Fixed(_, found), Type::F32 | Type::F64) => return Err(mismatch(found)),
+            (Value::Float(f), Type::F64) => Value::Double(f as f64),
+            (Value::Double(d), Type::F32) => Value::Float(d as f32),
+            (value, _) => value,
+        })
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Array(elements) => {
+                let elements: Vec<String> = elements.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", elements.join(", "))
+            }
+            Value::Struct { name, fields } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect();
+                write!(f, "{} {{ {} }}", name, fields.join(", "))
+            }
+            scalar => write!(f, "{}", scalar.to_literal().expect("scalar value")),
+        }
+    }
+}